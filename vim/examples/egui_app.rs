@@ -275,36 +275,26 @@ impl VimApp {
         let vim_event = convert_egui_event(key, modifiers, &self.engine.snapshot().mode);
 
         if let Some(event) = vim_event {
-            let (new_cursor, commands) =
-                self.engine
-                    .handle_event(&self.buffer, &mut self.clipboard, self.cursor, event);
-
-            for cmd in commands {
-                match &cmd {
-                    Command::SetCursor(pos) => self.cursor = *pos,
-                    Command::SetSelection(sel) => self.selection = *sel,
-                    _ => self.buffer.apply_command(&cmd),
-                }
-            }
-
-            self.cursor = new_cursor;
+            self.send(event);
         }
     }
 
-    fn handle_char_input(&mut self, ch: char) {
-        let mode = self.engine.snapshot().mode;
-        let event = match mode {
-            Mode::Insert | Mode::SearchPrompt => InputEvent::ReceivedChar(ch),
-            _ => InputEvent::Key(KeyEvent {
-                code: KeyCode::Char(ch),
-                mods: Modifiers::empty(),
-            }),
-        };
-
+    fn send(&mut self, event: InputEvent) {
         let (new_cursor, commands) =
             self.engine
                 .handle_event(&self.buffer, &mut self.clipboard, self.cursor, event);
+        self.apply_commands(new_cursor, commands);
 
+        // Replay macro keys one at a time so each sees the updated buffer
+        while let Some((new_cursor, commands)) =
+            self.engine
+                .step_replay(&self.buffer, &mut self.clipboard, self.cursor)
+        {
+            self.apply_commands(new_cursor, commands);
+        }
+    }
+
+    fn apply_commands(&mut self, new_cursor: Position, commands: Vec<Command>) {
         for cmd in commands {
             match &cmd {
                 Command::SetCursor(pos) => self.cursor = *pos,
@@ -314,6 +304,21 @@ impl VimApp {
         }
 
         self.cursor = new_cursor;
+    }
+
+    fn handle_char_input(&mut self, ch: char) {
+        let mode = self.engine.snapshot().mode;
+        let event = match mode {
+            Mode::Insert | Mode::Replace | Mode::SearchPrompt | Mode::CommandLine => {
+                InputEvent::ReceivedChar(ch)
+            }
+            _ => InputEvent::Key(KeyEvent {
+                code: KeyCode::Char(ch),
+                mods: Modifiers::empty(),
+            }),
+        };
+
+        self.send(event);
 
         // Update search query in search mode
         if let Mode::SearchPrompt = mode {
@@ -328,23 +333,46 @@ impl VimApp {
 
 fn convert_egui_event(
     key: egui::Key,
-    _modifiers: egui::Modifiers,
+    modifiers: egui::Modifiers,
     _mode: &Mode,
 ) -> Option<InputEvent> {
     match key {
-        egui::Key::Escape => Some(InputEvent::Key(KeyEvent {
-            code: KeyCode::Esc,
-            mods: Modifiers::empty(),
-        })),
-        egui::Key::Enter => Some(InputEvent::Key(KeyEvent {
-            code: KeyCode::Enter,
-            mods: Modifiers::empty(),
-        })),
-        egui::Key::Backspace => Some(InputEvent::Key(KeyEvent {
-            code: KeyCode::Backspace,
-            mods: Modifiers::empty(),
-        })),
-        _ => None,
+        // Ctrl+letter, e.g. Ctrl-O and Ctrl-I for the jumplist
+        _ if modifiers.ctrl => {
+            let mut name = key.name().chars();
+            match (name.next(), name.next()) {
+                (Some(c), None) if c.is_ascii_alphabetic() => Some(InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char(c.to_ascii_lowercase()),
+                    mods: Modifiers::CTRL,
+                })),
+                _ => None,
+            }
+        }
+        _ => {
+            let code = match key {
+                egui::Key::Escape => KeyCode::Esc,
+                egui::Key::Enter => KeyCode::Enter,
+                egui::Key::Backspace => KeyCode::Backspace,
+                egui::Key::Delete => KeyCode::Delete,
+                egui::Key::Tab => KeyCode::Tab,
+                egui::Key::ArrowUp => KeyCode::Up,
+                egui::Key::ArrowDown => KeyCode::Down,
+                egui::Key::ArrowLeft => KeyCode::Left,
+                egui::Key::ArrowRight => KeyCode::Right,
+                egui::Key::Home => KeyCode::Home,
+                egui::Key::End => KeyCode::End,
+                egui::Key::PageUp => KeyCode::PageUp,
+                egui::Key::PageDown => KeyCode::PageDown,
+                egui::Key::Insert => KeyCode::Insert,
+                _ => return None,
+            };
+            let mods = if modifiers.shift {
+                Modifiers::SHIFT
+            } else {
+                Modifiers::empty()
+            };
+            Some(InputEvent::Key(KeyEvent { code, mods }))
+        }
     }
 }
 
@@ -357,10 +385,19 @@ impl eframe::App for VimApp {
             let mode_text = match self.engine.snapshot().mode {
                 Mode::Normal => "NORMAL",
                 Mode::Insert => "INSERT",
+                Mode::Replace => "REPLACE",
                 Mode::Visual(_) => "VISUAL",
                 Mode::SearchPrompt => &format!("SEARCH: /{}", self.search_query),
+                Mode::CommandLine => &format!(
+                    ":{}",
+                    self.engine.snapshot().command_line.unwrap_or_default()
+                ),
+                Mode::Confirm => "REPLACE? (y/n/a/q/l)",
             };
             ui.label(format!("Mode: {}", mode_text));
+            if let Some(reg) = self.engine.snapshot().recording {
+                ui.label(format!("recording @{}", reg));
+            }
 
             ui.separator();
 
@@ -268,7 +268,9 @@ struct App {
     clipboard: SimpleClipboard,
     cursor: Position,
     selection: Option<Selection>,
+    viewport: Viewport,
     message: String,
+    notice: Option<String>,
     should_quit: bool,
 }
 
@@ -280,46 +282,78 @@ impl App {
             clipboard: SimpleClipboard { content: None },
             cursor: Position::ZERO,
             selection: None,
+            viewport: Viewport::default(),
             message: String::new(),
+            notice: None,
             should_quit: false,
         }
     }
 
     fn handle_crossterm_event(&mut self, event: CKeyEvent) {
-        let vim_event = convert_crossterm_event(event);
+        let mut vim_event = convert_crossterm_event(event);
 
-        // Handle quit command
+        // Typed text arrives as characters on the command line
         if let InputEvent::Key(ke) = &vim_event
-            && self.message == ":q"
-            && ke.code == KeyCode::Enter
+            && let KeyCode::Char(c) = ke.code
+            && !ke.mods.contains(Modifiers::CTRL)
+            && self.engine.snapshot().mode == Mode::CommandLine
         {
-            self.should_quit = true;
-            return;
+            vim_event = InputEvent::ReceivedChar(c);
         }
+        self.notice = None;
+        self.engine.set_viewport(self.viewport);
 
         let (new_cursor, commands) =
             self.engine
                 .handle_event(&self.buffer, &mut self.clipboard, self.cursor, vim_event);
 
-        // Apply commands
-        for cmd in commands {
-            match &cmd {
-                Command::SetCursor(pos) => self.cursor = *pos,
-                Command::SetSelection(sel) => self.selection = *sel,
-                _ => self.buffer.apply_command(&cmd),
-            }
-        }
+        self.apply_commands(new_cursor, commands);
 
-        self.cursor = new_cursor;
+        // Replay macro keys one at a time so each sees the updated buffer
+        while let Some((new_cursor, commands)) =
+            self.engine
+                .step_replay(&self.buffer, &mut self.clipboard, self.cursor)
+        {
+            self.apply_commands(new_cursor, commands);
+        }
 
         // Update message based on mode
         let snapshot = self.engine.snapshot();
         self.message = match snapshot.mode {
             Mode::Normal => "-- NORMAL --".to_string(),
             Mode::Insert => "-- INSERT --".to_string(),
+            Mode::Replace => "-- REPLACE --".to_string(),
             Mode::Visual(_) => "-- VISUAL --".to_string(),
             Mode::SearchPrompt => format!("/{}", self.message.trim_start_matches('/')),
+            Mode::CommandLine => format!(":{}", snapshot.command_line.unwrap_or_default()),
+            Mode::Confirm => "-- CONFIRM --".to_string(),
         };
+        if let Some(notice) = &self.notice {
+            self.message = notice.clone();
+        }
+        if let Some(reg) = snapshot.recording {
+            self.message.push_str(&format!(" recording @{reg}"));
+        }
+    }
+
+    fn apply_commands(&mut self, new_cursor: Position, commands: Vec<Command>) {
+        for cmd in commands {
+            match &cmd {
+                Command::SetCursor(pos) => self.cursor = *pos,
+                Command::SetSelection(sel) => self.selection = *sel,
+                Command::Quit { .. } => self.should_quit = true,
+                Command::ScrollTo(top) => self.viewport.top = *top,
+                Command::Error(message) | Command::Message(message) => {
+                    self.notice = Some(message.clone())
+                }
+                Command::ConfirmSubstitute { replacement, .. } => {
+                    self.notice = Some(format!("replace with {replacement} (y/n/a/q/l)?"))
+                }
+                _ => self.buffer.apply_command(&cmd),
+            }
+        }
+
+        self.cursor = new_cursor;
     }
 }
 
@@ -340,35 +374,56 @@ fn convert_crossterm_event(event: CKeyEvent) -> InputEvent {
                 mods,
             })
         }
-        CKeyCode::Esc => InputEvent::Key(KeyEvent {
-            code: KeyCode::Esc,
-            mods: Modifiers::empty(),
-        }),
-        CKeyCode::Enter => InputEvent::Key(KeyEvent {
-            code: KeyCode::Enter,
-            mods: Modifiers::empty(),
-        }),
-        CKeyCode::Backspace => InputEvent::Key(KeyEvent {
-            code: KeyCode::Backspace,
-            mods: Modifiers::empty(),
-        }),
-        _ => InputEvent::Key(KeyEvent {
-            code: KeyCode::Esc,
-            mods: Modifiers::empty(),
-        }),
+        code => {
+            let code = match code {
+                CKeyCode::Enter => KeyCode::Enter,
+                CKeyCode::Backspace => KeyCode::Backspace,
+                CKeyCode::Delete => KeyCode::Delete,
+                CKeyCode::Tab => KeyCode::Tab,
+                CKeyCode::Up => KeyCode::Up,
+                CKeyCode::Down => KeyCode::Down,
+                CKeyCode::Left => KeyCode::Left,
+                CKeyCode::Right => KeyCode::Right,
+                CKeyCode::Home => KeyCode::Home,
+                CKeyCode::End => KeyCode::End,
+                CKeyCode::PageUp => KeyCode::PageUp,
+                CKeyCode::PageDown => KeyCode::PageDown,
+                CKeyCode::Insert => KeyCode::Insert,
+                CKeyCode::F(n) => KeyCode::F(n),
+                _ => KeyCode::Esc,
+            };
+            let mods = if event.modifiers.contains(KeyModifiers::CONTROL) {
+                Modifiers::CTRL
+            } else if event.modifiers.contains(KeyModifiers::SHIFT) {
+                Modifiers::SHIFT
+            } else {
+                Modifiers::empty()
+            };
+            InputEvent::Key(KeyEvent { code, mods })
+        }
     }
 }
 
-fn ui(f: &mut Frame, app: &App) {
+fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
         .split(f.size());
 
+    // Scroll to keep the cursor on screen
+    let view = &mut app.viewport;
+    view.height = chunks[0].height.saturating_sub(2).max(1) as u32;
+    if app.cursor.line < view.top {
+        view.top = app.cursor.line;
+    } else if app.cursor.line >= view.top + view.height {
+        view.top = app.cursor.line + 1 - view.height;
+    }
+
     // Main text area
     let mut lines = vec![];
-    for i in 0..app.buffer.line_count() {
+    let end = (app.viewport.top + app.viewport.height).min(app.buffer.line_count());
+    for i in app.viewport.top..end {
         let line_text = app.buffer.line_text(i);
         let trimmed = line_text.trim_end_matches('\n').to_string();
 
@@ -403,7 +458,7 @@ fn ui(f: &mut Frame, app: &App) {
     // Set cursor position
     f.set_cursor(
         chunks[0].x + 1 + app.cursor.col as u16,
-        chunks[0].y + 1 + app.cursor.line as u16,
+        chunks[0].y + 1 + (app.cursor.line - app.viewport.top) as u16,
     );
 }
 
@@ -418,7 +473,7 @@ fn main() -> Result<(), io::Error> {
     let mut app = App::new();
 
     loop {
-        terminal.draw(|f| ui(f, &app))?;
+        terminal.draw(|f| ui(f, &mut app))?;
 
         if let Event::Key(key) = event::read()? {
             if key.code == CKeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -0,0 +1,340 @@
+//! Visual block mode.
+//!
+//! A block is a rectangle of screen columns, so it stays straight on screen
+//! when lines hold tabs or wide characters. A tab or wide character cut by
+//! an edge of the block is split into spaces, as in Vim: the part outside
+//! the block stays as blanks and the part inside counts as blanks too.
+
+use crate::scan;
+use crate::traits::TextOps;
+use crate::types::{Command, Position, Range, Selection, VisualKind};
+use unicode_segmentation::UnicodeSegmentation;
+
+fn spaces(n: u32) -> String {
+    " ".repeat(n as usize)
+}
+
+/// The screen width of `s` when it starts at column 0.
+fn str_width(s: &str, tabstop: u32) -> u32 {
+    s.graphemes(true)
+        .fold(0, |col, g| col + scan::display_width(g, col, tabstop))
+}
+
+/// The screen columns taken by the grapheme at `pos`. Past the end of the
+/// line, as on an empty line, that is one column.
+fn cells<T: TextOps>(text: &T, pos: Position, tabstop: u32) -> (u32, u32) {
+    let graphemes = scan::line_graphemes(text, pos.line);
+    let start = scan::display_col(text, pos, tabstop);
+    let width = graphemes
+        .get(pos.col as usize)
+        .map_or(1, |g| scan::display_width(g, start, tabstop));
+    (start, start + width - 1)
+}
+
+/// A rectangle of screen columns over a run of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Block {
+    pub(crate) top: u32,
+    pub(crate) bottom: u32,
+    pub(crate) left: u32,
+    pub(crate) right: u32, // inclusive; u32::MAX reaches the end of every line
+}
+
+impl Block {
+    /// The block with corners at `anchor` and `cursor`, covering both
+    /// characters whole. With `to_eol` it reaches the end of every line.
+    pub(crate) fn new<T: TextOps>(
+        text: &T,
+        anchor: Position,
+        cursor: Position,
+        tabstop: u32,
+        to_eol: bool,
+    ) -> Self {
+        let anchor_cells = cells(text, anchor, tabstop);
+        let cursor_cells = cells(text, cursor, tabstop);
+        Block {
+            top: anchor.line.min(cursor.line),
+            bottom: anchor.line.max(cursor.line),
+            left: anchor_cells.0.min(cursor_cells.0),
+            right: if to_eol {
+                u32::MAX
+            } else {
+                anchor_cells.1.max(cursor_cells.1)
+            },
+        }
+    }
+
+    /// The selection shown for the block, in screen columns.
+    pub(crate) fn selection(&self) -> Selection {
+        Selection {
+            start: Position {
+                line: self.top,
+                col: self.left,
+            },
+            end: Position {
+                line: self.bottom,
+                col: self.right,
+            },
+            kind: VisualKind::Block,
+        }
+    }
+
+    /// The part of each line of the block, from the top.
+    pub(crate) fn parts<T: TextOps>(&self, text: &T, tabstop: u32) -> Vec<Part> {
+        (self.top..=self.bottom)
+            .map(|line| self.part(text, line, tabstop))
+            .collect()
+    }
+
+    fn part<T: TextOps>(&self, text: &T, line: u32, tabstop: u32) -> Part {
+        let graphemes = scan::line_graphemes(text, line);
+        let len = graphemes.len() as u32;
+        let mut part = Part {
+            line,
+            start: len,
+            end: len,
+            text: String::new(),
+            before: 0,
+            after: 0,
+            cells: 0,
+            short: true,
+        };
+        let mut vcol = 0;
+        for (col, g) in graphemes.iter().enumerate() {
+            let width = scan::display_width(g, vcol, tabstop);
+            let last = vcol + width - 1;
+            if last >= self.left && vcol <= self.right {
+                let before = self.left.saturating_sub(vcol);
+                let after = last.saturating_sub(self.right);
+                if part.short {
+                    part.short = false;
+                    part.start = col as u32;
+                    part.before = before;
+                }
+                part.end = col as u32 + 1;
+                part.after = after;
+                part.cells += width - before - after;
+                if before + after == 0 {
+                    part.text.push_str(g);
+                } else {
+                    part.text.push_str(&spaces(width - before - after));
+                }
+            }
+            vcol += width;
+        }
+        part.short = vcol <= self.left;
+        part
+    }
+}
+
+/// The graphemes of one line that fall in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Part {
+    pub(crate) line: u32,
+    pub(crate) start: u32,   // first grapheme in the block
+    pub(crate) end: u32,     // grapheme after the last one in the block
+    pub(crate) text: String, // the text in the block, split graphemes as blanks
+    pub(crate) before: u32,  // columns of a split grapheme left of the block
+    pub(crate) after: u32,   // columns of a split grapheme right of the block
+    pub(crate) cells: u32,   // screen columns of the line inside the block
+    pub(crate) short: bool,  // the line ends before the block starts
+}
+
+impl Part {
+    /// Where text inserted at the left edge of the block goes once the part
+    /// has been deleted with [`delete`](Self::delete).
+    pub(crate) fn insert_pos(&self) -> Position {
+        Position {
+            line: self.line,
+            col: self.start + self.before,
+        }
+    }
+
+    /// The length of the line once the part has been deleted.
+    pub(crate) fn len_after_delete<T: TextOps>(&self, text: &T) -> u32 {
+        text.line_len(self.line) - (self.end - self.start) + self.before + self.after
+    }
+
+    /// Delete the part, keeping the columns of split graphemes outside the
+    /// block as blanks.
+    pub(crate) fn delete(&self) -> Vec<Command> {
+        let start = Position {
+            line: self.line,
+            col: self.start,
+        };
+        let mut cmds = Vec::new();
+        if self.start < self.end {
+            cmds.push(Command::Delete {
+                range: Range {
+                    start,
+                    end: Position {
+                        line: self.line,
+                        col: self.end,
+                    },
+                },
+            });
+        }
+        if self.before + self.after > 0 {
+            cmds.push(Command::InsertText {
+                at: start,
+                text: spaces(self.before + self.after),
+            });
+        }
+        cmds
+    }
+
+    /// Replace every screen column of the part with `ch` (`r` in block mode).
+    pub(crate) fn replace(&self, ch: char, tabstop: u32) -> Vec<Command> {
+        let width = str_width(ch.encode_utf8(&mut [0; 4]), tabstop);
+        let mut cmds = self.delete();
+        let mut filled = ch.to_string().repeat((self.cells / width) as usize);
+        filled.push_str(&spaces(self.cells % width));
+        cmds.push(Command::InsertText {
+            at: self.insert_pos(),
+            text: filled,
+        });
+        cmds
+    }
+
+    /// Where a row of a put block goes once the part has been deleted.
+    pub(crate) fn slot<T: TextOps>(&self, text: &T, left: u32, tabstop: u32) -> Slot {
+        if self.short {
+            let width = scan::display_col(text, self.insert_pos(), tabstop);
+            return Slot {
+                before: left.saturating_sub(width),
+                ..Slot::new(self.insert_pos())
+            };
+        }
+        Slot {
+            follows: self.after > 0 || self.end < text.line_len(self.line),
+            ..Slot::new(self.insert_pos())
+        }
+    }
+}
+
+/// Where one row of a block is put.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Slot {
+    pos: Position,
+    before: u32,    // blanks before the row
+    after: u32,     // blanks after the row
+    split: bool,    // the grapheme at `pos` straddles the column and is replaced
+    follows: bool,  // text follows the row, so it is padded to the block width
+    new_line: bool, // past the last line of the buffer
+}
+
+impl Slot {
+    fn new(pos: Position) -> Self {
+        Slot {
+            pos,
+            before: 0,
+            after: 0,
+            split: false,
+            follows: false,
+            new_line: false,
+        }
+    }
+
+    /// Where the row starts once it is put.
+    pub(crate) fn start(&self) -> Position {
+        Position {
+            line: self.pos.line,
+            col: self.pos.col + self.before,
+        }
+    }
+
+    /// The slot for a row starting at screen column `vcol` of `line`.
+    pub(crate) fn at<T: TextOps>(text: &T, line: u32, vcol: u32, tabstop: u32) -> Self {
+        if line >= text.line_count() {
+            return Slot {
+                before: vcol,
+                new_line: true,
+                ..Slot::new(Position { line, col: 0 })
+            };
+        }
+        let mut start = 0;
+        for (col, g) in scan::line_graphemes(text, line).iter().enumerate() {
+            let width = scan::display_width(g, start, tabstop);
+            if start + width > vcol {
+                return Slot {
+                    pos: Position {
+                        line,
+                        col: col as u32,
+                    },
+                    before: vcol - start,
+                    after: if start < vcol {
+                        start + width - vcol
+                    } else {
+                        0
+                    },
+                    split: start < vcol,
+                    follows: true,
+                    new_line: false,
+                };
+            }
+            start += width;
+        }
+        Slot {
+            before: vcol - start,
+            ..Slot::new(Position {
+                line,
+                col: text.line_len(line),
+            })
+        }
+    }
+}
+
+/// Put `rows` as a block, row `i` at `slots[i]` and `count` times side by
+/// side. Rows followed by text are padded to the width of the widest one.
+///
+/// Rows past the last line are added as new lines at `end`, the end of the
+/// buffer. They come first, so the other rows do not move it.
+pub(crate) fn put(
+    end: Position,
+    slots: &[Slot],
+    rows: &[&str],
+    count: u32,
+    tabstop: u32,
+) -> Vec<Command> {
+    let width = rows.iter().map(|row| str_width(row, tabstop)).max();
+    let width = width.unwrap_or(0);
+    let mut cmds = Vec::new();
+    let mut appended = String::new();
+    for (slot, row) in slots.iter().zip(rows) {
+        let padded = format!("{row}{}", spaces(width - str_width(row, tabstop)));
+        let mut piece = spaces(slot.before);
+        piece.push_str(&padded.repeat(count as usize - 1));
+        piece.push_str(if slot.follows { &padded } else { row });
+        piece.push_str(&spaces(slot.after));
+        if slot.new_line {
+            appended.push('\n');
+            appended.push_str(&piece);
+            continue;
+        }
+        if slot.split {
+            cmds.push(Command::Delete {
+                range: Range {
+                    start: slot.pos,
+                    end: Position {
+                        line: slot.pos.line,
+                        col: slot.pos.col + 1,
+                    },
+                },
+            });
+        }
+        cmds.push(Command::InsertText {
+            at: slot.pos,
+            text: piece,
+        });
+    }
+    if !appended.is_empty() {
+        cmds.insert(
+            0,
+            Command::InsertText {
+                at: end,
+                text: appended,
+            },
+        );
+    }
+    cmds
+}
@@ -0,0 +1,52 @@
+//! Changing the case of text (`~`, `g~`, `gu`, `gU`) and rot13 (`g?`).
+
+/// How to change the letters of a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Case {
+    Toggle,
+    Lower,
+    Upper,
+    Rot13,
+}
+
+impl Case {
+    /// The change made by a key: `~`, `u`, `U` or `?`.
+    pub(crate) fn from_key(key: char) -> Option<Case> {
+        match key {
+            '~' => Some(Case::Toggle),
+            'u' => Some(Case::Lower),
+            'U' => Some(Case::Upper),
+            '?' => Some(Case::Rot13),
+            _ => None,
+        }
+    }
+}
+
+/// Rotate an ASCII letter 13 places; other characters stay.
+fn rot13(ch: char) -> char {
+    match ch {
+        'a'..='z' => (b'a' + (ch as u8 - b'a' + 13) % 26) as char,
+        'A'..='Z' => (b'A' + (ch as u8 - b'A' + 13) % 26) as char,
+        _ => ch,
+    }
+}
+
+/// `text` with its letters changed. The full Unicode case mappings are
+/// used, so one character may become several, as `ß` becomes `SS`.
+pub(crate) fn convert(text: &str, case: Case) -> String {
+    match case {
+        Case::Lower => text.to_lowercase(),
+        Case::Upper => text.to_uppercase(),
+        Case::Toggle => text
+            .chars()
+            .map(|ch| {
+                if ch.is_lowercase() {
+                    ch.to_uppercase().collect()
+                } else {
+                    ch.to_lowercase().collect::<String>()
+                }
+            })
+            .collect(),
+        Case::Rot13 => text.chars().map(rot13).collect(),
+    }
+}
@@ -1,6 +1,29 @@
-use crate::key::{InputEvent, KeyCode};
+use crate::block::{self, Block, Part, Slot};
+use crate::case::{self, Case};
+use crate::ex::{self, Builtin, ExName};
+use crate::indent;
+use crate::insert;
+use crate::join::{self, Spacing};
+use crate::key::{self, InputEvent, KeyCode, KeyEvent, Modifiers};
+use crate::marks::{self, Marks};
+use crate::options::Options;
+use crate::pattern::Pattern;
+use crate::prompt;
+use crate::registers::{self, Register, RegisterKind, Registers};
+use crate::replace;
+use crate::scan;
+use crate::scroll;
+use crate::substitute::{self, LineText, Replacement, Substitute};
+use crate::textobj;
 use crate::traits::{Clipboard, TextOps};
-use crate::types::{Command, Mode, Position, Range, Selection, VisualKind};
+use crate::types::{
+    Command, ExCommand, LineRange, Mode, Position, Range, Selection, Viewport, VisualKind,
+};
+#[cfg(feature = "undo")]
+use crate::undo::UndoHistory;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
 use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, Default, Clone)]
@@ -27,16 +50,127 @@ impl Counts {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PendingKey {
     None,
-    G,                  // for 'gg' sequence
-    D,                  // for 'dd' sequence
-    Y,                  // for 'yy' sequence
-    F { before: bool }, // for 'f' and 't' find character motions
+    G,                                  // for 'gg' sequence
+    D,                                  // for 'dd' sequence
+    Y,                                  // for 'yy' sequence
+    C,                                  // for 'cc' sequence
+    ShiftRight,                         // for '>>' sequence
+    ShiftLeft,                          // for '<<' sequence
+    Indent,                             // for '==' sequence
+    Case(char),                         // for 'g~~', 'guu', 'gUU', 'g??' and '~~' with 'tildeop'
+    Register,                           // for '"' register selection
+    Record,                             // for 'q' macro recording
+    Execute,                            // for '@' macro execution
+    F { before: bool, backward: bool }, // for 'f', 't', 'F' and 'T' find character motions
+    SetMark,                            // for 'm' mark setting
+    Mark { exact: bool },               // for '\'' and '`' mark motions
+    Object { around: bool },            // for 'i' and 'a' text objects
+    Replace,                            // for 'r'
+    Z,                                  // for 'zt', 'zz' and 'zb'
+    Bracket { forward: bool },          // for '[(', '[{', '])' and ']}'
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operator {
     Delete,
-    Yank, // skeleton; fully implemented later
+    Yank,
+    Change,
+    ShiftRight,
+    ShiftLeft,
+    Indent,
+    Case(Case),
+}
+
+impl Operator {
+    /// Operators that always act on whole lines.
+    fn on_lines(self) -> bool {
+        matches!(
+            self,
+            Operator::ShiftRight | Operator::ShiftLeft | Operator::Indent
+        )
+    }
+}
+
+type IndentFnBox = Arc<dyn Fn(&dyn TextOps, u32) -> Option<u32> + Send + Sync>;
+
+/// The indent function of the host, for `=`; see [`EngineBuilder::indent_fn`].
+#[derive(Clone)]
+struct IndentFn(IndentFnBox);
+
+impl fmt::Debug for IndentFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IndentFn")
+    }
+}
+
+/// Size of the visual area a change was made over, used to repeat it with `.`.
+#[derive(Debug, Clone, Copy)]
+struct VisualExtent {
+    kind: VisualKind,
+    lines: u32, // number of lines below the start
+    cols: u32,  // columns covered when single-line, else end column of the last line
+    eol: bool,  // a block reaching the end of every line (`$`)
+}
+
+/// How the last visual area was selected, for `gv`. Its corners are the
+/// `<` and `>` marks.
+#[derive(Debug, Clone, Copy)]
+struct LastVisual {
+    kind: VisualKind,
+    cursor_at_start: bool, // the cursor was on the `<` corner
+    eol: bool,             // a block reaching the end of every line (`$`)
+}
+
+/// A character search in the line with `f`, `t`, `F` or `T`, for `;` and `,`.
+#[derive(Debug, Clone, Copy)]
+struct Find {
+    ch: char,
+    before: bool,   // 't' or 'T': stop next to the character
+    backward: bool, // 'F' or 'T'
+}
+
+/// The last buffer change, as needed by dot-repeat.
+#[derive(Debug, Clone)]
+struct LastChange {
+    keys: Vec<InputEvent>,        // keys of the command, without count digits
+    count: Option<u32>,           // count the command was given
+    visual: Option<VisualExtent>, // set when the change was made in visual mode
+    inserted: Option<String>,     // text typed in the insert session that followed
+}
+
+impl LastChange {
+    /// Move a put from a numbered register on to the next one, so repeating
+    /// `"1p` puts registers 2, 3 and so on.
+    fn next_numbered_put(&mut self) {
+        let chars: Vec<Option<char>> = self
+            .keys
+            .iter()
+            .map(|key| match key {
+                InputEvent::Key(ke) if ke.mods.is_empty() => match ke.code {
+                    KeyCode::Char(c) => Some(c),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        let put = matches!(
+            chars[2.min(chars.len())..],
+            [Some('p' | 'P')] | [Some('g'), Some('p' | 'P')]
+        );
+        if let [Some('"'), Some(name @ '1'..='8'), ..] = chars[..]
+            && put
+            && let InputEvent::Key(ke) = &mut self.keys[1]
+        {
+            ke.code = KeyCode::Char((name as u8 + 1) as char);
+        }
+    }
+}
+
+/// A key waiting to be replayed by `@{reg}`.
+#[derive(Debug, Clone)]
+enum ReplayKey {
+    Event(InputEvent), // recorded with `q`
+    Text(char),        // register text, decoded for the mode it is replayed in
 }
 
 #[derive(Debug, Clone)]
@@ -45,6 +179,21 @@ struct SearchState {
     last_dir_forward: bool,
 }
 
+/// An `:s///c` waiting for an answer about its current match.
+#[derive(Debug, Clone)]
+struct ConfirmState {
+    pattern: Pattern,
+    template: String,
+    global: bool,
+    line: u32,                // line of the current match
+    last_line: u32,           // last line of the range
+    text: LineText,           // text of `line`
+    current: Replacement,     // the match being asked about
+    substitutions: u32,       // substitutions made so far
+    lines: u32,               // lines changed so far
+    cursor: Option<Position>, // where the cursor goes when done
+}
+
 /// The main vim engine that processes input events and maintains modal state.
 ///
 /// The Engine is the core of the vim_mini library. It maintains the current mode,
@@ -80,10 +229,42 @@ pub struct Engine {
     counts: Counts,
     pending: PendingKey,
     op_pending: Option<Operator>,
-    visual_anchor: Option<Position>,  // when in Visual mode
-    last_yank_was_line: bool,         // track if last yank was linewise for paste behavior
-    search: Option<SearchState>,      // current search prompt state
+    visual_anchor: Option<Position>, // when in Visual mode
+    block_eol: bool,                 // the visual block reaches the end of every line ('$')
+    last_visual: Option<LastVisual>, // for 'gv'
+    registers: Registers,
+    register: Option<char>, // register selected with '"' for the next command
+    search: Option<SearchState>, // current search prompt state
     last_search: Option<SearchState>, // last confirmed search
+    command_line: Option<String>, // text typed after ':'
+    prompt_col: usize,      // cursor in the text typed after ':' or '/', in graphemes
+    ex_commands: Vec<ExName>, // ex commands registered by the host
+    marks: Marks,
+    options: Options,
+    indent_fn: Option<IndentFn>,           // for '='
+    last_substitute: Option<Substitute>,   // for ':s' without a pattern, ':&' and 'g&'
+    confirm: Option<ConfirmState>,         // ':s///c' in progress
+    change_keys: Vec<InputEvent>,          // keys of the command being typed
+    last_change: Option<LastChange>,       // last change, for dot-repeat
+    insert_change: Option<LastChange>,     // change waiting for its insert session to end
+    inserted: String,                      // text typed in the current insert session
+    replace_origins: Vec<replace::Origin>, // what replace mode overwrote, for Backspace
+    replace_copies: u32,                   // more copies of the text for 'R' with a count
+    insert_start: Option<Position>,        // where the current insert session started
+    block_insert: Vec<(Position, String)>, // other lines of a block 'I', 'A' or 'c', with padding
+    open_copies: Option<(u32, String)>, // more lines for 'o' or 'O' with a count, and their indent
+    auto_indent: Option<Position>,      // end of an indent nothing has been typed after yet
+    recording: Option<char>,            // register a macro is being recorded into
+    recorded: Vec<InputEvent>,          // keys of the macro being recorded
+    replay: VecDeque<ReplayKey>,        // keys queued by '@'
+    last_macro: Option<char>,           // register last executed with '@', for '@@'
+    last_find: Option<Find>,            // for ';' and ','
+    viewport: Option<Viewport>,         // lines the host shows, for 'H', 'M', 'L' and scrolling
+    scroll_lines: u32, // lines Ctrl-D and Ctrl-U move, set by a count; 0 for half the screen
+    #[cfg(feature = "undo")]
+    history: UndoHistory,
+    #[cfg(feature = "undo")]
+    undoing: bool, // the commands being emitted are an undo or redo
 }
 
 /// A snapshot of the engine's current state.
@@ -97,6 +278,13 @@ pub struct EngineSnapshot {
     pub preferred_col: Option<u32>,
     /// Any pending count entered before a command.
     pub pending_count: Option<u32>,
+    /// The register a macro is being recorded into, if any.
+    pub recording: Option<char>,
+    /// The text typed after `:` in command-line mode.
+    pub command_line: Option<String>,
+    /// Where the cursor is in the text typed after `:` or `/`, in
+    /// graphemes.
+    pub prompt_cursor: Option<usize>,
 }
 
 /// Builder for creating an Engine with custom initial state.
@@ -112,11 +300,19 @@ pub struct EngineSnapshot {
 /// ```
 pub struct EngineBuilder {
     mode: Mode,
+    ex_commands: Vec<ExName>,
+    options: Options,
+    indent_fn: Option<IndentFn>,
 }
 
 impl Default for EngineBuilder {
     fn default() -> Self {
-        Self { mode: Mode::Normal }
+        Self {
+            mode: Mode::Normal,
+            ex_commands: Vec::new(),
+            options: Options::default(),
+            indent_fn: None,
+        }
     }
 }
 
@@ -127,6 +323,28 @@ impl EngineBuilder {
         self
     }
 
+    /// Register an ex command the host implements; see
+    /// [`Engine::register_ex_command`].
+    pub fn ex_command(mut self, spec: &str) -> Self {
+        add_ex_command(&mut self.ex_commands, spec);
+        self
+    }
+
+    /// Set the editor options, such as `tabstop` and `expandtab`.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the function `=` indents lines with; see [`Engine::set_indent_fn`].
+    pub fn indent_fn(
+        mut self,
+        f: impl Fn(&dyn TextOps, u32) -> Option<u32> + Send + Sync + 'static,
+    ) -> Self {
+        self.indent_fn = Some(IndentFn(Arc::new(f)));
+        self
+    }
+
     /// Build the Engine with the configured settings.
     pub fn build(self) -> Engine {
         Engine {
@@ -136,9 +354,41 @@ impl EngineBuilder {
             pending: PendingKey::None,
             op_pending: None,
             visual_anchor: None,
-            last_yank_was_line: false,
+            block_eol: false,
+            last_visual: None,
+            registers: Registers::default(),
+            register: None,
             search: None,
             last_search: None,
+            command_line: None,
+            prompt_col: 0,
+            ex_commands: self.ex_commands,
+            marks: Marks::default(),
+            options: self.options,
+            indent_fn: self.indent_fn,
+            last_substitute: None,
+            confirm: None,
+            change_keys: Vec::new(),
+            last_change: None,
+            insert_change: None,
+            inserted: String::new(),
+            replace_origins: Vec::new(),
+            replace_copies: 0,
+            insert_start: None,
+            block_insert: Vec::new(),
+            open_copies: None,
+            auto_indent: None,
+            recording: None,
+            recorded: Vec::new(),
+            replay: VecDeque::new(),
+            last_macro: None,
+            last_find: None,
+            viewport: None,
+            scroll_lines: 0,
+            #[cfg(feature = "undo")]
+            history: UndoHistory::new(),
+            #[cfg(feature = "undo")]
+            undoing: false,
         }
     }
 }
@@ -161,9 +411,124 @@ impl Engine {
             mode: self.mode,
             preferred_col: self.preferred_col,
             pending_count: self.counts.current,
+            recording: self.recording,
+            command_line: self.command_line.clone(),
+            prompt_cursor: matches!(self.mode, Mode::CommandLine | Mode::SearchPrompt)
+                .then_some(self.prompt_col),
         }
     }
 
+    /// Register an ex command the host implements.
+    ///
+    /// Running it emits [`Command::Ex`] with the full name, the resolved
+    /// range, the bang and the arguments. `spec` is the name, optionally
+    /// with the part that may be left out in brackets as Vim writes it:
+    /// `"Fo[rmat]"` accepts `:Fo` through `:Format`. Without brackets any
+    /// unambiguous prefix is accepted. Built-in commands (`:w`, `:q`, `:wq`,
+    /// `:x`, `:e`, `:d`, `:y`) take precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vim_mini::EngineBuilder;
+    ///
+    /// let mut engine = EngineBuilder::default().ex_command("Fo[rmat]").build();
+    /// engine.register_ex_command("make");
+    /// ```
+    pub fn register_ex_command(&mut self, spec: &str) {
+        add_ex_command(&mut self.ex_commands, spec);
+    }
+
+    /// The engine's registers.
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Mutable access to the registers, e.g. to preload named registers.
+    pub fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.registers
+    }
+
+    /// The engine's marks.
+    pub fn marks(&self) -> &Marks {
+        &self.marks
+    }
+
+    /// Mutable access to the marks, e.g. to move them after an edit the
+    /// host made itself; see [`Marks::adjust`].
+    pub fn marks_mut(&mut self) -> &mut Marks {
+        &mut self.marks
+    }
+
+    /// The editor options.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Mutable access to the editor options, e.g. for the host's `:set`.
+    pub fn options_mut(&mut self) -> &mut Options {
+        &mut self.options
+    }
+
+    /// Tell the engine which lines the host shows. `H`, `M`, `L`, `zt`,
+    /// `zz`, `zb` and the scrolling keys (Ctrl-D, Ctrl-U, Ctrl-F, Ctrl-B,
+    /// Ctrl-E and Ctrl-Y) need it and fail without one. Call it before
+    /// [`handle_event`](Self::handle_event) when the view was scrolled or
+    /// resized; the engine moves `top` itself when it emits
+    /// [`Command::ScrollTo`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vim_mini::{Engine, Viewport};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_viewport(Viewport { top: 0, height: 40 });
+    /// ```
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = Some(viewport);
+    }
+
+    /// The lines the host shows, as last set or scrolled by the engine.
+    pub fn viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// Set the function the `=` operator indents lines with, as Vim's
+    /// `indentexpr`. It gets the text and a line number and returns the
+    /// indent for the line in screen columns, or `None` to leave it. It sees
+    /// the text before any of the lines are changed. Without a function `=`
+    /// leaves the indent alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vim_mini::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    /// // Indent every line by four columns
+    /// engine.set_indent_fn(|_, _| Some(4));
+    /// ```
+    pub fn set_indent_fn(
+        &mut self,
+        f: impl Fn(&dyn TextOps, u32) -> Option<u32> + Send + Sync + 'static,
+    ) {
+        self.indent_fn = Some(IndentFn(Arc::new(f)));
+    }
+
+    /// The engine's undo history.
+    #[cfg(feature = "undo")]
+    pub fn undo_history(&self) -> &UndoHistory {
+        &self.history
+    }
+
+    /// Mutable access to the undo history, e.g. to clear it when the host
+    /// loads another file.
+    #[cfg(feature = "undo")]
+    pub fn undo_history_mut(&mut self) -> &mut UndoHistory {
+        &mut self.history
+    }
+
     fn clear_pending(&mut self) {
         self.pending = PendingKey::None;
     }
@@ -184,14 +549,41 @@ impl Engine {
         vec![Command::Delete { range }]
     }
 
-    fn yank_range<T: TextOps, C: Clipboard>(
+    /// Store text in the register selected with `"`, or the default ones.
+    fn write_register<C: Clipboard>(
         &mut self,
-        text: &T,
         clipboard: &mut C,
+        value: Register,
+        is_delete: bool,
+    ) {
+        match self.register.take() {
+            Some(name) if registers::is_clipboard_register(name) => {
+                clipboard.set(value.text.clone());
+                self.registers.set_unnamed(value);
+            }
+            // Read-only registers cannot be written
+            Some('.' | '/' | ':') => {}
+            name if is_delete => self.registers.record_delete(name, value),
+            name => self.registers.record_yank(name, value),
+        }
+    }
+
+    /// Read the register selected with `"`, or the unnamed register.
+    fn read_register<C: Clipboard>(&mut self, clipboard: &mut C) -> Option<Register> {
+        match self.register.take().unwrap_or('"') {
+            name if registers::is_clipboard_register(name) => {
+                clipboard.get().map(Register::from_external)
+            }
+            name => self.registers.get(name).cloned(),
+        }
+    }
+
+    fn register_value<T: TextOps>(
+        text: &T,
         start: Position,
         end: Position,
         is_line: bool,
-    ) {
+    ) -> Register {
         let range = if start <= end {
             Range { start, end }
         } else {
@@ -200,152 +592,2764 @@ impl Engine {
                 end: start,
             }
         };
-        let text_content = text.slice_to_string(range);
-        clipboard.set(text_content);
-        self.last_yank_was_line = is_line;
+        let content = text.slice_to_string(range);
+        if is_line {
+            Register::linewise(content)
+        } else {
+            Register::charwise(content)
+        }
     }
 
-    /// Process an input event and return the new cursor position and commands.
-    ///
-    /// This is the main entry point for processing vim input. It takes the current
-    /// text buffer state, clipboard, cursor position, and an input event, then
-    /// returns the new cursor position and a list of commands to apply.
-    ///
-    /// # Arguments
-    ///
-    /// * `text` - The text buffer implementing TextOps
-    /// * `clipboard` - The clipboard for yank/paste operations
-    /// * `cursor` - The current cursor position
-    /// * `input` - The input event to process
-    ///
-    /// # Returns
-    ///
-    /// A tuple of (new_cursor_position, commands_to_apply)
-    pub fn handle_event<T: TextOps, C: Clipboard>(
+    fn yank_range<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        start: Position,
+        end: Position,
+        is_line: bool,
+    ) {
+        let value = Self::register_value(text, start, end, is_line);
+        self.write_register(clipboard, value, false);
+        let (start, end) = (start.min(end), start.max(end));
+        // '] is on the last character yanked, the end of the line for lines
+        let last = if end.col == 0 && end.line > start.line {
+            let line = end.line - 1;
+            Position {
+                line,
+                col: text.line_len(line).saturating_sub(1),
+            }
+        } else {
+            Position {
+                line: end.line,
+                col: end.col.saturating_sub(1).max(start.col),
+            }
+        };
+        self.marks.set('[', start);
+        self.marks.set(']', last);
+    }
+
+    /// Delete [start, end), saving the text in the registers first.
+    fn delete_range<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        start: Position,
+        end: Position,
+        is_line: bool,
+    ) -> Vec<Command> {
+        let value = Self::register_value(text, start, end, is_line);
+        self.write_register(clipboard, value, true);
+        self.apply_delete(start, end)
+    }
+
+    /// Apply a characterwise operator over [start, end) and return the new cursor.
+    fn apply_operator<T: TextOps, C: Clipboard>(
+        &mut self,
+        op: Operator,
+        text: &T,
+        clipboard: &mut C,
+        start: Position,
+        end: Position,
+    ) -> (Position, Vec<Command>) {
+        let new_cursor = start.min(end);
+        let cmds = match op {
+            Operator::Delete => self.delete_range(text, clipboard, start, end, false),
+            Operator::Yank => {
+                self.yank_range(text, clipboard, start, end, false);
+                vec![]
+            }
+            Operator::Change => {
+                self.mode = Mode::Insert;
+                if start == end {
+                    self.register = None;
+                    vec![]
+                } else {
+                    self.delete_range(text, clipboard, start, end, false)
+                }
+            }
+            Operator::Case(case) => {
+                let ranges = Self::line_ranges(text, new_cursor, start.max(end));
+                Self::map_ranges(text, &ranges, |old| case::convert(old, case))
+            }
+            Operator::ShiftRight | Operator::ShiftLeft | Operator::Indent => {
+                let end = start.max(end);
+                // An exclusive motion ending at the start of a line leaves it out
+                let last = if end.col == 0 && end.line > new_cursor.line {
+                    end.line - 1
+                } else {
+                    end.line
+                };
+                return self.apply_lines(op, text, clipboard, new_cursor.line, last);
+            }
+        };
+        (new_cursor, cmds)
+    }
+
+    /// Apply an operator to a text object and return the new cursor.
+    fn apply_object<T: TextOps, C: Clipboard>(
         &mut self,
+        op: Operator,
         text: &T,
         clipboard: &mut C,
         cursor: Position,
-        input: InputEvent,
+        selected: textobj::Selected,
     ) -> (Position, Vec<Command>) {
-        // Ensure cursor is within valid bounds before processing
-        let cursor = text.clamp(cursor);
+        match selected.area(text) {
+            textobj::Area::Lines(first, last) => self.apply_lines(op, text, clipboard, first, last),
+            // Nothing to operate on, e.g. "di(" on "()"
+            textobj::Area::Chars(start, end) if start == end && op != Operator::Change => {
+                (cursor, vec![])
+            }
+            textobj::Area::Chars(start, end) => {
+                self.apply_operator(op, text, clipboard, start, end)
+            }
+        }
+    }
 
-        match (&self.mode, input) {
-            (Mode::Insert, InputEvent::Key(ke)) => {
-                if let KeyCode::Esc = ke.code {
-                    self.mode = Mode::Normal;
-                    return (cursor, vec![]);
+    /// Apply an operator to whole lines and return the new cursor.
+    fn apply_lines<T: TextOps, C: Clipboard>(
+        &mut self,
+        op: Operator,
+        text: &T,
+        clipboard: &mut C,
+        first: u32,
+        last: u32,
+    ) -> (Position, Vec<Command>) {
+        let start = text.line_start(first);
+        let end = Position {
+            line: last + 1,
+            col: 0,
+        };
+        match op {
+            Operator::Change => self.change_lines(text, clipboard, first, last),
+            Operator::Delete => {
+                let cmds = self.delete_range(text, clipboard, start, end, true);
+                (start, cmds)
+            }
+            Operator::Yank => {
+                self.yank_range(text, clipboard, start, end, true);
+                (start, vec![])
+            }
+            Operator::ShiftRight | Operator::ShiftLeft => {
+                let left = op == Operator::ShiftLeft;
+                self.preferred_col = None;
+                self.shift_lines(text, first, last, 1, left)
+            }
+            Operator::Indent => {
+                self.preferred_col = None;
+                self.reindent_lines(text, first, last)
+            }
+            Operator::Case(case) => {
+                let last = last.min(text.line_count().saturating_sub(1));
+                let end = Position {
+                    line: last,
+                    col: text.line_len(last),
+                };
+                let ranges = Self::line_ranges(text, start, end);
+                let cmds = Self::map_ranges(text, &ranges, |old| case::convert(old, case));
+                (start, cmds)
+            }
+        }
+    }
+
+    /// Apply the pending operator to the cursor line and as many lines below
+    /// it as the count asks for (`>>`, `guu`).
+    fn apply_to_count_lines<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+    ) -> (Position, Vec<Command>) {
+        self.clear_pending();
+        let count = self.counts.take_or(1);
+        let Some(op) = self.op_pending.take() else {
+            return (cursor, vec![]);
+        };
+        let last = (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
+        self.apply_lines(op, text, clipboard, cursor.line, last)
+    }
+
+    /// Linewise change (`cc`, `S`, `V_c`): clear the lines but keep the
+    /// indentation of the first one, then enter insert mode.
+    fn change_lines<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        first_line: u32,
+        last_line: u32,
+    ) -> (Position, Vec<Command>) {
+        let last_line = last_line.min(text.line_count().saturating_sub(1));
+        // The registers get the whole lines, as with "dd"
+        let value = Self::register_value(
+            text,
+            text.line_start(first_line),
+            Position {
+                line: last_line + 1,
+                col: 0,
+            },
+            true,
+        );
+        self.write_register(clipboard, value, true);
+        let start = Position {
+            line: first_line,
+            col: scan::first_non_blank(text, first_line),
+        };
+        let end = Position {
+            line: last_line,
+            col: text.line_len(last_line),
+        };
+        self.mode = Mode::Insert;
+        self.preferred_col = None;
+        let cmds = if start < end {
+            self.apply_delete(start, end)
+        } else {
+            vec![]
+        };
+        (start, cmds)
+    }
+
+    /// The selection shown for a visual area between `anchor` and `cursor`.
+    fn selection<T: TextOps>(
+        &self,
+        text: &T,
+        kind: VisualKind,
+        anchor: Position,
+        cursor: Position,
+    ) -> Selection {
+        match kind {
+            VisualKind::CharWise => {
+                let (start, end) = if anchor <= cursor {
+                    (anchor, cursor)
+                } else {
+                    (cursor, anchor)
+                };
+                Selection {
+                    start,
+                    end,
+                    kind: VisualKind::CharWise,
                 }
-                // Insert-mode special keys in later phase (Backspace, Enter)
-                (cursor, vec![])
             }
-            (Mode::Insert, InputEvent::ReceivedChar(ch)) => {
-                // Direct insertion; host applies this edit
-                let cmd = Command::InsertText {
-                    at: cursor,
-                    text: ch.to_string(),
+            VisualKind::LineWise => {
+                let (start_line, end_line) = if anchor.line <= cursor.line {
+                    (anchor.line, cursor.line)
+                } else {
+                    (cursor.line, anchor.line)
                 };
-                (
+                Selection {
+                    start: text.line_start(start_line),
+                    end: text.line_end(end_line),
+                    kind: VisualKind::LineWise,
+                }
+            }
+            VisualKind::Block => self.block(text, anchor, cursor).selection(),
+        }
+    }
+
+    /// The visual block with corners at `anchor` and `cursor`.
+    fn block<T: TextOps>(&self, text: &T, anchor: Position, cursor: Position) -> Block {
+        Block::new(text, anchor, cursor, self.options.tabstop, self.block_eol)
+    }
+
+    /// `v`, `V` or Ctrl-V in visual mode: switch to `kind`, or leave visual
+    /// mode when it is the current one.
+    fn switch_visual<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        kind: VisualKind,
+    ) -> (Position, Vec<Command>) {
+        if self.mode == Mode::Visual(kind) {
+            self.mode = Mode::Normal;
+            self.visual_anchor = None;
+            return (cursor, vec![Command::SetSelection(None)]);
+        }
+        self.mode = Mode::Visual(kind);
+        let anchor = *self.visual_anchor.get_or_insert(cursor);
+        let selection = self.selection(text, kind, anchor, cursor);
+        (cursor, vec![Command::SetSelection(Some(selection))])
+    }
+
+    /// A command on a visual block: `d`, `y`, `c`, `I`, `A`, `p` or `P`, or
+    /// `r` with the replacement character.
+    fn block_command<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        anchor: Position,
+        cursor: Position,
+        key: char,
+    ) -> (Position, Vec<Command>) {
+        let tabstop = self.options.tabstop;
+        let block = self.block(text, anchor, cursor);
+        let parts = block.parts(text, tabstop);
+        let top = &parts[0];
+        self.mode = Mode::Normal;
+        self.visual_anchor = None;
+        self.block_insert.clear();
+        let (new_cursor, mut cmds) = match key {
+            'y' => {
+                self.write_register(clipboard, Self::block_register(&parts), false);
+                let bottom = &parts[parts.len() - 1];
+                self.marks.set(
+                    '[',
                     Position {
-                        line: cursor.line,
-                        col: cursor.col + 1,
+                        line: top.line,
+                        col: top.start,
                     },
-                    vec![cmd],
-                )
+                );
+                self.marks.set(
+                    ']',
+                    Position {
+                        line: bottom.line,
+                        col: bottom.end.saturating_sub(1),
+                    },
+                );
+                let pos = Self::on_char(
+                    text,
+                    Position {
+                        line: top.line,
+                        col: top.start,
+                    },
+                );
+                (pos, vec![])
+            }
+            'd' => {
+                self.write_register(clipboard, Self::block_register(&parts), true);
+                let cmds = parts.iter().flat_map(Part::delete).collect();
+                let len = top.len_after_delete(text);
+                let pos = top.insert_pos();
+                let pos = Position {
+                    line: pos.line,
+                    col: pos.col.min(len.saturating_sub(1)),
+                };
+                (pos, cmds)
+            }
+            'c' => {
+                self.write_register(clipboard, Self::block_register(&parts), true);
+                self.mode = Mode::Insert;
+                self.block_insert = parts[1..]
+                    .iter()
+                    .filter(|part| !part.short)
+                    .map(|part| (part.insert_pos(), String::new()))
+                    .collect();
+                let cmds = parts.iter().flat_map(Part::delete).collect();
+                (top.insert_pos(), cmds)
+            }
+            'I' => {
+                self.mode = Mode::Insert;
+                let start = |part: &Part| Position {
+                    line: part.line,
+                    col: part.start,
+                };
+                self.block_insert = parts[1..]
+                    .iter()
+                    .filter(|part| !part.short)
+                    .map(|part| (start(part), String::new()))
+                    .collect();
+                (start(top), vec![])
+            }
+            'A' => {
+                self.mode = Mode::Insert;
+                // Lines ending inside the block are padded to its right edge
+                let append = |part: &Part| {
+                    let len = text.line_len(part.line);
+                    if block.right == u32::MAX || part.end < len {
+                        let col = if part.short { len } else { part.end };
+                        return (
+                            Position {
+                                line: part.line,
+                                col,
+                            },
+                            String::new(),
+                        );
+                    }
+                    let end = Position {
+                        line: part.line,
+                        col: len,
+                    };
+                    let width = scan::display_col(text, end, tabstop);
+                    let pad = (block.right + 1).saturating_sub(width);
+                    (end, " ".repeat(pad as usize))
+                };
+                self.block_insert = parts[1..].iter().map(append).collect();
+                let (pos, pad) = append(top);
+                if pad.is_empty() {
+                    (pos, vec![])
+                } else {
+                    let end = Self::advance_over(pos, &pad);
+                    (end, vec![Command::InsertText { at: pos, text: pad }])
+                }
+            }
+            'p' | 'P' => {
+                let Some(reg) = self.read_register(clipboard).filter(|r| !r.text.is_empty()) else {
+                    return (cursor, vec![Command::SetSelection(None)]);
+                };
+                let count = self.counts.take_or(1);
+                // 'P' leaves the registers as they were
+                if key == 'p' {
+                    self.write_register(clipboard, Self::block_register(&parts), true);
+                }
+                let mut cmds: Vec<Command> = parts.iter().flat_map(Part::delete).collect();
+                let rows: Vec<&str> = match reg.kind {
+                    // One line of text goes on every line of the block
+                    RegisterKind::CharWise if !reg.text.contains('\n') => {
+                        vec![reg.text.as_str(); parts.len()]
+                    }
+                    _ => reg.text.trim_end_matches('\n').split('\n').collect(),
+                };
+                let slots: Vec<Slot> = (0..rows.len() as u32)
+                    .map(|i| match parts.get(i as usize) {
+                        Some(part) => part.slot(text, block.left, tabstop),
+                        None => Slot::at(text, block.top + i, block.left, tabstop),
+                    })
+                    .collect();
+                // The end of the buffer once the block is deleted
+                let last = text.line_count().saturating_sub(1);
+                let end = Position {
+                    line: last,
+                    col: match parts.iter().find(|part| part.line == last) {
+                        Some(part) => part.len_after_delete(text),
+                        None => text.line_len(last),
+                    },
+                };
+                cmds.extend(block::put(end, &slots, &rows, count, tabstop));
+                (slots[0].start(), cmds)
+            }
+            ch => {
+                let cmds = parts
+                    .iter()
+                    .filter(|part| !part.short)
+                    .flat_map(|part| part.replace(ch, tabstop))
+                    .collect();
+                (top.insert_pos(), cmds)
             }
+        };
+        self.preferred_col = None;
+        cmds.push(Command::SetSelection(None));
+        (new_cursor, cmds)
+    }
 
-            (Mode::Normal, InputEvent::Key(ke)) => {
-                // Handle pending sequences
-                match (self.pending, ke.code) {
-                    (PendingKey::G, KeyCode::Char('g')) => {
-                        self.clear_pending();
-                        let count = self.counts.current.take();
-                        let target_line = match count {
-                            Some(n) if n > 0 => (n - 1).min(text.line_count().saturating_sub(1)),
-                            _ => 0,
-                        };
-                        let pos = text.line_start(target_line);
-                        self.preferred_col = Some(0);
+    /// The register value for the text of a visual block.
+    fn block_register(parts: &[Part]) -> Register {
+        let rows: Vec<&str> = parts.iter().map(|part| part.text.as_str()).collect();
+        Register::blockwise(rows.join("\n"))
+    }
+
+    /// The text of a visual area on each of its lines, without line breaks.
+    fn visual_ranges<T: TextOps>(
+        &self,
+        text: &T,
+        kind: VisualKind,
+        anchor: Position,
+        cursor: Position,
+    ) -> Vec<Range> {
+        let range = |line, start, end| Range {
+            start: Position { line, col: start },
+            end: Position { line, col: end },
+        };
+        let ranges: Vec<Range> = match kind {
+            VisualKind::Block => self
+                .block(text, anchor, cursor)
+                .parts(text, self.options.tabstop)
+                .iter()
+                .map(|part| range(part.line, part.start, part.end))
+                .collect(),
+            VisualKind::LineWise => (anchor.line.min(cursor.line)..=anchor.line.max(cursor.line))
+                .map(|line| range(line, 0, text.line_len(line)))
+                .collect(),
+            VisualKind::CharWise => {
+                let end = anchor.max(cursor);
+                let end = Position {
+                    line: end.line,
+                    col: (end.col + 1).min(text.line_len(end.line)),
+                };
+                return Self::line_ranges(text, anchor.min(cursor), end);
+            }
+        };
+        ranges.into_iter().filter(|r| r.start < r.end).collect()
+    }
+
+    /// The text from `start` up to `end` on each of its lines, without line
+    /// breaks.
+    fn line_ranges<T: TextOps>(text: &T, start: Position, end: Position) -> Vec<Range> {
+        (start.line..=end.line)
+            .map(|line| {
+                let len = text.line_len(line);
+                let from = if line == start.line { start.col } else { 0 };
+                let to = if line == end.line {
+                    end.col.min(len)
+                } else {
+                    len
+                };
+                Range {
+                    start: Position { line, col: from },
+                    end: Position { line, col: to },
+                }
+            })
+            .filter(|r| r.start < r.end)
+            .collect()
+    }
+
+    /// Replace the text of each range with `f` of it, where that changes it.
+    fn map_ranges<T: TextOps>(
+        text: &T,
+        ranges: &[Range],
+        f: impl Fn(&str) -> String,
+    ) -> Vec<Command> {
+        let mut cmds = Vec::new();
+        for &range in ranges {
+            let old = text.slice_to_string(range);
+            let new = f(&old);
+            if new != old {
+                cmds.push(Command::Delete { range });
+                cmds.push(Command::InsertText {
+                    at: range.start,
+                    text: new,
+                });
+            }
+        }
+        cmds
+    }
+
+    /// Change the case of a visual area (`~`, `u`, `U`) or rot13 it (`g?`).
+    fn visual_case<T: TextOps>(
+        &mut self,
+        text: &T,
+        kind: VisualKind,
+        anchor: Position,
+        cursor: Position,
+        case: Case,
+    ) -> (Position, Vec<Command>) {
+        let ranges = self.visual_ranges(text, kind, anchor, cursor);
+        let cmds = Self::map_ranges(text, &ranges, |old| case::convert(old, case));
+        let pos = ranges.first().map_or(anchor.min(cursor), |r| r.start);
+        self.end_visual(pos, cmds)
+    }
+
+    /// Leave visual mode after a command that changed the buffer.
+    fn end_visual(&mut self, pos: Position, mut cmds: Vec<Command>) -> (Position, Vec<Command>) {
+        self.mode = Mode::Normal;
+        self.visual_anchor = None;
+        self.preferred_col = None;
+        cmds.push(Command::SetSelection(None));
+        (pos, cmds)
+    }
+
+    /// Give lines `first` to `last` the indent `width` returns for each line
+    /// and its current indent, in screen columns, and return the first
+    /// non-blank of the first line. Empty lines are left alone.
+    fn set_indents<T: TextOps>(
+        &self,
+        text: &T,
+        first: u32,
+        last: u32,
+        width: impl Fn(u32, u32) -> Option<u32>,
+    ) -> (Position, Vec<Command>) {
+        let mut cmds = Vec::new();
+        let mut pos = Position {
+            line: first,
+            col: scan::first_non_blank(text, first),
+        };
+        let last = last.min(text.line_count().saturating_sub(1));
+        for line in first..=last {
+            if text.line_len(line) == 0 {
+                continue;
+            }
+            let (blanks, old) = indent::current(text, line, self.options.tabstop);
+            let Some(new) = width(line, old) else {
+                continue;
+            };
+            let new = indent::blanks(new, &self.options);
+            let start = Position { line, col: 0 };
+            let end = Position { line, col: blanks };
+            if line == first {
+                pos.col = new.chars().count() as u32;
+            }
+            if text.slice_to_string(Range { start, end }) == new {
+                continue;
+            }
+            if blanks > 0 {
+                cmds.push(Command::Delete {
+                    range: Range { start, end },
+                });
+            }
+            if !new.is_empty() {
+                cmds.push(Command::InsertText {
+                    at: start,
+                    text: new,
+                });
+            }
+        }
+        (pos, cmds)
+    }
+
+    /// Shift lines `first` to `last` `amount` times, right or `left`.
+    fn shift_lines<T: TextOps>(
+        &self,
+        text: &T,
+        first: u32,
+        last: u32,
+        amount: u32,
+        left: bool,
+    ) -> (Position, Vec<Command>) {
+        self.set_indents(text, first, last, |_, width| {
+            Some(indent::shifted(width, amount, left, &self.options))
+        })
+    }
+
+    /// Indent lines `first` to `last` with the host's indent function (`=`).
+    /// Without one the lines keep their indent.
+    fn reindent_lines<T: TextOps>(
+        &self,
+        text: &T,
+        first: u32,
+        last: u32,
+    ) -> (Position, Vec<Command>) {
+        let Some(indent_fn) = &self.indent_fn else {
+            return self.set_indents(text, first, last, |_, _| None);
+        };
+        self.set_indents(text, first, last, |line, _| (indent_fn.0)(text, line))
+    }
+
+    /// Replace a charwise or linewise visual area with the contents of a
+    /// register (`p`, `P`). The area goes to the registers with `p` only.
+    fn visual_put<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        kind: VisualKind,
+        anchor: Position,
+        cursor: Position,
+        key: char,
+    ) -> (Position, Vec<Command>) {
+        let Some(reg) = self.read_register(clipboard).filter(|r| !r.text.is_empty()) else {
+            return self.end_visual(cursor, vec![]);
+        };
+        let count = self.counts.take_or(1) as usize;
+        let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+        let linewise = kind == VisualKind::LineWise;
+        let (start, end) = if linewise {
+            let end = Position {
+                line: end.line,
+                col: text.line_len(end.line),
+            };
+            (text.line_start(start.line), end)
+        } else {
+            (start, text.move_right(end, 1))
+        };
+        if key == 'p' {
+            let value = if linewise {
+                let content = text.slice_to_string(Range { start, end });
+                Register::linewise(format!("{content}\n"))
+            } else {
+                Self::register_value(text, start, end, false)
+            };
+            self.write_register(clipboard, value, true);
+        }
+        // Lines keep their last line break, so the last line of the buffer
+        // can be replaced too
+        let mut cmds = self.apply_delete(start, end);
+        let repeated = reg.text.repeat(count);
+        let (piece, pos) = match (reg.kind == RegisterKind::LineWise, linewise) {
+            (true, true) => (
+                repeated.strip_suffix('\n').unwrap_or(&repeated).to_string(),
+                start,
+            ),
+            // Lines put over characters go between the two halves of the line
+            (true, false) => (
+                format!("\n{repeated}"),
+                Position {
+                    line: start.line + 1,
+                    col: 0,
+                },
+            ),
+            (false, true) => (repeated, start),
+            (false, false) => {
+                let after = Self::advance_over(start, &repeated);
+                let last = Position {
+                    line: after.line,
+                    col: after.col.saturating_sub(1),
+                };
+                (repeated, last)
+            }
+        };
+        cmds.push(Command::InsertText {
+            at: start,
+            text: piece,
+        });
+        self.end_visual(pos, cmds)
+    }
+
+    /// Select the last visual area again (`gv`). In visual mode the current
+    /// area becomes the last one.
+    fn reselect<T: TextOps>(&mut self, text: &T, cursor: Position) -> (Position, Vec<Command>) {
+        let (Some(last), Some(start), Some(end)) =
+            (self.last_visual, self.marks.get('<'), self.marks.get('>'))
+        else {
+            self.fail();
+            return (cursor, vec![]);
+        };
+        if let (Mode::Visual(kind), Some(anchor)) = (self.mode, self.visual_anchor) {
+            self.marks.set('<', anchor.min(cursor));
+            self.marks.set('>', anchor.max(cursor));
+            self.last_visual = Some(LastVisual {
+                kind,
+                cursor_at_start: cursor < anchor,
+                eol: self.block_eol,
+            });
+        }
+        let (start, end) = (Self::on_char(text, start), Self::on_char(text, end));
+        let (anchor, new_cursor) = if last.cursor_at_start {
+            (end, start)
+        } else {
+            (start, end)
+        };
+        self.mode = Mode::Visual(last.kind);
+        self.visual_anchor = Some(anchor);
+        self.block_eol = last.eol;
+        self.preferred_col = None;
+        let selection = self.selection(text, last.kind, anchor, new_cursor);
+        (
+            new_cursor,
+            vec![
+                Command::SetCursor(new_cursor),
+                Command::SetSelection(Some(selection)),
+            ],
+        )
+    }
+
+    /// Copy the text typed after a blockwise `I`, `A` or `c` to the other
+    /// lines of the block. Text with a line break is not copied.
+    fn copy_block_insert(&mut self, inserted: &str) -> Vec<Command> {
+        let lines = std::mem::take(&mut self.block_insert);
+        if inserted.is_empty() || inserted.contains('\n') {
+            return vec![];
+        }
+        lines
+            .into_iter()
+            .map(|(at, pad)| Command::InsertText {
+                at,
+                text: pad + inserted,
+            })
+            .collect()
+    }
+
+    /// Repeat the text typed after `o` or `O` with a count on as many more
+    /// new lines, after the one typed at `end`. Returns where the last copy
+    /// ends.
+    fn copy_open_lines(&mut self, end: Position, inserted: &str) -> Option<(Position, Command)> {
+        let (copies, indent) = self.open_copies.take()?;
+        let text = format!("\n{indent}{inserted}").repeat(copies as usize);
+        let last = Self::advance_over(end, &text);
+        Some((last, Command::InsertText { at: end, text }))
+    }
+
+    /// The copies of `inserted` still to type over the text after `R` with a
+    /// count.
+    fn replace_copies(&mut self, inserted: &str) -> String {
+        inserted.repeat(std::mem::take(&mut self.replace_copies) as usize)
+    }
+
+    /// Remove an indent that autoindent put on a line and nothing was typed
+    /// after, ending at `end`. Returns where the cursor goes and the delete.
+    fn drop_auto_indent(&mut self, end: Position) -> (Position, Command) {
+        let start = Position {
+            line: end.line,
+            col: 0,
+        };
+        let keep = self.inserted.rfind('\n').map_or(0, |i| i + 1);
+        self.inserted.truncate(keep);
+        if self.inserted.is_empty()
+            && let Some((_, indent)) = &mut self.open_copies
+        {
+            indent.clear();
+        }
+        (
+            start,
+            Command::Delete {
+                range: Range { start, end },
+            },
+        )
+    }
+
+    /// Remember the cursor before a jump in the `'` mark.
+    fn jump(&mut self, from: Position) {
+        self.marks.set('\'', from);
+    }
+
+    /// The position of mark `name` for `'` (`exact` unset) or `` ` ``.
+    fn mark_position<T: TextOps>(&self, text: &T, name: char, exact: bool) -> Option<Position> {
+        let pos = text.clamp(self.marks.get(name)?);
+        Some(if exact {
+            pos
+        } else {
+            Position {
+                line: pos.line,
+                col: scan::first_non_blank(text, pos.line),
+            }
+        })
+    }
+
+    /// Where `H`, `M` or `L` goes: the first non-blank of a line on screen.
+    fn screen_line<T: TextOps>(&self, text: &T, key: char, count: u32) -> Option<Position> {
+        let line = scroll::screen_line(self.viewport?, text.line_count(), key, count);
+        Some(Position {
+            line,
+            col: scan::first_non_blank(text, line),
+        })
+    }
+
+    /// Where `%` goes: the bracket matching the one at or after the cursor,
+    /// or with a count the first non-blank of the line `count` percent into
+    /// the text.
+    fn percent<T: TextOps>(text: &T, cursor: Position, count: Option<u32>) -> Option<Position> {
+        match count {
+            None => scan::find_match(text, cursor),
+            Some(n) if n > 100 => None,
+            Some(n) => {
+                let lines = u64::from(text.line_count());
+                let line = ((u64::from(n) * lines).div_ceil(100) as u32).saturating_sub(1);
+                Some(Position {
+                    line,
+                    col: scan::first_non_blank(text, line),
+                })
+            }
+        }
+    }
+
+    /// Where `[(`, `[{`, `])` or `]}` goes: `count` unmatched brackets out
+    /// from the cursor, or as many as there are.
+    fn unmatched_bracket<T: TextOps>(
+        text: &T,
+        cursor: Position,
+        forward: bool,
+        key: char,
+        count: u32,
+    ) -> Option<Position> {
+        let (open, close) = match (forward, key) {
+            (false, '(') | (true, ')') => ('(', ')'),
+            (false, '{') | (true, '}') => ('{', '}'),
+            _ => return None,
+        };
+        let mut pos = scan::find_unmatched(text, cursor, open, close, forward)?;
+        for _ in 1..count {
+            match scan::find_unmatched(text, pos, open, close, forward) {
+                Some(next) => pos = next,
+                None => break,
+            }
+        }
+        Some(pos)
+    }
+
+    /// Where `find` goes on the line, `count` characters on. Repeated with `;`
+    /// or `,`, a `t` or `T` right next to its character skips it to the next
+    /// one, unless `cpo_semicolon` is set.
+    fn find_char<T: TextOps>(
+        &self,
+        text: &T,
+        cursor: Position,
+        find: Find,
+        count: u32,
+        repeat: bool,
+    ) -> Option<Position> {
+        let mut from = cursor;
+        if repeat && find.before && !self.options.cpo_semicolon {
+            from.col = if find.backward {
+                from.col.saturating_sub(1)
+            } else {
+                from.col + 1
+            };
+        }
+        let pos = if find.backward {
+            text.find_in_line_backward(from, find.ch, count)?
+        } else {
+            text.find_in_line(from, find.ch, find.before, count)?
+        };
+        Some(match (find.before, find.backward) {
+            (false, _) => pos,
+            (true, false) => Position {
+                line: pos.line,
+                col: pos.col.saturating_sub(1),
+            },
+            (true, true) => Position {
+                line: pos.line,
+                col: pos.col + 1,
+            },
+        })
+    }
+
+    /// `f`, `t`, `F` or `T`, or `;` and `,` with `repeat`: move to the
+    /// character, or apply `op` up to it. Forward the motion is inclusive,
+    /// backward exclusive.
+    #[allow(clippy::too_many_arguments)]
+    fn find_motion<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        op: Option<Operator>,
+        find: Find,
+        count: u32,
+        repeat: bool,
+    ) -> (Position, Vec<Command>) {
+        let Some(pos) = self.find_char(text, cursor, find, count, repeat) else {
+            self.fail();
+            return (cursor, vec![]);
+        };
+        if let Some(op) = op {
+            let end = if find.backward {
+                pos
+            } else {
+                text.move_right(pos, 1)
+            };
+            return self.apply_operator(op, text, clipboard, cursor, end);
+        }
+        self.preferred_col = None;
+        (pos, vec![Command::SetCursor(pos)])
+    }
+
+    /// The last find for `;`, or turned around for `,`.
+    fn repeated_find(&self, key: char) -> Option<Find> {
+        let find = self.last_find?;
+        Some(Find {
+            backward: find.backward != (key == ','),
+            ..find
+        })
+    }
+
+    /// Move the cursor end of the visual selection to `pos`.
+    fn visual_move<T: TextOps>(
+        &mut self,
+        text: &T,
+        kind: VisualKind,
+        cursor: Position,
+        pos: Position,
+    ) -> (Position, Vec<Command>) {
+        let anchor = self.visual_anchor.unwrap_or(cursor);
+        let selection = self.selection(text, kind, anchor, pos);
+        (
+            pos,
+            vec![
+                Command::SetCursor(pos),
+                Command::SetSelection(Some(selection)),
+            ],
+        )
+    }
+
+    /// Scroll with Ctrl-D, Ctrl-U, Ctrl-F, Ctrl-B, Ctrl-E or Ctrl-Y (`key`)
+    /// and move the cursor with the text. Ctrl-E and Ctrl-Y keep the column,
+    /// the others go to the first non-blank. `None` without a viewport or
+    /// when there is nothing to scroll.
+    fn scroll<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        key: char,
+        count: Option<u32>,
+    ) -> Option<(Position, Vec<Command>)> {
+        let view = self.viewport?;
+        let lines = text.line_count();
+        let count = count.map(|n| n.max(1));
+        let (top, line) = match key {
+            'd' | 'u' => {
+                if let Some(count) = count {
+                    self.scroll_lines = count;
+                }
+                let amount = match self.scroll_lines {
+                    0 => (view.height / 2).max(1),
+                    n => n,
+                };
+                scroll::half_page(view, lines, cursor.line, amount, key == 'd')?
+            }
+            'f' | 'b' => {
+                let amount = count.unwrap_or(1) * view.height.saturating_sub(2).max(1);
+                scroll::scroll(view, lines, cursor.line, amount, key == 'f')?
+            }
+            _ => scroll::scroll(view, lines, cursor.line, count.unwrap_or(1), key == 'e')?,
+        };
+        let pos = match key {
+            'e' | 'y' if line == cursor.line => cursor,
+            'e' | 'y' => {
+                // Pushed along like 'j' and 'k'
+                let pos = if line > cursor.line {
+                    text.move_down(cursor, line - cursor.line, self.preferred_col)
+                } else {
+                    text.move_up(cursor, cursor.line - line, self.preferred_col)
+                };
+                self.preferred_col = Some(pos.col);
+                pos
+            }
+            _ => {
+                self.preferred_col = None;
+                Position {
+                    line,
+                    col: scan::first_non_blank(text, line),
+                }
+            }
+        };
+        let mut cmds = Vec::new();
+        if top != view.top {
+            self.viewport = Some(Viewport { top, ..view });
+            cmds.push(Command::ScrollTo(top));
+        }
+        cmds.push(Command::SetCursor(pos));
+        Some((pos, cmds))
+    }
+
+    /// `pos` moved onto a character of the buffer, where normal mode keeps
+    /// the cursor.
+    fn on_char<T: TextOps>(text: &T, pos: Position) -> Position {
+        let pos = text.clamp(pos);
+        Position {
+            line: pos.line,
+            col: pos.col.min(text.line_len(pos.line).saturating_sub(1)),
+        }
+    }
+
+    /// Ctrl with a key in normal mode.
+    fn ctrl_key<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        code: KeyCode,
+    ) -> (Position, Vec<Command>) {
+        self.clear_pending();
+        self.clear_op();
+        if let KeyCode::Char(c @ ('d' | 'u' | 'f' | 'b' | 'e' | 'y')) = code {
+            let count = self.counts.current.take();
+            return self.scroll(text, cursor, c, count).unwrap_or_else(|| {
+                self.fail();
+                (cursor, vec![])
+            });
+        }
+        let count = self.counts.take_or(1) as usize;
+        let target = match code {
+            KeyCode::Char('o') => self.marks.jump_older(cursor, count),
+            KeyCode::Char('i') => self.marks.jump_newer(count),
+            #[cfg(feature = "undo")]
+            KeyCode::Char('r') => return self.undo(cursor, count, true),
+            KeyCode::Char('v') => {
+                self.mode = Mode::Visual(VisualKind::Block);
+                self.visual_anchor = Some(cursor);
+                self.block_eol = false;
+                let selection = self.selection(text, VisualKind::Block, cursor, cursor);
+                return (cursor, vec![Command::SetSelection(Some(selection))]);
+            }
+            _ => return (cursor, vec![]),
+        };
+        let Some(pos) = target else {
+            self.fail();
+            return (cursor, vec![]);
+        };
+        let pos = Self::on_char(text, pos);
+        self.preferred_col = None;
+        (pos, vec![Command::SetCursor(pos)])
+    }
+
+    /// Undo `count` changes, or redo them with `redo` (`u`, Ctrl-R).
+    #[cfg(feature = "undo")]
+    fn undo(&mut self, cursor: Position, count: usize, redo: bool) -> (Position, Vec<Command>) {
+        let mut new_cursor = cursor;
+        let mut cmds = Vec::new();
+        for _ in 0..count {
+            let step = if redo {
+                self.history.redo()
+            } else {
+                self.history.undo()
+            };
+            match step {
+                Some((pos, step)) => {
+                    new_cursor = pos;
+                    cmds.extend(step);
+                }
+                None if cmds.is_empty() => {
+                    self.fail();
+                    let message = if redo {
+                        "Already at newest change"
+                    } else {
+                        "Already at oldest change"
+                    };
+                    return (cursor, vec![Command::Message(message.to_string())]);
+                }
+                None => break,
+            }
+        }
+        self.undoing = true;
+        self.preferred_col = None;
+        (new_cursor, cmds)
+    }
+
+    /// The error for a mark motion to `name` that is not set.
+    fn mark_error(&mut self, name: char) -> Command {
+        self.fail();
+        Command::Error(if marks::is_valid_mark(name) {
+            "E20: Mark not set".to_string()
+        } else {
+            "E78: Unknown mark".to_string()
+        })
+    }
+
+    /// A motion failed. Like Vim's beep, this aborts a macro being replayed.
+    fn fail(&mut self) {
+        self.replay.clear();
+    }
+
+    /// Store the keys recorded since `q{reg}` in that register.
+    fn finish_recording(&mut self) {
+        if let Some(name) = self.recording.take() {
+            let keys = std::mem::take(&mut self.recorded);
+            self.registers.set(name, Register::recorded(keys));
+        }
+    }
+
+    /// Queue the contents of register `name` `count` times for replay (`@`).
+    fn queue_macro<C: Clipboard>(&mut self, clipboard: &mut C, name: char, count: u32) {
+        let name = match name {
+            '@' => match self.last_macro {
+                Some(name) => name,
+                None => return self.fail(),
+            },
+            name => name,
+        };
+        let register = if registers::is_clipboard_register(name) {
+            clipboard.get().map(Register::from_external)
+        } else {
+            self.registers.get(name).cloned()
+        };
+        let Some(register) = register else {
+            return self.fail();
+        };
+        self.last_macro = Some(name);
+        let keys: Vec<ReplayKey> = match register.keys {
+            Some(keys) => keys.into_iter().map(ReplayKey::Event).collect(),
+            // "@:" runs the last command line again
+            None if name == ':' => format!(":{}\r", register.text)
+                .chars()
+                .map(ReplayKey::Text)
+                .collect(),
+            None => register.text.chars().map(ReplayKey::Text).collect(),
+        };
+        // Queued ahead of what is left, so a macro can run another one
+        for _ in 0..count {
+            for key in keys.iter().rev() {
+                self.replay.push_front(key.clone());
+            }
+        }
+    }
+
+    /// Run a command line typed after `:`. Errors are reported to the host
+    /// with [`Command::Error`].
+    fn execute_ex<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        line: &str,
+    ) -> (Position, Vec<Command>) {
+        match self.run_ex(text, clipboard, cursor, line) {
+            Ok(result) => result,
+            Err(message) => {
+                self.fail();
+                (cursor, vec![Command::Error(message)])
+            }
+        }
+    }
+
+    fn run_ex<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        line: &str,
+    ) -> Result<(Position, Vec<Command>), String> {
+        let parsed = ex::parse(line)?;
+        let lines = ex::resolve(
+            text,
+            &parsed.range,
+            cursor.line,
+            |name| self.marks.get(name).map(|pos| pos.line),
+            self.last_search.as_ref().map(|s| s.query.as_str()),
+        )?;
+
+        if parsed.name.is_empty() {
+            // A range alone moves to its last line, stopping at the end
+            let Some((_, last)) = lines else {
+                return Ok((cursor, vec![]));
+            };
+            if last < 0 {
+                return Err("E16: Invalid range".to_string());
+            }
+            let last_line = i64::from(text.line_count().saturating_sub(1));
+            let line = (last - 1).clamp(0, last_line) as u32;
+            let pos = Position {
+                line,
+                col: scan::first_non_blank(text, line),
+            };
+            self.preferred_col = None;
+            self.jump(cursor);
+            return Ok((pos, vec![Command::SetCursor(pos)]));
+        }
+
+        let range = lines.map(|lines| ex::check(text, lines)).transpose()?;
+        let Some(builtin) = ex::builtin(parsed.name) else {
+            return match ex::lookup(&self.ex_commands, parsed.name)? {
+                Some(name) => Ok((
+                    cursor,
+                    vec![Command::Ex(ExCommand {
+                        name: name.name.clone(),
+                        range,
+                        bang: parsed.bang,
+                        args: parsed.args.to_string(),
+                    })],
+                )),
+                None => Err(format!("E492: Not an editor command: {}", line.trim())),
+            };
+        };
+
+        let force = parsed.bang;
+        let path = Some(parsed.args.trim_end())
+            .filter(|p| !p.is_empty())
+            .map(str::to_string);
+        match builtin {
+            Builtin::Quit | Builtin::Edit if range.is_some() => {
+                Err("E481: No range allowed".to_string())
+            }
+            Builtin::Delete | Builtin::Yank | Builtin::Substitute | Builtin::SubstituteRepeat
+                if force =>
+            {
+                Err("E477: No ! allowed".to_string())
+            }
+            Builtin::Substitute | Builtin::SubstituteRepeat => {
+                let (sub, count) = substitute::parse(
+                    parsed.args,
+                    builtin == Builtin::SubstituteRepeat,
+                    self.last_substitute.as_ref(),
+                    self.last_search.as_ref().map(|s| s.query.as_str()),
+                )?;
+                let mut range = range.unwrap_or(LineRange {
+                    start: cursor.line,
+                    end: cursor.line,
+                });
+                if let Some(count) = count {
+                    // A count starts at the last line of the range
+                    let last_line = text.line_count().saturating_sub(1);
+                    range = LineRange {
+                        start: range.end,
+                        end: range.end.saturating_add(count - 1).min(last_line),
+                    };
+                }
+                self.substitute(text, cursor, range, sub)
+            }
+            Builtin::Write => Ok((cursor, vec![Command::Write { path, range, force }])),
+            Builtin::Quit => Ok((cursor, vec![Command::Quit { force }])),
+            Builtin::WriteQuit => Ok((
+                cursor,
+                vec![
+                    Command::Write { path, range, force },
+                    Command::Quit { force },
+                ],
+            )),
+            Builtin::Edit => Ok((cursor, vec![Command::Edit { path, force }])),
+            Builtin::Delete | Builtin::Yank => {
+                let current = LineRange {
+                    start: cursor.line,
+                    end: cursor.line,
+                };
+                let range = self.line_command_args(text, range.unwrap_or(current), parsed.args)?;
+                let start = text.line_start(range.start);
+                let end = Position {
+                    line: range.end + 1,
+                    col: 0,
+                };
+                if builtin == Builtin::Yank {
+                    self.yank_range(text, clipboard, start, end, true);
+                    return Ok((cursor, vec![]));
+                }
+                let cmds = self.delete_range(text, clipboard, start, end, true);
+                // The cursor goes to the line after the deleted ones, or the
+                // one before when the deletion reached the end
+                let (line, below) = if range.end + 1 < text.line_count() {
+                    (range.start, range.end + 1)
+                } else {
+                    let above = range.start.saturating_sub(1);
+                    (above, above)
+                };
+                self.preferred_col = None;
+                let pos = Position {
+                    line,
+                    col: scan::first_non_blank(text, below),
+                };
+                Ok((pos, cmds))
+            }
+        }
+    }
+
+    /// Run `:s` over the lines of `range`.
+    fn substitute<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        range: LineRange,
+        sub: Substitute,
+    ) -> Result<(Position, Vec<Command>), String> {
+        let pattern = Pattern::new(&sub.pattern, sub.flags.ignore_case)?;
+        self.last_substitute = Some(sub.clone());
+        let not_found = || format!("E486: Pattern not found: {}", sub.pattern);
+        let flags = sub.flags;
+
+        if flags.confirm && !flags.count_only {
+            for line in range.start..=range.end {
+                let line_text = LineText::of(text, line);
+                if let Some(current) =
+                    substitute::next_match(&pattern, &sub.replacement, &line_text, 0)
+                {
+                    self.confirm = Some(ConfirmState {
+                        pattern,
+                        template: sub.replacement,
+                        global: flags.global,
+                        line,
+                        last_line: range.end,
+                        text: line_text,
+                        current,
+                        substitutions: 0,
+                        lines: 0,
+                        cursor: None,
+                    });
+                    self.mode = Mode::Confirm;
+                    return Ok(self.confirm_prompt());
+                }
+            }
+            return if flags.no_error {
+                Ok((cursor, vec![]))
+            } else {
+                Err(not_found())
+            };
+        }
+
+        let mut found = Vec::new();
+        for line in range.start..=range.end {
+            let line_text = LineText::of(text, line);
+            let reps =
+                substitute::matches_in_line(&pattern, &sub.replacement, &line_text, flags.global);
+            if !reps.is_empty() {
+                found.push((line, line_text, reps));
+            }
+        }
+        if found.is_empty() {
+            return if flags.no_error {
+                Ok((cursor, vec![]))
+            } else {
+                Err(not_found())
+            };
+        }
+        let count: u32 = found.iter().map(|(_, _, reps)| reps.len() as u32).sum();
+        let lines = found.len() as u32;
+        if flags.count_only {
+            let message = substitute::report(count, lines, "match");
+            return Ok((cursor, vec![Command::Message(message)]));
+        }
+
+        let (pos, mut cmds) = Self::substitute_lines(found);
+        self.preferred_col = None;
+        cmds.push(Command::SetCursor(pos));
+        if count > 2 {
+            let message = substitute::report(count, lines, "substitution");
+            cmds.push(Command::Message(message));
+        }
+        Ok((pos, cmds))
+    }
+
+    /// Edits making the replacements found in several lines, and the first
+    /// non-blank of the last changed line once they are made. Lines are
+    /// edited bottom to top so line breaks inserted by `\r` do not move the
+    /// lines still to be edited.
+    fn substitute_lines(found: Vec<(u32, LineText, Vec<Replacement>)>) -> (Position, Vec<Command>) {
+        let mut cmds = Vec::new();
+        for (line, line_text, reps) in found.iter().rev() {
+            cmds.extend(substitute::edits(*line, line_text, reps));
+        }
+        let added: u32 = found
+            .iter()
+            .flat_map(|(_, _, reps)| reps)
+            .map(|rep| rep.text.matches('\n').count() as u32)
+            .sum();
+        let (_, line_text, reps) = found.last().expect("at least one line");
+        let new_text = substitute::apply(line_text, reps);
+        let last = new_text.rsplit('\n').next().unwrap_or_default();
+        let pos = Position {
+            line: found.last().map_or(0, |(line, ..)| *line) + added,
+            col: scan::first_non_blank_in(last),
+        };
+        (pos, cmds)
+    }
+
+    /// Show the current match of `:s///c`.
+    fn confirm_prompt(&self) -> (Position, Vec<Command>) {
+        let Some(state) = &self.confirm else {
+            return (Position::ZERO, vec![]);
+        };
+        let start = Position {
+            line: state.line,
+            col: state.text.col(state.current.start),
+        };
+        let end = Position {
+            line: state.line,
+            col: state.text.col(state.current.end),
+        };
+        (
+            start,
+            vec![
+                Command::SetCursor(start),
+                Command::ConfirmSubstitute {
+                    range: Range { start, end },
+                    replacement: state.current.text.clone(),
+                },
+            ],
+        )
+    }
+
+    /// Answer the `:s///c` prompt: `y` substitutes, `n` skips, `a`
+    /// substitutes this and all remaining matches, `l` substitutes this one
+    /// and stops, and `q` or Esc stops.
+    fn confirm_key<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        code: KeyCode,
+    ) -> (Position, Vec<Command>) {
+        let Some(mut state) = self.confirm.take() else {
+            self.mode = Mode::Normal;
+            return (cursor, vec![]);
+        };
+        match code {
+            KeyCode::Char('y' | 'l') => {
+                let cmds = substitute::edits(
+                    state.line,
+                    &state.text,
+                    std::slice::from_ref(&state.current),
+                );
+                // The rest of the line as it will be after the substitution
+                let new_text = substitute::apply(&state.text, std::slice::from_ref(&state.current));
+                let breaks = state.current.text.matches('\n').count() as u32;
+                let last = new_text.rsplit('\n').next().unwrap_or_default();
+                let replaced_end = match state.current.text.rsplit_once('\n') {
+                    Some((_, tail)) => tail.chars().count(),
+                    None => state.current.start + state.current.text.chars().count(),
+                };
+                state.substitutions += 1;
+                if state.cursor.map(|pos| pos.line) != Some(state.line) {
+                    state.lines += 1;
+                }
+                state.cursor = Some(Position {
+                    line: state.line + breaks,
+                    col: scan::first_non_blank_in(last),
+                });
+                if code == KeyCode::Char('l') {
+                    return self.finish_confirm(state, cmds);
+                }
+                let empty = state.current.end == state.current.start;
+                let rest = (LineText::new(last), replaced_end, empty);
+                state.line += breaks;
+                state.last_line += breaks;
+                self.confirm_advance(text, state, rest, breaks, cmds)
+            }
+            KeyCode::Char('n') => {
+                let empty = state.current.end == state.current.start;
+                let rest = (state.text.clone(), state.current.end, empty);
+                self.confirm_advance(text, state, rest, 0, vec![])
+            }
+            KeyCode::Char('a') => {
+                let mut reps = vec![state.current.clone()];
+                if state.global {
+                    reps.extend(substitute::matches_after(
+                        &state.pattern,
+                        &state.template,
+                        &state.text,
+                        &state.current,
+                    ));
+                }
+                let mut found = vec![(state.line, state.text.clone(), reps)];
+                for line in state.line + 1..=state.last_line {
+                    let line_text = LineText::of(text, line);
+                    let reps = substitute::matches_in_line(
+                        &state.pattern,
+                        &state.template,
+                        &line_text,
+                        state.global,
+                    );
+                    if !reps.is_empty() {
+                        found.push((line, line_text, reps));
+                    }
+                }
+                state.substitutions += found.iter().map(|(_, _, r)| r.len() as u32).sum::<u32>();
+                state.lines += found.len() as u32;
+                if state.cursor.map(|pos| pos.line) == Some(state.line) {
+                    // Already counted when an earlier match was substituted
+                    state.lines -= 1;
+                }
+                let (pos, cmds) = Self::substitute_lines(found);
+                state.cursor = Some(pos);
+                self.finish_confirm(state, cmds)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.finish_confirm(state, vec![]),
+            _ => {
+                self.confirm = Some(state);
+                (cursor, vec![])
+            }
+        }
+    }
+
+    /// Move `:s///c` on to the next match: later in `rest` (the remainder of
+    /// the current line, with where the last match ended and whether it was
+    /// empty) when substituting globally, else on the following lines. `shift` is the number of line
+    /// breaks `cmds` insert, which the buffer passed in does not have yet.
+    fn confirm_advance<T: TextOps>(
+        &mut self,
+        text: &T,
+        mut state: ConfirmState,
+        rest: (LineText, usize, bool),
+        shift: u32,
+        mut cmds: Vec<Command>,
+    ) -> (Position, Vec<Command>) {
+        let (line_text, end, empty) = rest;
+        if state.global
+            && let Some(current) =
+                substitute::match_after(&state.pattern, &state.template, &line_text, end, empty)
+        {
+            state.text = line_text;
+            state.current = current;
+            self.confirm = Some(state);
+            let (pos, prompt) = self.confirm_prompt();
+            cmds.extend(prompt);
+            return (pos, cmds);
+        }
+        for line in state.line + 1..=state.last_line {
+            let line_text = LineText::of(text, line - shift);
+            if let Some(current) =
+                substitute::next_match(&state.pattern, &state.template, &line_text, 0)
+            {
+                state.line = line;
+                state.text = line_text;
+                state.current = current;
+                self.confirm = Some(state);
+                let (pos, prompt) = self.confirm_prompt();
+                cmds.extend(prompt);
+                return (pos, cmds);
+            }
+        }
+        self.finish_confirm(state, cmds)
+    }
+
+    /// Leave `:s///c`, putting the cursor on the last substituted line.
+    fn finish_confirm(
+        &mut self,
+        state: ConfirmState,
+        mut cmds: Vec<Command>,
+    ) -> (Position, Vec<Command>) {
+        self.mode = Mode::Normal;
+        self.confirm = None;
+        self.preferred_col = None;
+        let pos = state.cursor.unwrap_or(Position {
+            line: state.line,
+            col: state.text.col(state.current.start),
+        });
+        cmds.push(Command::SetCursor(pos));
+        if state.substitutions > 2 {
+            let message = substitute::report(state.substitutions, state.lines, "substitution");
+            cmds.push(Command::Message(message));
+        }
+        (pos, cmds)
+    }
+
+    /// Parse the `[x] [count]` arguments of `:d` and `:y`. The register is
+    /// selected for the command and a count makes the range start at its
+    /// last line.
+    fn line_command_args<T: TextOps>(
+        &mut self,
+        text: &T,
+        range: LineRange,
+        args: &str,
+    ) -> Result<LineRange, String> {
+        let mut args = args.trim();
+        if let Some(name) = args.chars().next()
+            && !name.is_ascii_digit()
+            && registers::is_valid_register(name)
+        {
+            self.register = Some(name);
+            args = args[name.len_utf8()..].trim_start();
+        }
+        if args.is_empty() {
+            return Ok(range);
+        }
+        let count: u32 = args
+            .parse()
+            .map_err(|_| format!("E488: Trailing characters: {args}"))?;
+        if count == 0 {
+            return Err("E939: Positive count required".to_string());
+        }
+        let last_line = text.line_count().saturating_sub(1);
+        Ok(LineRange {
+            start: range.end,
+            end: range.end.saturating_add(count - 1).min(last_line),
+        })
+    }
+
+    /// True when no count, pending key or operator is in progress.
+    fn is_idle(&self) -> bool {
+        self.counts.current.is_none()
+            && self.pending == PendingKey::None
+            && self.op_pending.is_none()
+            && self.register.is_none()
+    }
+
+    /// Process an input event and return the new cursor position and commands.
+    ///
+    /// This is the main entry point for processing vim input. It takes the current
+    /// text buffer state, clipboard, cursor position, and an input event, then
+    /// returns the new cursor position and a list of commands to apply.
+    ///
+    /// Commands are meant to be applied in order; positions in a later command
+    /// refer to the buffer after the earlier commands have been applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text buffer implementing TextOps
+    /// * `clipboard` - The clipboard for yank/paste operations
+    /// * `cursor` - The current cursor position
+    /// * `input` - The input event to process
+    ///
+    /// # Returns
+    ///
+    /// A tuple of (new_cursor_position, commands_to_apply)
+    pub fn handle_event<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        input: InputEvent,
+    ) -> (Position, Vec<Command>) {
+        let recording = self.recording.is_some();
+        let result = self.process(text, clipboard, cursor, input.clone());
+        // The 'q' that stops a recording is not part of it
+        if recording && self.recording.is_some() {
+            self.recorded.push(input);
+        }
+        result
+    }
+
+    /// Feed the next key of a macro started with `@{reg}`.
+    ///
+    /// `@{reg}` only queues the register's keys. Each of them must see the
+    /// buffer as the previous one left it, so the host replays them one at a
+    /// time and applies the returned commands in between. Returns `None` once
+    /// nothing is left to replay. A motion that fails during playback, such as
+    /// `j` on the last line or an `f` that finds nothing, cancels the rest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vim_mini::{Clipboard, Command, Engine, InputEvent, Position, TextOps};
+    ///
+    /// trait ApplyCommand {
+    ///     fn apply(&mut self, cmd: &Command);
+    /// }
+    ///
+    /// fn on_input<B: TextOps + ApplyCommand, C: Clipboard>(
+    ///     engine: &mut Engine,
+    ///     buffer: &mut B,
+    ///     clipboard: &mut C,
+    ///     cursor: &mut Position,
+    ///     input: InputEvent,
+    /// ) {
+    ///     let (next, commands) = engine.handle_event(buffer, clipboard, *cursor, input);
+    ///     commands.iter().for_each(|cmd| buffer.apply(cmd));
+    ///     *cursor = next;
+    ///
+    ///     while let Some((next, commands)) = engine.step_replay(buffer, clipboard, *cursor) {
+    ///         commands.iter().for_each(|cmd| buffer.apply(cmd));
+    ///         *cursor = next;
+    ///     }
+    /// }
+    /// ```
+    pub fn step_replay<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+    ) -> Option<(Position, Vec<Command>)> {
+        let input = match self.replay.pop_front()? {
+            ReplayKey::Event(input) => input,
+            ReplayKey::Text(ch) => key::char_to_event(
+                ch,
+                matches!(
+                    self.mode,
+                    Mode::Insert | Mode::Replace | Mode::SearchPrompt | Mode::CommandLine
+                ),
+            ),
+        };
+        Some(self.process(text, clipboard, cursor, input))
+    }
+
+    /// True while keys queued by `@{reg}` are waiting for [`Engine::step_replay`].
+    pub fn is_replaying(&self) -> bool {
+        !self.replay.is_empty()
+    }
+
+    /// Handle one key, whether typed or replayed from a macro.
+    fn process<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        input: InputEvent,
+    ) -> (Position, Vec<Command>) {
+        // Ensure cursor is within valid bounds before processing
+        let cursor = text.clamp(cursor);
+
+        let mode_before = self.mode;
+        let count_before = self.counts.current;
+        let anchor_before = self.visual_anchor;
+
+        match (mode_before, &input) {
+            (Mode::Normal, InputEvent::Key(ke))
+                if ke.code == KeyCode::Char('.')
+                    && self.pending == PendingKey::None
+                    && self.op_pending.is_none() =>
+            {
+                let (new_cursor, cmds) = self.repeat_last_change(text, clipboard, cursor);
+                #[cfg(feature = "undo")]
+                self.history.record(text, cursor, &cmds, false);
+                self.marks.record_edits(&cmds, false);
+                return (new_cursor, cmds);
+            }
+            (Mode::Normal | Mode::Visual(_), InputEvent::Key(_)) => {
+                if self.is_idle() {
+                    self.change_keys.clear();
+                }
+                self.change_keys.push(input.clone());
+            }
+            _ => {}
+        }
+
+        let register_before = self.register;
+        // Typing in insert mode extends the change its first key started
+        let extend = typing(mode_before) && !self.inserted.is_empty();
+        let (new_cursor, cmds) = self.dispatch(text, clipboard, cursor, input.clone());
+        if !typing(mode_before) && typing(self.mode) {
+            self.insert_start = Some(new_cursor);
+        }
+        // Undo and redo are not changes of their own
+        #[cfg(feature = "undo")]
+        let undone = std::mem::take(&mut self.undoing);
+        #[cfg(feature = "undo")]
+        if !undone {
+            // An insert session, or ':s///c' answered match by match, is one undo step
+            let keep_open = matches!(self.mode, Mode::Insert | Mode::Replace | Mode::Confirm);
+            self.history.record(text, cursor, &cmds, keep_open);
+        }
+        #[cfg(not(feature = "undo"))]
+        let undone = false;
+        if let (Mode::Visual(kind), Some(anchor)) = (mode_before, anchor_before)
+            && !matches!(self.mode, Mode::Visual(_))
+        {
+            let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+            let (start, end) = match kind {
+                VisualKind::CharWise | VisualKind::Block => (start, end),
+                VisualKind::LineWise => (text.line_start(start.line), text.line_end(end.line)),
+            };
+            self.marks.set('<', start);
+            self.marks.set('>', end);
+            self.last_visual = Some(LastVisual {
+                kind,
+                cursor_at_start: cursor < anchor,
+                eol: self.block_eol,
+            });
+        }
+        self.marks.record_edits(&cmds, extend);
+        if register_before.is_some()
+            && self.pending == PendingKey::None
+            && self.op_pending.is_none()
+            && self.counts.current.is_none()
+        {
+            // A register only applies to the command that follows it
+            self.register = None;
+        }
+
+        match (mode_before, &input) {
+            (Mode::Normal | Mode::Visual(_), InputEvent::Key(ke)) => {
+                if let KeyCode::Char(c) = ke.code
+                    && c.is_ascii_digit()
+                    && self.counts.current.is_some()
+                    && self.counts.current != count_before
+                {
+                    // Count digits are stored separately so `.` can replace them
+                    self.change_keys.pop();
+                }
+                let edited = cmds
+                    .iter()
+                    .any(|c| matches!(c, Command::Delete { .. } | Command::InsertText { .. }));
+                if (edited && !undone) || typing(self.mode) {
+                    let visual = match (mode_before, anchor_before) {
+                        (Mode::Visual(kind), Some(anchor)) => {
+                            let (start, end) = if anchor <= cursor {
+                                (anchor, cursor)
+                            } else {
+                                (cursor, anchor)
+                            };
+                            let lines = end.line - start.line;
+                            let cols = if kind == VisualKind::Block {
+                                // Blocks repeat with the same width on screen
+                                let block = self.block(text, anchor, cursor);
+                                block.right.saturating_sub(block.left)
+                            } else if lines == 0 {
+                                end.col - start.col
+                            } else {
+                                end.col
+                            };
+                            let eol = kind == VisualKind::Block && self.block_eol;
+                            Some(VisualExtent {
+                                kind,
+                                lines,
+                                cols,
+                                eol,
+                            })
+                        }
+                        _ => None,
+                    };
+                    let change = LastChange {
+                        keys: std::mem::take(&mut self.change_keys),
+                        count: count_before,
+                        visual,
+                        inserted: None,
+                    };
+                    if typing(self.mode) {
+                        self.insert_change = Some(change);
+                        self.inserted.clear();
+                    } else {
+                        self.last_change = Some(change);
+                    }
+                }
+            }
+            (Mode::Insert | Mode::Replace, InputEvent::Key(_)) if !typing(self.mode) => {
+                self.marks.set('^', cursor);
+                self.finish_insert();
+            }
+            _ => {}
+        }
+
+        (new_cursor, cmds)
+    }
+
+    /// End the insert session: the typed text goes to the `.` register and
+    /// completes the change that started the session.
+    fn finish_insert(&mut self) {
+        let inserted = std::mem::take(&mut self.inserted);
+        let change = match self.insert_change.take() {
+            Some(change) => change,
+            // Text typed after an arrow key repeats as an insert
+            None if !inserted.is_empty() => LastChange {
+                keys: vec![InputEvent::Key(KeyEvent {
+                    code: KeyCode::Char(if self.mode == Mode::Replace { 'R' } else { 'i' }),
+                    mods: Modifiers::empty(),
+                })],
+                count: None,
+                visual: None,
+                inserted: None,
+            },
+            None => return,
+        };
+        self.open_copies = None;
+        self.registers.set_last_inserted(&inserted);
+        self.last_change = Some(LastChange {
+            inserted: Some(inserted),
+            ..change
+        });
+    }
+
+    /// Move the cursor in insert mode. Like Vim, this ends the insert and
+    /// starts a new one at `pos`, so undo and `.` see the two apart.
+    fn restart_insert(&mut self, pos: Position) -> (Position, Vec<Command>) {
+        self.finish_insert();
+        self.block_insert.clear();
+        self.insert_start = Some(pos);
+        #[cfg(feature = "undo")]
+        self.history.close();
+        (pos, vec![Command::SetCursor(pos)])
+    }
+
+    /// The key in normal and visual mode that a navigation key stands for,
+    /// such as `h` for Left and Ctrl-F for Page Down.
+    fn command_key(&mut self, ke: KeyEvent) -> KeyEvent {
+        let key = |code| KeyEvent {
+            code,
+            mods: Modifiers::empty(),
+        };
+        let plain = |c| key(KeyCode::Char(c));
+        let ctrl = |c| KeyEvent {
+            code: KeyCode::Char(c),
+            mods: Modifiers::CTRL,
+        };
+        let visual = matches!(self.mode, Mode::Visual(_));
+        let word = ke.mods.intersects(Modifiers::SHIFT | Modifiers::CTRL);
+        let waits_for_char = matches!(
+            self.pending,
+            PendingKey::Register
+                | PendingKey::Record
+                | PendingKey::Execute
+                | PendingKey::F { .. }
+                | PendingKey::SetMark
+                | PendingKey::Mark { .. }
+                | PendingKey::Object { .. }
+                | PendingKey::Replace
+                | PendingKey::Bracket { .. }
+        );
+        match ke.code {
+            KeyCode::Char(_) | KeyCode::Esc | KeyCode::Enter => ke,
+            KeyCode::Tab if self.pending == PendingKey::Replace => ke,
+            // Not a character: cancels a command waiting for one
+            _ if waits_for_char => key(KeyCode::Esc),
+            KeyCode::Left if word => plain('b'),
+            KeyCode::Right if word => plain('w'),
+            KeyCode::Left | KeyCode::Backspace => plain('h'),
+            KeyCode::Right => plain('l'),
+            KeyCode::Up => plain('k'),
+            KeyCode::Down => plain('j'),
+            KeyCode::Home => {
+                // A count would take the 0 as a digit
+                self.counts.current = None;
+                plain('0')
+            }
+            KeyCode::End => plain('$'),
+            KeyCode::PageUp => ctrl('b'),
+            KeyCode::PageDown => ctrl('f'),
+            KeyCode::Tab => ctrl('i'),
+            KeyCode::Delete if visual => plain('d'),
+            KeyCode::Delete => plain('x'),
+            KeyCode::Insert if !visual => plain('i'),
+            KeyCode::Insert | KeyCode::F(_) => ke,
+        }
+    }
+
+    /// Replay the last change at the cursor (`.`).
+    ///
+    /// A count given to `.` replaces the count of the original command and is
+    /// remembered for further repeats.
+    fn repeat_last_change<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+    ) -> (Position, Vec<Command>) {
+        let count = self.counts.current.take();
+        let Some(change) = self.last_change.as_mut() else {
+            return (cursor, vec![]);
+        };
+        if count.is_some() {
+            change.count = count;
+        }
+        change.next_numbered_put();
+        let change = change.clone();
+
+        let mut cur = cursor;
+        let mut cmds = Vec::new();
+        if let Some(extent) = change.visual {
+            // Reselect an area of the same size starting at the cursor
+            let end = if extent.kind == VisualKind::Block {
+                let line = (cursor.line + extent.lines).min(text.line_count().saturating_sub(1));
+                let tabstop = self.options.tabstop;
+                let vcol = scan::display_col(text, cursor, tabstop) + extent.cols;
+                Position {
+                    line,
+                    col: scan::col_at_display(text, line, vcol, tabstop),
+                }
+            } else if extent.lines == 0 {
+                text.move_right(cursor, extent.cols)
+            } else {
+                let line = (cursor.line + extent.lines).min(text.line_count().saturating_sub(1));
+                Position {
+                    line,
+                    col: extent.cols.min(text.line_len(line).saturating_sub(1)),
+                }
+            };
+            self.mode = Mode::Visual(extent.kind);
+            self.visual_anchor = Some(cursor);
+            self.block_eol = extent.eol;
+            cur = end;
+        } else {
+            self.counts.current = change.count;
+        }
+
+        for key in change.keys {
+            let (next, out) = self.dispatch(text, clipboard, cur, key);
+            cur = next;
+            cmds.extend(out);
+        }
+
+        let inserted = change.inserted.filter(|s| !s.is_empty());
+        match self.mode {
+            Mode::Insert => {
+                let auto_indent = self.auto_indent.take();
+                if let Some(inserted) = &inserted {
+                    cmds.push(Command::InsertText {
+                        at: cur,
+                        text: inserted.clone(),
+                    });
+                    cur = Self::advance_over(cur, inserted);
+                    cmds.extend(self.copy_block_insert(inserted));
+                } else if let Some(end) = auto_indent {
+                    let (start, delete) = self.drop_auto_indent(end);
+                    cmds.push(delete);
+                    cur = start;
+                }
+                let inserted = inserted.as_deref().unwrap_or_default();
+                if let Some((last, copy)) = self.copy_open_lines(cur, inserted) {
+                    cmds.push(copy);
+                    cur = last;
+                }
+                self.block_insert.clear();
+                self.mode = Mode::Normal;
+            }
+            Mode::Replace => {
+                if let Some(inserted) = inserted {
+                    let typed = inserted.clone() + &self.replace_copies(&inserted);
+                    cmds.extend(replace::overwrite(text, cur, &typed));
+                    cur = Self::advance_over(cur, &typed);
+                }
+                self.replace_copies = 0;
+                self.replace_origins.clear();
+                self.mode = Mode::Normal;
+            }
+            _ => {}
+        }
+        self.counts.current = None;
+        (cur, cmds)
+    }
+
+    /// A key in insert mode.
+    fn insert_key<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        ke: KeyEvent,
+    ) -> (Position, Vec<Command>) {
+        let ctrl = ke.mods.contains(Modifiers::CTRL);
+        // Only Esc, Enter and moving to another line remove the indent again
+        let auto_indent = self
+            .auto_indent
+            .take()
+            .filter(|&end| end == cursor && text.line_len(cursor.line) == cursor.col);
+        match (ke.code, ctrl) {
+            (KeyCode::Esc, _) => {
+                self.mode = Mode::Normal;
+                let mut cmds = Vec::new();
+                let mut cursor = cursor;
+                if let Some(end) = auto_indent {
+                    let (start, delete) = self.drop_auto_indent(end);
+                    cmds.push(delete);
+                    cursor = start;
+                }
+                let inserted = self.inserted.clone();
+                cmds.extend(self.copy_block_insert(&inserted));
+                match self.copy_open_lines(cursor, &inserted) {
+                    Some((last, copy)) => {
+                        cmds.push(copy);
+                        (last, cmds)
+                    }
+                    None => (cursor, cmds),
+                }
+            }
+            (KeyCode::Enter, _) | (KeyCode::Char('j' | 'm'), true) => {
+                let (blanks, typed) = insert::line_break(text, cursor, &self.options);
+                let mut cmds = Vec::new();
+                let mut at = cursor;
+                if let Some(end) = auto_indent {
+                    let (start, delete) = self.drop_auto_indent(end);
+                    cmds.push(delete);
+                    at = start;
+                }
+                if blanks > 0 {
+                    let end = text.move_right(cursor, blanks);
+                    cmds.push(Command::Delete {
+                        range: Range { start: cursor, end },
+                    });
+                }
+                let moves_text = cursor.col + blanks < text.line_len(cursor.line);
+                let (new_cursor, insert) = self.insert_typed(at, typed);
+                cmds.extend(insert);
+                if !moves_text && new_cursor.col > 0 {
+                    self.auto_indent = Some(new_cursor);
+                }
+                (new_cursor, cmds)
+            }
+            (KeyCode::Tab, _) | (KeyCode::Char('i'), true) => {
+                let typed = insert::tab(text, cursor, &self.options);
+                self.insert_typed(cursor, typed)
+            }
+            (KeyCode::Left, _) if cursor.col > 0 => {
+                self.preferred_col = None;
+                self.restart_insert(text.move_left(cursor, 1))
+            }
+            (KeyCode::Right, _) if cursor.col < text.line_len(cursor.line) => {
+                self.preferred_col = None;
+                self.restart_insert(text.move_right(cursor, 1))
+            }
+            (KeyCode::Home, _) => {
+                self.preferred_col = None;
+                self.restart_insert(text.line_start(cursor.line))
+            }
+            (KeyCode::End, _) => {
+                self.preferred_col = None;
+                self.restart_insert(Position {
+                    line: cursor.line,
+                    col: text.line_len(cursor.line),
+                })
+            }
+            (KeyCode::Up, _) if cursor.line > 0 => {
+                self.insert_line_move(text, cursor, cursor.line - 1, auto_indent)
+            }
+            (KeyCode::Down, _) if cursor.line + 1 < text.line_count() => {
+                self.insert_line_move(text, cursor, cursor.line + 1, auto_indent)
+            }
+            (KeyCode::Backspace, _) | (KeyCode::Char('h'), true) => {
+                let from = insert::char_before(text, cursor);
+                self.delete_before(text, cursor, from, false)
+            }
+            (KeyCode::Char('w'), true) => {
+                let from = insert::word_before(text, cursor);
+                self.delete_before(text, cursor, from, true)
+            }
+            (KeyCode::Char('u'), true) => {
+                let from = insert::line_before(text, cursor);
+                self.delete_before(text, cursor, from, true)
+            }
+            (KeyCode::Insert, _) => {
+                self.mode = Mode::Replace;
+                self.replace_origins = vec![replace::Origin::new(text, cursor)];
+                (cursor, vec![])
+            }
+            (KeyCode::Delete, _) => {
+                let end = if cursor.col < text.line_len(cursor.line) {
+                    text.move_right(cursor, 1)
+                } else if cursor.line + 1 < text.line_count() {
+                    text.line_start(cursor.line + 1)
+                } else {
+                    return (cursor, vec![]);
+                };
+                (
+                    cursor,
+                    vec![Command::Delete {
+                        range: Range { start: cursor, end },
+                    }],
+                )
+            }
+            _ => (cursor, vec![]),
+        }
+    }
+
+    /// A key in replace mode. Keys that neither type nor delete work as in
+    /// insert mode, and replacing starts afresh where they leave the cursor.
+    fn replace_key<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        ke: KeyEvent,
+    ) -> (Position, Vec<Command>) {
+        let ctrl = ke.mods.contains(Modifiers::CTRL);
+        match (ke.code, ctrl) {
+            (KeyCode::Esc, _) => {
+                self.mode = Mode::Normal;
+                self.replace_origins.clear();
+                let typed = self.replace_copies(&self.inserted.clone());
+                let cmds = replace::overwrite(text, cursor, &typed);
+                (Self::advance_over(cursor, &typed), cmds)
+            }
+            (KeyCode::Insert, _) => {
+                self.mode = Mode::Insert;
+                self.replace_origins.clear();
+                self.replace_copies = 0;
+                (cursor, vec![])
+            }
+            (KeyCode::Tab, _) | (KeyCode::Char('i'), true) => {
+                let typed = insert::tab(text, cursor, &self.options);
+                self.replace_typed(text, cursor, typed)
+            }
+            (KeyCode::Enter, _) | (KeyCode::Char('j' | 'm'), true) => {
+                // A line break is inserted, not typed over a character
+                let (blanks, typed) = insert::line_break(text, cursor, &self.options);
+                let end = Position {
+                    line: cursor.line,
+                    col: cursor.col + blanks,
+                };
+                let line_end = Position {
+                    line: cursor.line,
+                    col: text.line_len(cursor.line),
+                };
+                let removed = text.slice_to_string(Range { start: cursor, end });
+                let rest = text.slice_to_string(Range {
+                    start: end,
+                    end: line_end,
+                });
+                let mut cmds = Vec::new();
+                if blanks > 0 {
+                    cmds.push(Command::Delete {
+                        range: Range { start: cursor, end },
+                    });
+                }
+                let (new_cursor, insert) = self.insert_typed(cursor, typed);
+                cmds.extend(insert);
+                self.replace_origins.push(replace::Origin::after_break(
+                    new_cursor, cursor, removed, &rest,
+                ));
+                (new_cursor, cmds)
+            }
+            (KeyCode::Backspace, _) | (KeyCode::Char('h'), true) => {
+                let restored = self
+                    .replace_origins
+                    .last()
+                    .and_then(|origin| origin.backspace(cursor));
+                let Some((pos, cmds)) = restored else {
+                    // Before where replacing started Backspace only moves
+                    let pos = text.move_left(cursor, 1);
+                    return (pos, vec![Command::SetCursor(pos)]);
+                };
+                if pos.line < cursor.line {
+                    self.replace_origins.pop();
+                }
+                // Forget what was taken back, so `.` repeats only what is left
+                let deleted = text.slice_to_string(Range {
+                    start: pos,
+                    end: cursor,
+                });
+                match self.inserted.strip_suffix(deleted.as_str()) {
+                    Some(rest) => self.inserted.truncate(rest.len()),
+                    None => self.inserted.clear(),
+                }
+                (pos, cmds)
+            }
+            _ => {
+                let (pos, cmds) = self.insert_key(text, cursor, ke);
+                let edited = cmds
+                    .iter()
+                    .any(|c| matches!(c, Command::Delete { .. } | Command::InsertText { .. }));
+                if edited {
+                    self.replace_origins.clear();
+                } else if pos != cursor {
+                    self.replace_origins = vec![replace::Origin::new(text, pos)];
+                }
+                (pos, cmds)
+            }
+        }
+    }
+
+    /// Overwrite the text at the cursor with `typed` in replace mode.
+    fn replace_typed<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        typed: String,
+    ) -> (Position, Vec<Command>) {
+        let cmds = replace::overwrite(text, cursor, &typed);
+        self.inserted.push_str(&typed);
+        (Self::advance_over(cursor, &typed), cmds)
+    }
+
+    /// Replace `count` characters at the cursor with the character typed
+    /// after `r`. Enter replaces them with a single line break.
+    fn replace_chars<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        code: KeyCode,
+        count: u32,
+    ) -> (Position, Vec<Command>) {
+        let ch = match code {
+            KeyCode::Char(ch) => Some(ch),
+            KeyCode::Tab => Some('\t'),
+            KeyCode::Enter => None,
+            _ => return (cursor, vec![]),
+        };
+        let end = Position {
+            line: cursor.line,
+            col: cursor.col + count,
+        };
+        if end.col > text.line_len(cursor.line) {
+            // Fewer characters than the count: nothing is replaced
+            self.fail();
+            return (cursor, vec![]);
+        }
+        self.preferred_col = None;
+        let Some(ch) = ch else {
+            let (blanks, typed) = insert::line_break(text, end, &self.options);
+            let range = Range {
+                start: cursor,
+                end: Position {
+                    line: end.line,
+                    col: end.col + blanks,
+                },
+            };
+            let pos = Self::advance_over(cursor, &typed);
+            let cmds = vec![
+                Command::Delete { range },
+                Command::InsertText {
+                    at: cursor,
+                    text: typed,
+                },
+            ];
+            return (pos, cmds);
+        };
+        let cmds = vec![
+            Command::Delete {
+                range: Range { start: cursor, end },
+            },
+            Command::InsertText {
+                at: cursor,
+                text: ch.to_string().repeat(count as usize),
+            },
+        ];
+        let last = Position {
+            line: cursor.line,
+            col: end.col - 1,
+        };
+        (last, cmds)
+    }
+
+    /// Join `count` lines from the cursor, at least two (`J`, `gJ`). A count
+    /// past the end joins the lines there are; on the last line it fails.
+    fn join_lines<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        spacing: Spacing,
+    ) -> (Position, Vec<Command>) {
+        let count = self.counts.take_or(1).max(2);
+        if cursor.line + 1 >= text.line_count() {
+            self.fail();
+            return (cursor, vec![]);
+        }
+        self.preferred_col = None;
+        join::join(text, cursor.line, cursor.line + count - 1, spacing)
+    }
+
+    /// Join the lines of a visual area; a single line is joined with the
+    /// next one.
+    fn visual_join<T: TextOps>(
+        &mut self,
+        text: &T,
+        anchor: Position,
+        cursor: Position,
+        spacing: Spacing,
+    ) -> (Position, Vec<Command>) {
+        let first = anchor.line.min(cursor.line);
+        let last = anchor.line.max(cursor.line).max(first + 1);
+        let (pos, cmds) = join::join(text, first, last, spacing);
+        self.end_visual(pos, cmds)
+    }
+
+    /// Up or Down in insert mode, keeping the column while lines are short.
+    /// An indent nothing was typed after is removed from the line left.
+    fn insert_line_move<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        line: u32,
+        auto_indent: Option<Position>,
+    ) -> (Position, Vec<Command>) {
+        let col = *self.preferred_col.get_or_insert(cursor.col);
+        let mut cmds = Vec::new();
+        if let Some(end) = auto_indent {
+            cmds.push(self.drop_auto_indent(end).1);
+        }
+        let (pos, moved) = self.restart_insert(Position {
+            line,
+            col: col.min(text.line_len(line)),
+        });
+        cmds.extend(moved);
+        (pos, cmds)
+    }
+
+    /// Insert `typed` at the cursor as text typed in insert mode.
+    fn insert_typed(&mut self, cursor: Position, typed: String) -> (Position, Vec<Command>) {
+        self.inserted.push_str(&typed);
+        let new_cursor = Self::advance_over(cursor, &typed);
+        (
+            new_cursor,
+            vec![Command::InsertText {
+                at: cursor,
+                text: typed,
+            }],
+        )
+    }
+
+    /// Delete from `from` to the cursor in insert mode (Backspace, Ctrl-W,
+    /// Ctrl-U). With `stop` the deletion stops once at the start of the
+    /// insert; without `backspace_start` it never goes past it.
+    fn delete_before<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        from: Option<Position>,
+        stop: bool,
+    ) -> (Position, Vec<Command>) {
+        let Some(mut from) = from else {
+            return (cursor, vec![]);
+        };
+        if let Some(start) = self.insert_start
+            && from < start
+        {
+            if start < cursor && (stop || !self.options.backspace_start) {
+                from = start;
+            } else if self.options.backspace_start {
+                self.insert_start = Some(from);
+            } else {
+                return (cursor, vec![]);
+            }
+        }
+        let range = Range {
+            start: from,
+            end: cursor,
+        };
+        // Forget the deleted part of the typed text, so `.` repeats what is left
+        let deleted = text.slice_to_string(range);
+        match self.inserted.strip_suffix(deleted.as_str()) {
+            Some(rest) => self.inserted.truncate(rest.len()),
+            None => self.inserted.clear(),
+        }
+        (from, vec![Command::Delete { range }])
+    }
+
+    /// Position just after `inserted` when it is inserted at `pos`.
+    fn advance_over(pos: Position, inserted: &str) -> Position {
+        match inserted.rsplit_once('\n') {
+            Some((before, last)) => Position {
+                line: pos.line + before.matches('\n').count() as u32 + 1,
+                col: last.graphemes(true).count() as u32,
+            },
+            None => Position {
+                line: pos.line,
+                col: pos.col + inserted.graphemes(true).count() as u32,
+            },
+        }
+    }
+
+    fn dispatch<T: TextOps, C: Clipboard>(
+        &mut self,
+        text: &T,
+        clipboard: &mut C,
+        cursor: Position,
+        input: InputEvent,
+    ) -> (Position, Vec<Command>) {
+        let input = match input {
+            InputEvent::Key(ke) if matches!(self.mode, Mode::Normal | Mode::Visual(_)) => {
+                InputEvent::Key(self.command_key(ke))
+            }
+            input => input,
+        };
+        match (&self.mode, input) {
+            (Mode::Insert, InputEvent::Key(ke)) => self.insert_key(text, cursor, ke),
+            (Mode::Insert, InputEvent::ReceivedChar(ch)) => {
+                self.auto_indent = None;
+                // Direct insertion; host applies this edit
+                let typed = match ch {
+                    '\t' => insert::tab(text, cursor, &self.options),
+                    ch => ch.to_string(),
+                };
+                self.insert_typed(cursor, typed)
+            }
+            (Mode::Replace, InputEvent::Key(ke)) => self.replace_key(text, cursor, ke),
+            (Mode::Replace, InputEvent::ReceivedChar(ch)) => {
+                let typed = match ch {
+                    '\t' => insert::tab(text, cursor, &self.options),
+                    ch => ch.to_string(),
+                };
+                self.replace_typed(text, cursor, typed)
+            }
+
+            (Mode::Normal, InputEvent::Key(ke)) => {
+                if ke.mods.contains(Modifiers::CTRL) {
+                    return self.ctrl_key(text, cursor, ke.code);
+                }
+                // Handle pending sequences
+                match (self.pending, ke.code) {
+                    (PendingKey::G, KeyCode::Char('&')) => {
+                        // Repeat the last ":s" with its flags on all lines
+                        self.clear_pending();
+                        self.clear_op();
+                        self.counts.current = None;
+                        return self.execute_ex(text, clipboard, cursor, "%s//~/&");
+                    }
+                    (PendingKey::G, KeyCode::Char(c @ (';' | ','))) => {
+                        // Older or newer position in the change list
+                        self.clear_pending();
+                        self.clear_op();
+                        let count = i64::from(self.counts.take_or(1));
+                        let count = if c == ';' { -count } else { count };
+                        return match self.marks.change_at(count) {
+                            Ok(pos) => {
+                                let pos = Self::on_char(text, pos);
+                                self.preferred_col = None;
+                                (pos, vec![Command::SetCursor(pos)])
+                            }
+                            Err(message) => {
+                                self.fail();
+                                (cursor, vec![Command::Error(message)])
+                            }
+                        };
+                    }
+                    (PendingKey::G, KeyCode::Char('v')) => {
+                        self.clear_pending();
+                        self.clear_op();
+                        self.counts.current = None;
+                        return self.reselect(text, cursor);
+                    }
+                    (PendingKey::G, KeyCode::Char(c @ ('~' | 'u' | 'U' | '?'))) => {
+                        let op = Case::from_key(c).map(Operator::Case);
+                        if self.op_pending.is_some() {
+                            // The second half of 'gugu' and the like
+                            if self.op_pending != op {
+                                self.clear_op();
+                            }
+                            return self.apply_to_count_lines(text, clipboard, cursor);
+                        }
+                        self.pending = PendingKey::Case(c); // to allow 'guu'
+                        self.op_pending = op;
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::G, KeyCode::Char('i')) => {
+                        // Insert where insert mode was last left
+                        self.clear_pending();
+                        self.clear_op();
+                        self.counts.current = None;
+                        self.mode = Mode::Insert;
+                        let pos = self.marks.get('^').map_or(cursor, |pos| text.clamp(pos));
+                        self.preferred_col = None;
+                        return (pos, vec![Command::SetCursor(pos)]);
+                    }
+                    (PendingKey::G, KeyCode::Char('J')) => {
+                        self.clear_pending();
+                        self.clear_op();
+                        return self.join_lines(text, cursor, Spacing::Keep);
+                    }
+                    (PendingKey::G, KeyCode::Char(c @ ('e' | 'E'))) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        let pos = if c == 'e' {
+                            text.prev_word_end(cursor, count)
+                        } else {
+                            text.prev_bigword_end(cursor, count)
+                        };
+                        self.preferred_col = None;
+                        if pos == cursor {
+                            self.clear_op();
+                            self.fail();
+                            return (cursor, vec![]);
+                        }
+                        if let Some(op) = self.op_pending.take() {
+                            // Inclusive: the character under the cursor goes too
+                            let end = text.move_right(cursor, 1);
+                            return self.apply_operator(op, text, clipboard, pos, end);
+                        }
+                        return (pos, vec![Command::SetCursor(pos)]);
+                    }
+                    (PendingKey::G, KeyCode::Char('g')) => {
+                        self.clear_pending();
+                        let count = self.counts.current.take();
+                        let target_line = match count {
+                            Some(n) if n > 0 => (n - 1).min(text.line_count().saturating_sub(1)),
+                            _ => 0,
+                        };
+                        if let Some(op) = self.op_pending.take() {
+                            // Linewise, from the cursor line to the target line
+                            let (first, last) =
+                                (cursor.line.min(target_line), cursor.line.max(target_line));
+                            return self.apply_lines(op, text, clipboard, first, last);
+                        }
+                        let pos = text.line_start(target_line);
+                        self.preferred_col = Some(0);
+                        self.jump(cursor);
+                        return (pos, vec![Command::SetCursor(pos)]);
+                    }
+                    (PendingKey::D, KeyCode::Char('d')) => {
+                        self.clear_pending();
+                        self.clear_op();
+                        let count = self.counts.take_or(1);
+                        // Delete current line and next (count-1) lines
+                        let start = text.line_start(cursor.line);
+                        let end_line =
+                            (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
+                        let end = text.line_end(end_line);
+                        // Include newline for line deletion
+                        let end_pos = Position {
+                            line: end.line + 1,
+                            col: 0,
+                        };
+                        let cmds = self.delete_range(text, clipboard, start, end_pos, true);
+                        return (start, cmds);
+                    }
+                    (PendingKey::Y, KeyCode::Char('y')) => {
+                        self.clear_pending();
+                        self.clear_op();
+                        let count = self.counts.take_or(1);
+                        // Yank current line and next (count-1) lines
+                        let start = text.line_start(cursor.line);
+                        let end_line =
+                            (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
+                        let end = text.line_end(end_line);
+                        // Include newline for line yanking
+                        let end_pos = Position {
+                            line: end.line + 1,
+                            col: 0,
+                        };
+                        self.yank_range(text, clipboard, start, end_pos, true);
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::Register, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        if registers::is_valid_register(name) {
+                            self.register = Some(name);
+                        }
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::Record, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        if registers::is_recording_register(name) {
+                            self.recording = Some(name);
+                            self.recorded.clear();
+                        }
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::Execute, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        if registers::is_valid_register(name) || name == '@' {
+                            self.queue_macro(clipboard, name, count);
+                        }
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::C, KeyCode::Char('c')) => {
+                        self.clear_pending();
+                        self.clear_op();
+                        let count = self.counts.take_or(1);
+                        return self.change_lines(
+                            text,
+                            clipboard,
+                            cursor.line,
+                            cursor.line + count - 1,
+                        );
+                    }
+                    (PendingKey::ShiftRight, KeyCode::Char('>'))
+                    | (PendingKey::ShiftLeft, KeyCode::Char('<'))
+                    | (PendingKey::Indent, KeyCode::Char('=')) => {
+                        return self.apply_to_count_lines(text, clipboard, cursor);
+                    }
+                    (PendingKey::Replace, code) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        return self.replace_chars(text, cursor, code, count);
+                    }
+                    (PendingKey::Case(key), KeyCode::Char(c)) if c == key => {
+                        return self.apply_to_count_lines(text, clipboard, cursor);
+                    }
+                    (PendingKey::Bracket { forward }, KeyCode::Char(c)) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        let Some(pos) = Self::unmatched_bracket(text, cursor, forward, c, count)
+                        else {
+                            self.clear_op();
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        if let Some(op) = self.op_pending.take() {
+                            return self.apply_operator(op, text, clipboard, cursor, pos);
+                        }
+                        self.preferred_col = None;
                         return (pos, vec![Command::SetCursor(pos)]);
                     }
-                    (PendingKey::D, KeyCode::Char('d')) => {
+                    (PendingKey::Z, KeyCode::Char(c @ ('t' | 'z' | 'b'))) => {
+                        // Scroll to show the line of the count, or the cursor
+                        // line, at the top, in the middle or at the bottom
                         self.clear_pending();
-                        self.clear_op();
-                        let count = self.counts.take_or(1);
-                        // Delete current line and next (count-1) lines
-                        let start = text.line_start(cursor.line);
-                        let end_line =
-                            (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
-                        let end = text.line_end(end_line);
-                        // Include newline for line deletion
-                        let end_pos = Position {
-                            line: end.line + 1,
-                            col: 0,
+                        let count = self.counts.current.take();
+                        let Some(view) = self.viewport else {
+                            self.fail();
+                            return (cursor, vec![]);
                         };
-                        let cmds = self.apply_delete(start, end_pos);
-                        return (start, cmds);
+                        let last = text.line_count().saturating_sub(1);
+                        let line = count.map_or(cursor.line, |n| n.saturating_sub(1).min(last));
+                        let pos = Self::on_char(
+                            text,
+                            Position {
+                                line,
+                                col: cursor.col,
+                            },
+                        );
+                        let top = scroll::place(view, line, c);
+                        let mut cmds = Vec::new();
+                        if top != view.top {
+                            self.viewport = Some(Viewport { top, ..view });
+                            cmds.push(Command::ScrollTo(top));
+                        }
+                        cmds.push(Command::SetCursor(pos));
+                        return (pos, cmds);
                     }
-                    (PendingKey::Y, KeyCode::Char('y')) => {
+                    (PendingKey::F { before, backward }, KeyCode::Char(ch)) => {
                         self.clear_pending();
-                        self.clear_op();
                         let count = self.counts.take_or(1);
-                        // Yank current line and next (count-1) lines
-                        let start = text.line_start(cursor.line);
-                        let end_line =
-                            (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
-                        let end = text.line_end(end_line);
-                        // Include newline for line yanking
-                        let end_pos = Position {
-                            line: end.line + 1,
-                            col: 0,
+                        let find = Find {
+                            ch,
+                            before,
+                            backward,
                         };
-                        self.yank_range(text, clipboard, start, end_pos, true);
+                        self.last_find = Some(find);
+                        let op = self.op_pending.take();
+                        return self.find_motion(text, clipboard, cursor, op, find, count, false);
+                    }
+                    (PendingKey::SetMark, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        if marks::is_settable_mark(name) {
+                            self.marks.set(name, cursor);
+                        }
                         return (cursor, vec![]);
                     }
-                    (PendingKey::F { before }, KeyCode::Char(ch)) => {
+                    (PendingKey::Mark { exact }, KeyCode::Char(name)) => {
                         self.clear_pending();
-                        let count = self.counts.take_or(1);
-                        if let Some(pos) = text.find_in_line(cursor, ch, before, count) {
-                            // If operator is pending, apply it
-                            if let Some(op) = self.op_pending {
-                                self.clear_op();
-                                let cmds = match op {
-                                    Operator::Delete => {
-                                        // For 'f', include the target char; for 't', stop before
-                                        let end =
-                                            if before { pos } else { text.move_right(pos, 1) };
-                                        self.apply_delete(cursor, end)
-                                    }
-                                    Operator::Yank => {
-                                        // For 'f', include the target char; for 't', stop before
-                                        let end =
-                                            if before { pos } else { text.move_right(pos, 1) };
-                                        self.yank_range(text, clipboard, cursor, end, false);
-                                        vec![]
-                                    }
-                                };
-                                return (cursor, cmds);
+                        self.counts.current = None;
+                        let Some(pos) = self.mark_position(text, name, exact) else {
+                            self.clear_op();
+                            return (cursor, vec![self.mark_error(name)]);
+                        };
+                        self.preferred_col = None;
+                        if let Some(op) = self.op_pending.take() {
+                            // "'" makes the motion linewise, '`' exclusive
+                            return if exact {
+                                self.apply_operator(op, text, clipboard, cursor, pos)
                             } else {
-                                // Just move
-                                let move_pos = if before {
-                                    // For 't', stop before the character
-                                    Position {
-                                        line: pos.line,
-                                        col: pos.col.saturating_sub(1),
-                                    }
+                                let (first, last) = if cursor.line <= pos.line {
+                                    (cursor.line, pos.line)
                                 } else {
-                                    // For 'f', move to the character
-                                    pos
+                                    (pos.line, cursor.line)
                                 };
-                                self.preferred_col = None;
-                                return (move_pos, vec![Command::SetCursor(move_pos)]);
-                            }
-                        } else {
-                            // Character not found, clear operator if any
-                            self.clear_op();
-                            return (cursor, vec![]);
+                                self.apply_lines(op, text, clipboard, first, last)
+                            };
                         }
+                        let pos = Self::on_char(text, pos);
+                        self.jump(cursor);
+                        return (pos, vec![Command::SetCursor(pos)]);
+                    }
+                    (PendingKey::Object { around }, KeyCode::Char(obj)) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        let Some(op) = self.op_pending.take() else {
+                            return (cursor, vec![]);
+                        };
+                        let Some(selected) =
+                            textobj::select(text, None, cursor, obj, count, around)
+                        else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        return self.apply_object(op, text, clipboard, cursor, selected);
                     }
                     _ => {
                         // Clear pending if not matched
@@ -374,9 +3378,18 @@ impl Engine {
                     }
                 }
 
+                // After an operator, 'i' and 'a' start a text object
+                if self.op_pending.is_some()
+                    && let KeyCode::Char(c @ ('i' | 'a')) = ke.code
+                {
+                    self.pending = PendingKey::Object { around: c == 'a' };
+                    return (cursor, vec![]);
+                }
+
                 // If operator is pending, next motion resolves a range
                 if let Some(op) = self.op_pending {
-                    let count = self.counts.take_or(1);
+                    let given = self.counts.current.take();
+                    let count = given.unwrap_or(1).max(1);
                     let mut end = cursor;
                     let mut handled = true;
 
@@ -401,45 +3414,158 @@ impl Engine {
                             // For line-end motion with operators, include the last character
                             end = text.move_right(end, 1);
                         }
+                        KeyCode::Char(c @ ('w' | 'W'))
+                            if op == Operator::Change
+                                && scan::Scanner::new(text, cursor).class(c == 'W')
+                                    != scan::CharClass::Blank =>
+                        {
+                            // Special case: on a non-blank "cw" behaves like "ce"
+                            end = scan::end_word(text, cursor, count, c == 'W', true)
+                                .map(|pos| text.move_right(pos, 1))
+                                .unwrap_or_else(|| {
+                                    let last = text.line_count().saturating_sub(1);
+                                    Position {
+                                        line: last,
+                                        col: text.line_len(last),
+                                    }
+                                });
+                        }
                         KeyCode::Char('w') => {
                             end = text.next_word_start(cursor, count);
                         }
                         KeyCode::Char('b') => {
                             end = text.prev_word_start(cursor, count);
                         }
+                        KeyCode::Char('W') => {
+                            end = text.next_bigword_start(cursor, count);
+                        }
+                        KeyCode::Char('B') => {
+                            end = text.prev_bigword_start(cursor, count);
+                        }
+                        KeyCode::Char(c @ ('e' | 'E')) => {
+                            let pos = if c == 'e' {
+                                text.next_word_end(cursor, count)
+                            } else {
+                                text.next_bigword_end(cursor, count)
+                            };
+                            // Inclusive: the last character of the word goes too
+                            if pos != cursor {
+                                end = text.move_right(pos, 1);
+                            }
+                        }
+                        KeyCode::Char('(') => {
+                            end = scan::find_sentence(text, cursor, false, count).unwrap_or(cursor);
+                        }
+                        KeyCode::Char(')') => {
+                            end = scan::find_sentence(text, cursor, true, count).unwrap_or(cursor);
+                        }
                         KeyCode::Char('{') => {
                             end = text.prev_paragraph_start(cursor, count);
                         }
                         KeyCode::Char('}') => {
                             end = text.next_paragraph_start(cursor, count);
                         }
-                        KeyCode::Char('f') => {
-                            // Enter pending state for f motion
-                            self.pending = PendingKey::F { before: false };
+                        KeyCode::Char('%') => {
+                            self.clear_op();
+                            let Some(pos) = Self::percent(text, cursor, given) else {
+                                self.fail();
+                                return (cursor, vec![]);
+                            };
+                            let (first, last) = (cursor.min(pos), cursor.max(pos));
+                            if given.is_some() {
+                                // Linewise to the line
+                                return self
+                                    .apply_lines(op, text, clipboard, first.line, last.line);
+                            }
+                            // Inclusive: both brackets go
+                            let end = text.move_right(last, 1);
+                            return self.apply_operator(op, text, clipboard, first, end);
+                        }
+                        KeyCode::Char('G') => {
+                            // Linewise, from the cursor line to line {count} or the last
+                            self.clear_op();
+                            let last = text.line_count().saturating_sub(1);
+                            let line = match given {
+                                Some(n) if n > 0 => (n - 1).min(last),
+                                _ => last,
+                            };
+                            let (first, last) = (cursor.line.min(line), cursor.line.max(line));
+                            return self.apply_lines(op, text, clipboard, first, last);
+                        }
+                        KeyCode::Char(c @ ('H' | 'M' | 'L')) => {
+                            // Linewise, from the cursor line to the screen line
+                            self.clear_op();
+                            let Some(pos) = self.screen_line(text, c, count) else {
+                                self.fail();
+                                return (cursor, vec![]);
+                            };
+                            let (first, last) =
+                                (cursor.line.min(pos.line), cursor.line.max(pos.line));
+                            return self.apply_lines(op, text, clipboard, first, last);
+                        }
+                        KeyCode::Char(c @ ('f' | 't' | 'F' | 'T')) => {
+                            // Enter pending state for the find motion, keeping the count
+                            self.pending = PendingKey::F {
+                                before: matches!(c, 't' | 'T'),
+                                backward: c.is_ascii_uppercase(),
+                            };
+                            self.counts.current = given;
                             handled = false;
                         }
-                        KeyCode::Char('t') => {
-                            // Enter pending state for t motion
-                            self.pending = PendingKey::F { before: true };
+                        KeyCode::Char(c @ (';' | ',')) => {
+                            self.clear_op();
+                            let Some(find) = self.repeated_find(c) else {
+                                self.fail();
+                                return (cursor, vec![]);
+                            };
+                            return self.find_motion(
+                                text,
+                                clipboard,
+                                cursor,
+                                Some(op),
+                                find,
+                                count,
+                                true,
+                            );
+                        }
+                        // Keys starting a motion that another key finishes
+                        KeyCode::Char('g' | '\'' | '`' | '[' | ']') => {
                             handled = false;
                         }
                         _ => {
-                            handled = false;
+                            // Not a motion: the operator is cancelled
+                            self.clear_op();
+                            if ke.code != KeyCode::Esc {
+                                self.fail();
+                            }
+                            return (cursor, vec![]);
                         }
                     }
 
+                    if handled
+                        && end == cursor
+                        && matches!(
+                            ke.code,
+                            KeyCode::Char('h' | 'j' | 'k' | 'b' | 'B' | 'e' | 'E' | '(' | ')')
+                        )
+                    {
+                        // The motion could not move: nothing to operate on
+                        self.clear_op();
+                        self.fail();
+                        return (cursor, vec![]);
+                    }
+                    if handled
+                        && (op.on_lines() || matches!(op, Operator::Case(_)))
+                        && matches!(ke.code, KeyCode::Char('j' | 'k'))
+                    {
+                        self.clear_op();
+                        let (first, last) = (cursor.line.min(end.line), cursor.line.max(end.line));
+                        return self.apply_lines(op, text, clipboard, first, last);
+                    }
                     if handled {
-                        let cmds = match op {
-                            Operator::Delete => self.apply_delete(cursor, end),
-                            Operator::Yank => {
-                                self.yank_range(text, clipboard, cursor, end, false);
-                                vec![]
-                            }
-                        };
                         self.clear_op();
-                        // Move cursor to start of deleted range
-                        let new_cursor = if cursor <= end { cursor } else { end };
-                        return (new_cursor, cmds);
+                        // Cursor moves to the start of the operated range
+                        return self.apply_operator(op, text, clipboard, cursor, end);
                     }
                     // If not handled, continue processing the key normally
                 }
@@ -450,24 +3576,36 @@ impl Engine {
                         let count = self.counts.take_or(1);
                         let pos = text.move_left(cursor, count);
                         self.preferred_col = None;
+                        if pos == cursor {
+                            self.fail();
+                        }
                         (pos, vec![Command::SetCursor(pos)])
                     }
                     KeyCode::Char('l') => {
                         let count = self.counts.take_or(1);
                         let pos = text.move_right(cursor, count);
                         self.preferred_col = None;
+                        if pos == cursor {
+                            self.fail();
+                        }
                         (pos, vec![Command::SetCursor(pos)])
                     }
                     KeyCode::Char('k') => {
                         let count = self.counts.take_or(1);
                         let pos = text.move_up(cursor, count, self.preferred_col);
                         self.preferred_col = Some(pos.col);
+                        if pos == cursor {
+                            self.fail();
+                        }
                         (pos, vec![Command::SetCursor(pos)])
                     }
                     KeyCode::Char('j') => {
                         let count = self.counts.take_or(1);
                         let pos = text.move_down(cursor, count, self.preferred_col);
                         self.preferred_col = Some(pos.col);
+                        if pos == cursor {
+                            self.fail();
+                        }
                         (pos, vec![Command::SetCursor(pos)])
                     }
                     KeyCode::Char('0') => {
@@ -494,6 +3632,7 @@ impl Engine {
                         };
                         let pos = text.line_start(target_line);
                         self.preferred_col = Some(0);
+                        self.jump(cursor);
                         (pos, vec![Command::SetCursor(pos)])
                     }
                     KeyCode::Char('d') => {
@@ -506,6 +3645,91 @@ impl Engine {
                         self.op_pending = Some(Operator::Yank);
                         (cursor, vec![])
                     }
+                    KeyCode::Char('"') => {
+                        self.pending = PendingKey::Register;
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('>') => {
+                        self.pending = PendingKey::ShiftRight; // to allow '>>'
+                        self.op_pending = Some(Operator::ShiftRight);
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('<') => {
+                        self.pending = PendingKey::ShiftLeft; // to allow '<<'
+                        self.op_pending = Some(Operator::ShiftLeft);
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('=') => {
+                        self.pending = PendingKey::Indent; // to allow '=='
+                        self.op_pending = Some(Operator::Indent);
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('q') => {
+                        self.counts.current = None;
+                        self.clear_op();
+                        if self.recording.is_some() {
+                            self.finish_recording();
+                        } else {
+                            self.pending = PendingKey::Record;
+                        }
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('@') => {
+                        self.pending = PendingKey::Execute;
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('c') => {
+                        self.pending = PendingKey::C; // to allow 'cc'
+                        self.op_pending = Some(Operator::Change);
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('C') => {
+                        let count = self.counts.take_or(1);
+                        let last_line =
+                            (cursor.line + count - 1).min(text.line_count().saturating_sub(1));
+                        let end = Position {
+                            line: last_line,
+                            col: text.line_len(last_line),
+                        };
+                        self.clear_op();
+                        self.preferred_col = None;
+                        self.apply_operator(Operator::Change, text, clipboard, cursor, end)
+                    }
+                    KeyCode::Char('s') => {
+                        let count = self.counts.take_or(1);
+                        let end = text.move_right(cursor, count);
+                        self.clear_op();
+                        self.apply_operator(Operator::Change, text, clipboard, cursor, end)
+                    }
+                    KeyCode::Char('S') => {
+                        let count = self.counts.take_or(1);
+                        self.clear_op();
+                        self.change_lines(text, clipboard, cursor.line, cursor.line + count - 1)
+                    }
+                    KeyCode::Char('~') if self.options.tildeop => {
+                        self.pending = PendingKey::Case('~'); // to allow '~~'
+                        self.op_pending = Some(Operator::Case(Case::Toggle));
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('~') => {
+                        let count = self.counts.take_or(1);
+                        let end = text.move_right(cursor, count);
+                        if end == cursor {
+                            self.fail();
+                            return (cursor, vec![]);
+                        }
+                        let range = Range { start: cursor, end };
+                        let new = case::convert(&text.slice_to_string(range), Case::Toggle);
+                        let width = new.graphemes(true).count() as u32;
+                        let len = text.line_len(cursor.line) - (end.col - cursor.col) + width;
+                        // The cursor moves past the changed text but stays on the line
+                        let pos = Position {
+                            line: cursor.line,
+                            col: (cursor.col + width).min(len - 1),
+                        };
+                        self.preferred_col = None;
+                        (pos, Self::map_ranges(text, &[range], |_| new.clone()))
+                    }
                     KeyCode::Char('x') => {
                         let count = self.counts.take_or(1);
                         // Delete character(s) under cursor
@@ -514,7 +3738,7 @@ impl Engine {
                             // Nothing to delete
                             return (cursor, vec![]);
                         }
-                        let cmds = self.apply_delete(cursor, end);
+                        let cmds = self.delete_range(text, clipboard, cursor, end, false);
                         (cursor, cmds)
                     }
                     KeyCode::Char('n') => {
@@ -527,9 +3751,11 @@ impl Engine {
                             };
                             if let Some(pos) = found {
                                 self.preferred_col = None;
+                                self.jump(cursor);
                                 return (pos, vec![Command::SetCursor(pos)]);
                             }
                         }
+                        self.fail();
                         (cursor, vec![])
                     }
                     KeyCode::Char('N') => {
@@ -543,61 +3769,90 @@ impl Engine {
                             };
                             if let Some(pos) = found {
                                 self.preferred_col = None;
+                                self.jump(cursor);
                                 return (pos, vec![Command::SetCursor(pos)]);
                             }
                         }
+                        self.fail();
                         (cursor, vec![])
                     }
                     KeyCode::Char('p') => {
                         let count = self.counts.take_or(1);
-                        if let Some(content) = clipboard.get() {
-                            let mut cmds = Vec::new();
-                            let mut insert_pos = if self.last_yank_was_line {
-                                // For linewise yank, paste on the next line
-                                Position {
-                                    line: cursor.line + 1,
-                                    col: 0,
-                                }
-                            } else {
-                                // For charwise yank, paste after cursor
-                                text.move_right(cursor, 1)
-                            };
-
-                            // Insert the content N times
-                            for _ in 0..count {
-                                cmds.push(Command::InsertText {
-                                    at: insert_pos,
-                                    text: content.clone(),
-                                });
-                                // Move insert position for next paste
-                                if self.last_yank_was_line {
-                                    let lines_in_content = content.matches('\n').count() as u32;
-                                    insert_pos.line += lines_in_content;
-                                } else {
-                                    let content_len = content.graphemes(true).count() as u32;
-                                    insert_pos.col += content_len;
-                                }
+                        match self.read_register(clipboard) {
+                            Some(reg) if reg.kind == RegisterKind::Block => {
+                                // A block goes after the cursor column, on the lines below
+                                let tabstop = self.options.tabstop;
+                                let next = Position {
+                                    line: cursor.line,
+                                    col: cursor.col + 1,
+                                };
+                                let vcol = scan::display_col(text, next, tabstop);
+                                let rows: Vec<&str> = reg.text.split('\n').collect();
+                                let slots: Vec<Slot> = (0..rows.len() as u32)
+                                    .map(|i| Slot::at(text, cursor.line + i, vcol, tabstop))
+                                    .collect();
+                                let last = text.line_count().saturating_sub(1);
+                                let end = Position {
+                                    line: last,
+                                    col: text.line_len(last),
+                                };
+                                let cmds = block::put(end, &slots, &rows, count, tabstop);
+                                (slots[0].start(), cmds)
                             }
+                            Some(reg) if !reg.text.is_empty() => {
+                                let linewise = reg.kind == RegisterKind::LineWise;
+                                let (mut insert_pos, piece) = if !linewise {
+                                    // For charwise text, paste after cursor
+                                    (text.move_right(cursor, 1), reg.text)
+                                } else if cursor.line + 1 < text.line_count() {
+                                    // For linewise text, paste on the next line
+                                    (
+                                        Position {
+                                            line: cursor.line + 1,
+                                            col: 0,
+                                        },
+                                        reg.text,
+                                    )
+                                } else {
+                                    // Last line has no newline to paste after: add one
+                                    let eol = Position {
+                                        line: cursor.line,
+                                        col: text.line_len(cursor.line),
+                                    };
+                                    (eol, format!("\n{}", reg.text.trim_end_matches('\n')))
+                                };
 
-                            // Cursor position depends on what was pasted
-                            let new_cursor = if self.last_yank_was_line {
-                                Position {
-                                    line: cursor.line + 1,
-                                    col: 0,
+                                // Insert the content N times
+                                let mut cmds = Vec::new();
+                                for _ in 0..count {
+                                    cmds.push(Command::InsertText {
+                                        at: insert_pos,
+                                        text: piece.clone(),
+                                    });
+                                    // Move insert position for next paste
+                                    insert_pos = Self::advance_over(insert_pos, &piece);
                                 }
-                            } else {
-                                text.move_right(cursor, 1)
-                            };
 
-                            (new_cursor, cmds)
-                        } else {
-                            // No clipboard content
-                            (cursor, vec![])
+                                // Cursor position depends on what was pasted
+                                let new_cursor = if linewise {
+                                    Position {
+                                        line: cursor.line + 1,
+                                        col: 0,
+                                    }
+                                } else {
+                                    text.move_right(cursor, 1)
+                                };
+
+                                (new_cursor, cmds)
+                            }
+                            // Empty register
+                            _ => (cursor, vec![]),
                         }
                     }
                     KeyCode::Char('v') => {
                         self.mode = Mode::Visual(VisualKind::CharWise);
                         self.visual_anchor = Some(cursor);
+                        self.block_eol = false;
                         self.clear_pending();
                         self.clear_op();
                         (
@@ -614,6 +3869,7 @@ impl Engine {
                         let start = text.line_start(cursor.line);
                         let end = text.line_end(cursor.line);
                         self.visual_anchor = Some(cursor);
+                        self.block_eol = false;
                         self.clear_pending();
                         self.clear_op();
                         (
@@ -629,49 +3885,229 @@ impl Engine {
                         let count = self.counts.take_or(1);
                         let pos = text.next_word_start(cursor, count);
                         self.preferred_col = None;
+                        if pos == cursor {
+                            self.fail();
+                        }
+                        (pos, vec![Command::SetCursor(pos)])
+                    }
+                    KeyCode::Char('b') => {
+                        let count = self.counts.take_or(1);
+                        let pos = text.prev_word_start(cursor, count);
+                        self.preferred_col = None;
+                        if pos == cursor {
+                            self.fail();
+                        }
+                        (pos, vec![Command::SetCursor(pos)])
+                    }
+                    KeyCode::Char(c @ ('e' | 'E' | 'W' | 'B')) => {
+                        let count = self.counts.take_or(1);
+                        let pos = match c {
+                            'e' => text.next_word_end(cursor, count),
+                            'E' => text.next_bigword_end(cursor, count),
+                            'W' => Self::on_char(text, text.next_bigword_start(cursor, count)),
+                            _ => text.prev_bigword_start(cursor, count),
+                        };
+                        self.preferred_col = None;
+                        if pos == cursor {
+                            self.fail();
+                        }
+                        (pos, vec![Command::SetCursor(pos)])
+                    }
+                    KeyCode::Char(c @ ('(' | ')')) => {
+                        let count = self.counts.take_or(1);
+                        self.preferred_col = None;
+                        match scan::find_sentence(text, cursor, c == ')', count) {
+                            Some(pos) if pos != cursor => {
+                                self.jump(cursor);
+                                (pos, vec![Command::SetCursor(pos)])
+                            }
+                            _ => {
+                                self.fail();
+                                (cursor, vec![])
+                            }
+                        }
+                    }
+                    KeyCode::Char('{') => {
+                        let count = self.counts.take_or(1);
+                        let pos = text.prev_paragraph_start(cursor, count);
+                        self.preferred_col = Some(0);
+                        if pos == cursor {
+                            self.fail();
+                        } else {
+                            self.jump(cursor);
+                        }
+                        (pos, vec![Command::SetCursor(pos)])
+                    }
+                    KeyCode::Char('}') => {
+                        let count = self.counts.take_or(1);
+                        let pos = text.next_paragraph_start(cursor, count);
+                        self.preferred_col = Some(0);
+                        if pos == cursor {
+                            self.fail();
+                        } else {
+                            self.jump(cursor);
+                        }
                         (pos, vec![Command::SetCursor(pos)])
                     }
-                    KeyCode::Char('b') => {
+                    KeyCode::Char(c @ ('f' | 't' | 'F' | 'T')) => {
+                        self.pending = PendingKey::F {
+                            before: matches!(c, 't' | 'T'),
+                            backward: c.is_ascii_uppercase(),
+                        };
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char(c @ (';' | ',')) => {
+                        let count = self.counts.take_or(1);
+                        let Some(find) = self.repeated_find(c) else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.find_motion(text, clipboard, cursor, None, find, count, true)
+                    }
+                    KeyCode::Char('m') => {
+                        self.pending = PendingKey::SetMark;
+                        self.clear_op();
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char(c @ ('\'' | '`')) => {
+                        self.pending = PendingKey::Mark { exact: c == '`' };
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('/') => {
+                        self.mode = Mode::SearchPrompt;
+                        self.search = Some(SearchState {
+                            query: String::new(),
+                            last_dir_forward: true,
+                        });
+                        self.prompt_col = 0;
+                        self.counts.current = None;
+                        self.pending = PendingKey::None;
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('&') => {
+                        // Repeat the last ":s" on the cursor line, without its flags
+                        self.counts.current = None;
+                        self.clear_op();
+                        self.execute_ex(text, clipboard, cursor, "s")
+                    }
+                    #[cfg(feature = "undo")]
+                    KeyCode::Char('u') => {
+                        let count = self.counts.take_or(1) as usize;
+                        self.clear_op();
+                        self.undo(cursor, count, false)
+                    }
+                    #[cfg(feature = "undo")]
+                    KeyCode::Char('U') => {
+                        self.counts.current = None;
+                        self.clear_op();
+                        match self.history.undo_line(text) {
+                            Some((pos, cmds)) => {
+                                self.preferred_col = None;
+                                (pos, cmds)
+                            }
+                            None => {
+                                self.fail();
+                                (cursor, vec![])
+                            }
+                        }
+                    }
+                    KeyCode::Char(':') => {
+                        // A count gives the range ".,.+{count-1}"
+                        let range = match self.counts.current.take() {
+                            None => String::new(),
+                            Some(1) => ".".to_string(),
+                            Some(n) => format!(".,.+{}", n - 1),
+                        };
+                        self.mode = Mode::CommandLine;
+                        self.prompt_col = range.len();
+                        self.command_line = Some(range);
+                        self.pending = PendingKey::None;
+                        self.clear_op();
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('i') => {
+                        self.mode = Mode::Insert;
+                        self.counts.current = None;
+                        self.pending = PendingKey::None;
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('R') => {
+                        self.mode = Mode::Replace;
+                        self.replace_copies = self.counts.take_or(1) - 1;
+                        self.pending = PendingKey::None;
+                        self.replace_origins = vec![replace::Origin::new(text, cursor)];
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char('r') => {
+                        self.clear_op();
+                        self.pending = PendingKey::Replace;
+                        (cursor, vec![])
+                    }
+                    KeyCode::Char(c @ ('H' | 'M' | 'L')) => {
                         let count = self.counts.take_or(1);
-                        let pos = text.prev_word_start(cursor, count);
+                        let Some(pos) = self.screen_line(text, c, count) else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
                         self.preferred_col = None;
+                        self.jump(cursor);
                         (pos, vec![Command::SetCursor(pos)])
                     }
-                    KeyCode::Char('{') => {
-                        let count = self.counts.take_or(1);
-                        let pos = text.prev_paragraph_start(cursor, count);
-                        self.preferred_col = Some(0);
-                        (pos, vec![Command::SetCursor(pos)])
+                    KeyCode::Char('z') => {
+                        self.clear_op();
+                        self.pending = PendingKey::Z;
+                        (cursor, vec![])
                     }
-                    KeyCode::Char('}') => {
-                        let count = self.counts.take_or(1);
-                        let pos = text.next_paragraph_start(cursor, count);
-                        self.preferred_col = Some(0);
+                    KeyCode::Char('%') => {
+                        let count = self.counts.current.take();
+                        let Some(pos) = Self::percent(text, cursor, count) else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        self.jump(cursor);
                         (pos, vec![Command::SetCursor(pos)])
                     }
-                    KeyCode::Char('f') => {
-                        self.pending = PendingKey::F { before: false };
-                        (cursor, vec![])
-                    }
-                    KeyCode::Char('t') => {
-                        self.pending = PendingKey::F { before: true };
+                    KeyCode::Char(c @ ('[' | ']')) => {
+                        self.pending = PendingKey::Bracket { forward: c == ']' };
                         (cursor, vec![])
                     }
-                    KeyCode::Char('/') => {
-                        self.mode = Mode::SearchPrompt;
-                        self.search = Some(SearchState {
-                            query: String::new(),
-                            last_dir_forward: true,
-                        });
-                        self.counts.current = None;
-                        self.pending = PendingKey::None;
-                        (cursor, vec![])
+                    KeyCode::Char('J') => {
+                        self.clear_op();
+                        let joinspaces = self.options.joinspaces;
+                        self.join_lines(text, cursor, Spacing::Adjust { joinspaces })
                     }
-                    KeyCode::Char('i') => {
+                    KeyCode::Char(c @ ('o' | 'O')) => {
                         self.mode = Mode::Insert;
-                        self.counts.current = None;
                         self.pending = PendingKey::None;
-                        (cursor, vec![])
+                        self.clear_op();
+                        let count = self.counts.take_or(1);
+                        let indent = insert::open_indent(text, cursor.line, &self.options);
+                        let width = indent.graphemes(true).count() as u32;
+                        let (at, opened, pos) = if c == 'o' {
+                            let at = Position {
+                                line: cursor.line,
+                                col: text.line_len(cursor.line),
+                            };
+                            let pos = Position {
+                                line: cursor.line + 1,
+                                col: width,
+                            };
+                            (at, format!("\n{indent}"), pos)
+                        } else {
+                            let at = text.line_start(cursor.line);
+                            let pos = Position {
+                                line: cursor.line,
+                                col: width,
+                            };
+                            (at, format!("{indent}\n"), pos)
+                        };
+                        if count > 1 {
+                            self.open_copies = Some((count - 1, indent));
+                        }
+                        self.auto_indent = (width > 0).then_some(pos);
+                        self.preferred_col = None;
+                        (pos, vec![Command::InsertText { at, text: opened }])
                     }
                     KeyCode::Char('a') => {
                         self.mode = Mode::Insert;
@@ -702,6 +4138,8 @@ impl Engine {
                     KeyCode::Esc => {
                         self.counts.current = None;
                         self.pending = PendingKey::None;
+                        self.register = None;
+                        self.clear_op();
                         self.preferred_col = None;
                         (cursor, vec![])
                     }
@@ -715,46 +4153,174 @@ impl Engine {
 
             (Mode::Visual(kind), InputEvent::Key(ke)) => {
                 let kind = *kind; // Copy to avoid borrow issues
+                if let (PendingKey::Register, KeyCode::Char(name)) = (self.pending, ke.code) {
+                    self.clear_pending();
+                    if registers::is_valid_register(name) {
+                        self.register = Some(name);
+                    }
+                    return (cursor, vec![]);
+                }
+                if let (PendingKey::Object { around }, KeyCode::Char(obj)) = (self.pending, ke.code)
+                {
+                    self.clear_pending();
+                    let count = self.counts.take_or(1);
+                    let anchor = self.visual_anchor.unwrap_or(cursor);
+                    let Some(selected) =
+                        textobj::select(text, Some(anchor), cursor, obj, count, around)
+                    else {
+                        self.fail();
+                        return (cursor, vec![]);
+                    };
+                    // Paragraphs select lines, the other objects characters
+                    let kind = if selected.linewise {
+                        VisualKind::LineWise
+                    } else {
+                        VisualKind::CharWise
+                    };
+                    self.mode = Mode::Visual(kind);
+                    self.visual_anchor = Some(selected.anchor);
+                    self.preferred_col = None;
+                    let selection = self.selection(text, kind, selected.anchor, selected.cursor);
+                    return (
+                        selected.cursor,
+                        vec![
+                            Command::SetCursor(selected.cursor),
+                            Command::SetSelection(Some(selection)),
+                        ],
+                    );
+                }
+                match (self.pending, ke.code) {
+                    (PendingKey::SetMark, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        if marks::is_settable_mark(name) {
+                            self.marks.set(name, cursor);
+                        }
+                        return (cursor, vec![]);
+                    }
+                    (PendingKey::G, KeyCode::Char('v')) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        return self.reselect(text, cursor);
+                    }
+                    (PendingKey::Bracket { forward }, KeyCode::Char(c)) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        let Some(pos) = Self::unmatched_bracket(text, cursor, forward, c, count)
+                        else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        return self.visual_move(text, kind, cursor, pos);
+                    }
+                    (PendingKey::F { before, backward }, KeyCode::Char(ch)) => {
+                        self.clear_pending();
+                        let count = self.counts.take_or(1);
+                        let find = Find {
+                            ch,
+                            before,
+                            backward,
+                        };
+                        self.last_find = Some(find);
+                        let Some(pos) = self.find_char(text, cursor, find, count, false) else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        return self.visual_move(text, kind, cursor, pos);
+                    }
+                    (PendingKey::G, KeyCode::Char('J')) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        let Some(anchor) = self.visual_anchor else {
+                            return (cursor, vec![]);
+                        };
+                        return self.visual_join(text, anchor, cursor, Spacing::Keep);
+                    }
+                    (PendingKey::G, KeyCode::Char('?')) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        let Some(anchor) = self.visual_anchor else {
+                            return (cursor, vec![]);
+                        };
+                        return self.visual_case(text, kind, anchor, cursor, Case::Rot13);
+                    }
+                    (PendingKey::Replace, code) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        let (KeyCode::Char(ch), Some(anchor)) = (code, self.visual_anchor) else {
+                            return (cursor, vec![]);
+                        };
+                        if kind == VisualKind::Block {
+                            return self.block_command(text, clipboard, anchor, cursor, ch);
+                        }
+                        // Every character, but not the line breaks
+                        let ranges = self.visual_ranges(text, kind, anchor, cursor);
+                        let cmds = Self::map_ranges(text, &ranges, |old| {
+                            ch.to_string().repeat(old.graphemes(true).count())
+                        });
+                        let pos = ranges.first().map_or(anchor.min(cursor), |r| r.start);
+                        return self.end_visual(pos, cmds);
+                    }
+                    (PendingKey::Mark { exact }, KeyCode::Char(name)) => {
+                        self.clear_pending();
+                        self.counts.current = None;
+                        let Some(pos) = self.mark_position(text, name, exact) else {
+                            return (cursor, vec![self.mark_error(name)]);
+                        };
+                        self.jump(cursor);
+                        self.preferred_col = None;
+                        let anchor = self.visual_anchor.unwrap_or(cursor);
+                        let selection = self.selection(text, kind, anchor, pos);
+                        return (
+                            pos,
+                            vec![
+                                Command::SetCursor(pos),
+                                Command::SetSelection(Some(selection)),
+                            ],
+                        );
+                    }
+                    _ => {}
+                }
+                if ke.mods.contains(Modifiers::CTRL) {
+                    return match ke.code {
+                        KeyCode::Char('v') => self.switch_visual(text, cursor, VisualKind::Block),
+                        KeyCode::Char(c @ ('d' | 'u' | 'f' | 'b' | 'e' | 'y')) => {
+                            self.clear_pending();
+                            let count = self.counts.current.take();
+                            let Some((pos, mut cmds)) = self.scroll(text, cursor, c, count) else {
+                                self.fail();
+                                return (cursor, vec![]);
+                            };
+                            let anchor = self.visual_anchor.unwrap_or(cursor);
+                            let selection = self.selection(text, kind, anchor, pos);
+                            cmds.push(Command::SetSelection(Some(selection)));
+                            (pos, cmds)
+                        }
+                        _ => (cursor, vec![]),
+                    };
+                }
+                if let KeyCode::Char(c) = ke.code
+                    && c.is_ascii_digit()
+                    && (c != '0' || self.counts.current.is_some())
+                {
+                    self.counts.push_digit((c as u8 - b'0') as u32);
+                    return (cursor, vec![]);
+                }
                 match ke.code {
                     KeyCode::Esc => {
                         self.mode = Mode::Normal;
                         self.visual_anchor = None;
+                        self.register = None;
                         self.clear_pending();
                         return (cursor, vec![Command::SetSelection(None)]);
                     }
-                    KeyCode::Char('v') if matches!(kind, VisualKind::CharWise) => {
-                        // Toggle off from charwise
-                        self.mode = Mode::Normal;
-                        self.visual_anchor = None;
-                        return (cursor, vec![Command::SetSelection(None)]);
+                    // Switch between charwise and linewise, or toggle off
+                    KeyCode::Char('v') => {
+                        return self.switch_visual(text, cursor, VisualKind::CharWise);
                     }
                     KeyCode::Char('V') => {
-                        // Switch to linewise or toggle off
-                        if matches!(kind, VisualKind::LineWise) {
-                            self.mode = Mode::Normal;
-                            self.visual_anchor = None;
-                            return (cursor, vec![Command::SetSelection(None)]);
-                        } else {
-                            self.mode = Mode::Visual(VisualKind::LineWise);
-                            // Update selection to line bounds
-                            if let Some(anchor) = self.visual_anchor {
-                                let (start_line, end_line) = if anchor.line <= cursor.line {
-                                    (anchor.line, cursor.line)
-                                } else {
-                                    (cursor.line, anchor.line)
-                                };
-                                let start = text.line_start(start_line);
-                                let end = text.line_end(end_line);
-                                return (
-                                    cursor,
-                                    vec![Command::SetSelection(Some(Selection {
-                                        start,
-                                        end,
-                                        kind: VisualKind::LineWise,
-                                    }))],
-                                );
-                            }
-                        }
+                        return self.switch_visual(text, cursor, VisualKind::LineWise);
                     }
                     KeyCode::Char('h')
                     | KeyCode::Char('j')
@@ -766,22 +4332,40 @@ impl Engine {
                     | KeyCode::Char('G')
                     | KeyCode::Char('w')
                     | KeyCode::Char('b')
+                    | KeyCode::Char('e')
+                    | KeyCode::Char('W')
+                    | KeyCode::Char('B')
+                    | KeyCode::Char('E')
                     | KeyCode::Char('{')
-                    | KeyCode::Char('}') => {
+                    | KeyCode::Char('}')
+                    | KeyCode::Char('(')
+                    | KeyCode::Char(')')
+                    | KeyCode::Char('H')
+                    | KeyCode::Char('M')
+                    | KeyCode::Char('L') => {
                         // Handle movement
                         let count = self.counts.take_or(1);
+                        // After '$' a block keeps reaching the end of each line
+                        self.block_eol = ke.code == KeyCode::Char('$')
+                            || (self.block_eol && matches!(ke.code, KeyCode::Char('j' | 'k')));
                         let new_cursor = match ke.code {
                             KeyCode::Char('h') => text.move_left(cursor, count),
                             KeyCode::Char('l') => text.move_right(cursor, count),
+                            KeyCode::Char('k') if self.block_eol => {
+                                text.line_end(text.move_up(cursor, count, None).line)
+                            }
+                            KeyCode::Char('j') if self.block_eol => {
+                                text.line_end(text.move_down(cursor, count, None).line)
+                            }
                             KeyCode::Char('k') => {
-                                let pos = text.move_up(cursor, count, self.preferred_col);
-                                self.preferred_col = Some(pos.col);
-                                pos
+                                // Keep the column over short lines, for blocks
+                                let col = *self.preferred_col.get_or_insert(cursor.col);
+                                text.move_up(cursor, count, Some(col))
                             }
                             KeyCode::Char('j') => {
-                                let pos = text.move_down(cursor, count, self.preferred_col);
-                                self.preferred_col = Some(pos.col);
-                                pos
+                                // Keep the column over short lines, for blocks
+                                let col = *self.preferred_col.get_or_insert(cursor.col);
+                                text.move_down(cursor, count, Some(col))
                             }
                             KeyCode::Char('0') => {
                                 self.preferred_col = Some(0);
@@ -828,6 +4412,35 @@ impl Engine {
                                 self.preferred_col = None;
                                 text.prev_word_start(cursor, count)
                             }
+                            KeyCode::Char(c @ ('e' | 'E')) if self.pending == PendingKey::G => {
+                                self.clear_pending();
+                                self.preferred_col = None;
+                                if c == 'e' {
+                                    text.prev_word_end(cursor, count)
+                                } else {
+                                    text.prev_bigword_end(cursor, count)
+                                }
+                            }
+                            KeyCode::Char('e') => {
+                                self.preferred_col = None;
+                                text.next_word_end(cursor, count)
+                            }
+                            KeyCode::Char('E') => {
+                                self.preferred_col = None;
+                                text.next_bigword_end(cursor, count)
+                            }
+                            KeyCode::Char('W') => {
+                                self.preferred_col = None;
+                                Self::on_char(text, text.next_bigword_start(cursor, count))
+                            }
+                            KeyCode::Char('B') => {
+                                self.preferred_col = None;
+                                text.prev_bigword_start(cursor, count)
+                            }
+                            KeyCode::Char(c @ ('(' | ')')) => {
+                                self.preferred_col = None;
+                                scan::find_sentence(text, cursor, c == ')', count).unwrap_or(cursor)
+                            }
                             KeyCode::Char('{') => {
                                 self.preferred_col = Some(0);
                                 text.prev_paragraph_start(cursor, count)
@@ -836,39 +4449,30 @@ impl Engine {
                                 self.preferred_col = Some(0);
                                 text.next_paragraph_start(cursor, count)
                             }
+                            KeyCode::Char(c @ ('H' | 'M' | 'L')) => {
+                                self.preferred_col = None;
+                                self.screen_line(text, c, count).unwrap_or(cursor)
+                            }
                             _ => cursor,
                         };
+                        if new_cursor != cursor
+                            && matches!(
+                                ke.code,
+                                KeyCode::Char('g' | 'G' | '{' | '}' | '(' | ')' | 'H' | 'M' | 'L')
+                            )
+                        {
+                            self.jump(cursor);
+                        }
+                        // Only the jumps to a line or an end cannot fail
+                        if new_cursor == cursor
+                            && !matches!(ke.code, KeyCode::Char('0' | '$' | 'g' | 'G'))
+                        {
+                            self.fail();
+                        }
 
                         // Update selection based on anchor and new cursor
                         if let Some(anchor) = self.visual_anchor {
-                            let selection = match kind {
-                                VisualKind::CharWise => {
-                                    let (start, end) = if anchor <= new_cursor {
-                                        (anchor, new_cursor)
-                                    } else {
-                                        (new_cursor, anchor)
-                                    };
-                                    Selection {
-                                        start,
-                                        end,
-                                        kind: VisualKind::CharWise,
-                                    }
-                                }
-                                VisualKind::LineWise => {
-                                    let (start_line, end_line) = if anchor.line <= new_cursor.line {
-                                        (anchor.line, new_cursor.line)
-                                    } else {
-                                        (new_cursor.line, anchor.line)
-                                    };
-                                    let start = text.line_start(start_line);
-                                    let end = text.line_end(end_line);
-                                    Selection {
-                                        start,
-                                        end,
-                                        kind: VisualKind::LineWise,
-                                    }
-                                }
-                            };
+                            let selection = self.selection(text, kind, anchor, new_cursor);
                             return (
                                 new_cursor,
                                 vec![
@@ -905,10 +4509,21 @@ impl Engine {
                                     };
                                     (start, end)
                                 }
+                                VisualKind::Block => {
+                                    return self
+                                        .block_command(text, clipboard, anchor, cursor, 'd');
+                                }
                             };
                             self.mode = Mode::Normal;
                             self.visual_anchor = None;
-                            let cmds = self.apply_delete(selection.0, selection.1);
+                            let is_linewise = matches!(kind, VisualKind::LineWise);
+                            let cmds = self.delete_range(
+                                text,
+                                clipboard,
+                                selection.0,
+                                selection.1,
+                                is_linewise,
+                            );
                             let mut result = cmds;
                             result.push(Command::SetSelection(None));
                             return (selection.0, result);
@@ -941,6 +4556,10 @@ impl Engine {
                                     };
                                     ((start, end), true)
                                 }
+                                VisualKind::Block => {
+                                    return self
+                                        .block_command(text, clipboard, anchor, cursor, 'y');
+                                }
                             };
                             self.yank_range(text, clipboard, selection.0, selection.1, is_linewise);
                             self.mode = Mode::Normal;
@@ -948,12 +4567,175 @@ impl Engine {
                             return (cursor, vec![Command::SetSelection(None)]);
                         }
                     }
-                    KeyCode::Char('f') => {
-                        self.pending = PendingKey::F { before: false };
+                    KeyCode::Char('c' | 's') => {
+                        if let Some(anchor) = self.visual_anchor {
+                            self.visual_anchor = None;
+                            let (start, end) = if anchor <= cursor {
+                                (anchor, cursor)
+                            } else {
+                                (cursor, anchor)
+                            };
+                            let (new_cursor, mut cmds) = match kind {
+                                VisualKind::CharWise => {
+                                    // For charwise visual, include the character under cursor
+                                    let end = text.move_right(end, 1);
+                                    self.apply_operator(
+                                        Operator::Change,
+                                        text,
+                                        clipboard,
+                                        start,
+                                        end,
+                                    )
+                                }
+                                VisualKind::LineWise => {
+                                    self.change_lines(text, clipboard, start.line, end.line)
+                                }
+                                VisualKind::Block => {
+                                    return self
+                                        .block_command(text, clipboard, anchor, cursor, 'c');
+                                }
+                            };
+                            cmds.push(Command::SetSelection(None));
+                            return (new_cursor, cmds);
+                        }
+                    }
+                    KeyCode::Char(c @ ('I' | 'A')) if kind == VisualKind::Block => {
+                        if let Some(anchor) = self.visual_anchor {
+                            return self.block_command(text, clipboard, anchor, cursor, c);
+                        }
+                    }
+                    KeyCode::Char(c @ ('p' | 'P')) => {
+                        if let Some(anchor) = self.visual_anchor {
+                            if kind == VisualKind::Block {
+                                return self.block_command(text, clipboard, anchor, cursor, c);
+                            }
+                            return self.visual_put(text, clipboard, kind, anchor, cursor, c);
+                        }
+                    }
+                    KeyCode::Char('r') => {
+                        self.pending = PendingKey::Replace;
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char(c @ ('o' | 'O')) => {
+                        if let Some(anchor) = self.visual_anchor {
+                            let (anchor, new_cursor) = if c == 'O' && kind == VisualKind::Block {
+                                // The other corner on the same line
+                                let tabstop = self.options.tabstop;
+                                let anchor_col = scan::display_col(text, anchor, tabstop);
+                                let cursor_col = scan::display_col(text, cursor, tabstop);
+                                let corner = |pos: Position, vcol| Position {
+                                    line: pos.line,
+                                    col: scan::col_at_display(text, pos.line, vcol, tabstop),
+                                };
+                                (corner(anchor, cursor_col), corner(cursor, anchor_col))
+                            } else {
+                                (cursor, anchor)
+                            };
+                            self.visual_anchor = Some(anchor);
+                            self.preferred_col = None;
+                            let selection = self.selection(text, kind, anchor, new_cursor);
+                            return (
+                                new_cursor,
+                                vec![
+                                    Command::SetCursor(new_cursor),
+                                    Command::SetSelection(Some(selection)),
+                                ],
+                            );
+                        }
+                    }
+                    KeyCode::Char(c @ ('~' | 'u' | 'U')) => {
+                        if let (Some(anchor), Some(case)) = (self.visual_anchor, Case::from_key(c))
+                        {
+                            return self.visual_case(text, kind, anchor, cursor, case);
+                        }
+                    }
+                    KeyCode::Char('J') => {
+                        if let Some(anchor) = self.visual_anchor {
+                            self.counts.current = None;
+                            let joinspaces = self.options.joinspaces;
+                            let spacing = Spacing::Adjust { joinspaces };
+                            return self.visual_join(text, anchor, cursor, spacing);
+                        }
+                    }
+                    KeyCode::Char(c @ ('>' | '<')) => {
+                        if let Some(anchor) = self.visual_anchor {
+                            let amount = self.counts.take_or(1);
+                            let (first, last) =
+                                (anchor.line.min(cursor.line), anchor.line.max(cursor.line));
+                            let (pos, cmds) = self.shift_lines(text, first, last, amount, c == '<');
+                            return self.end_visual(pos, cmds);
+                        }
+                    }
+                    KeyCode::Char('=') => {
+                        if let Some(anchor) = self.visual_anchor {
+                            self.counts.current = None;
+                            let (first, last) =
+                                (anchor.line.min(cursor.line), anchor.line.max(cursor.line));
+                            let (pos, cmds) = self.reindent_lines(text, first, last);
+                            return self.end_visual(pos, cmds);
+                        }
+                    }
+                    KeyCode::Char(':') => {
+                        // The command line starts with the range of the selected lines
+                        self.mode = Mode::CommandLine;
+                        let range = "'<,'>";
+                        self.prompt_col = range.len();
+                        self.command_line = Some(range.to_string());
+                        self.visual_anchor = None;
+                        self.counts.current = None;
+                        return (cursor, vec![Command::SetSelection(None)]);
+                    }
+                    KeyCode::Char('"') => {
+                        self.pending = PendingKey::Register;
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char('q') if self.recording.is_some() => {
+                        self.finish_recording();
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char(c @ ('i' | 'a')) => {
+                        self.pending = PendingKey::Object { around: c == 'a' };
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char('%') => {
+                        let count = self.counts.current.take();
+                        let Some(pos) = Self::percent(text, cursor, count) else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        self.jump(cursor);
+                        return self.visual_move(text, kind, cursor, pos);
+                    }
+                    KeyCode::Char(c @ ('[' | ']')) => {
+                        self.pending = PendingKey::Bracket { forward: c == ']' };
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char(c @ ('f' | 't' | 'F' | 'T')) => {
+                        self.pending = PendingKey::F {
+                            before: matches!(c, 't' | 'T'),
+                            backward: c.is_ascii_uppercase(),
+                        };
+                        return (cursor, vec![]);
+                    }
+                    KeyCode::Char(c @ (';' | ',')) => {
+                        let count = self.counts.take_or(1);
+                        let Some(pos) = self
+                            .repeated_find(c)
+                            .and_then(|find| self.find_char(text, cursor, find, count, true))
+                        else {
+                            self.fail();
+                            return (cursor, vec![]);
+                        };
+                        self.preferred_col = None;
+                        return self.visual_move(text, kind, cursor, pos);
+                    }
+                    KeyCode::Char('m') => {
+                        self.pending = PendingKey::SetMark;
                         return (cursor, vec![]);
                     }
-                    KeyCode::Char('t') => {
-                        self.pending = PendingKey::F { before: true };
+                    KeyCode::Char(c @ ('\'' | '`')) => {
+                        self.pending = PendingKey::Mark { exact: c == '`' };
                         return (cursor, vec![]);
                     }
                     _ => {
@@ -977,28 +4759,68 @@ impl Engine {
                             // Confirm search and move to first match
                             self.mode = Mode::Normal;
                             let query = search.query.clone();
+                            self.registers.set_last_search(&query);
                             self.last_search = self.search.take();
 
                             // Search for first match
                             if let Some(pos) = text.search_forward(cursor, &query, true) {
                                 self.preferred_col = None;
+                                self.jump(cursor);
                                 return (pos, vec![Command::SetCursor(pos)]);
                             }
+                            self.fail();
                             return (cursor, vec![]);
                         }
-                        KeyCode::Backspace => {
-                            // Remove last character from query
-                            search.query.pop();
+                        code => {
+                            prompt::edit(&mut search.query, &mut self.prompt_col, code);
                             return (cursor, vec![]);
                         }
-                        _ => return (cursor, vec![]),
                     }
                 }
                 (cursor, vec![])
             }
             (Mode::SearchPrompt, InputEvent::ReceivedChar(ch)) => {
                 if let Some(ref mut search) = self.search {
-                    search.query.push(ch);
+                    prompt::insert(&mut search.query, &mut self.prompt_col, ch);
+                }
+                (cursor, vec![])
+            }
+
+            (Mode::CommandLine, InputEvent::Key(ke)) => match ke.code {
+                KeyCode::Esc => {
+                    self.mode = Mode::Normal;
+                    self.command_line = None;
+                    (cursor, vec![])
+                }
+                KeyCode::Enter => {
+                    self.mode = Mode::Normal;
+                    let line = self.command_line.take().unwrap_or_default();
+                    if !line.trim().is_empty() {
+                        self.registers.set_last_command(&line);
+                    }
+                    self.execute_ex(text, clipboard, cursor, &line)
+                }
+                code => {
+                    match self.command_line.as_mut() {
+                        Some(line) if !line.is_empty() || code != KeyCode::Backspace => {
+                            prompt::edit(line, &mut self.prompt_col, code);
+                        }
+                        // Backspace on an empty command line leaves it
+                        _ => {
+                            self.mode = Mode::Normal;
+                            self.command_line = None;
+                        }
+                    }
+                    (cursor, vec![])
+                }
+            },
+            (Mode::Confirm, InputEvent::Key(ke)) => self.confirm_key(text, cursor, ke.code),
+            (Mode::Confirm, InputEvent::ReceivedChar(ch)) => {
+                self.confirm_key(text, cursor, KeyCode::Char(ch))
+            }
+            (Mode::CommandLine, InputEvent::ReceivedChar(ch)) => {
+                if let Some(line) = self.command_line.as_mut() {
+                    prompt::insert(line, &mut self.prompt_col, ch);
                 }
                 (cursor, vec![])
             }
@@ -1007,3 +4829,15 @@ impl Engine {
         }
     }
 }
+
+/// Whether keys typed in `mode` go into the text: insert and replace mode.
+fn typing(mode: Mode) -> bool {
+    matches!(mode, Mode::Insert | Mode::Replace)
+}
+
+/// Add an ex command to the host table, replacing one of the same name.
+fn add_ex_command(table: &mut Vec<ExName>, spec: &str) {
+    let name = ExName::parse(spec);
+    table.retain(|n| n.name != name.name);
+    table.push(name);
+}
@@ -0,0 +1,383 @@
+//! Ex command lines, as typed after `:`.
+//!
+//! A command line is an optional range, a name, an optional `!` and the
+//! arguments: `:[range]name[!] [args]`. The range is one or more addresses
+//! separated by `,` or `;`; only the last two are used.
+//!
+//! | Address  | Line |
+//! |----------|------|
+//! | `{n}`    | Line `n` |
+//! | `.`      | The cursor line |
+//! | `$`      | The last line |
+//! | `%`      | The whole buffer, same as `1,$` |
+//! | `'x`     | The line of mark `x` |
+//! | `/pat/`  | The next line containing `pat` |
+//! | `?pat?`  | The previous line containing `pat` |
+//!
+//! Each address may be followed by offsets such as `+2`, `-` or `3` (same as
+//! `+3`). With `;` the cursor moves to the previous address before the next
+//! one is looked up, so `/a/;/b/` finds the first `b` after the next `a`.
+
+use crate::traits::TextOps;
+use crate::types::{LineRange, Position};
+
+/// Where an address points before its offsets are applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Address {
+    Current,
+    Last,
+    Number(u32), // one-based, as typed
+    Mark(char),
+    Search { pattern: String, forward: bool },
+}
+
+/// One address of a range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LineSpec {
+    address: Address,
+    offset: i64,
+    set_cursor: bool, // followed by ';'
+}
+
+/// A command line split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Parsed<'a> {
+    pub(crate) range: Vec<LineSpec>,
+    pub(crate) name: &'a str,
+    pub(crate) bang: bool,
+    pub(crate) args: &'a str,
+}
+
+/// Built-in commands the engine runs itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Write,
+    Quit,
+    WriteQuit,
+    Edit,
+    Delete,
+    Yank,
+    Substitute,
+    SubstituteRepeat,
+}
+
+const BUILTINS: &[(&str, Builtin)] = &[
+    ("w[rite]", Builtin::Write),
+    ("q[uit]", Builtin::Quit),
+    ("wq", Builtin::WriteQuit),
+    ("x[it]", Builtin::WriteQuit),
+    ("e[dit]", Builtin::Edit),
+    ("d[elete]", Builtin::Delete),
+    ("y[ank]", Builtin::Yank),
+    ("s[ubstitute]", Builtin::Substitute),
+    ("&", Builtin::SubstituteRepeat),
+];
+
+/// A command name with the shortest abbreviation that is accepted, written
+/// the way Vim documents it: `"d[elete]"` accepts `d`, `de`, ... `delete`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExName {
+    pub(crate) name: String,
+    min: usize, // chars needed, 0 for any unambiguous prefix
+}
+
+impl ExName {
+    pub(crate) fn parse(spec: &str) -> Self {
+        match spec.split_once('[') {
+            Some((head, tail)) => Self {
+                name: format!("{head}{}", tail.trim_end_matches(']')),
+                min: head.chars().count(),
+            },
+            None => Self {
+                name: spec.to_string(),
+                min: 0,
+            },
+        }
+    }
+
+    /// True when `typed` is the name or an accepted abbreviation of it.
+    pub(crate) fn accepts(&self, typed: &str) -> bool {
+        !typed.is_empty() && self.name.starts_with(typed) && typed.chars().count() >= self.min
+    }
+}
+
+/// Look up a built-in command by what was typed.
+pub(crate) fn builtin(typed: &str) -> Option<Builtin> {
+    BUILTINS
+        .iter()
+        .find(|(spec, _)| ExName::parse(spec).accepts(typed))
+        .map(|&(_, cmd)| cmd)
+}
+
+/// Look up a host command. An exact match wins over abbreviations, which
+/// must be unambiguous.
+pub(crate) fn lookup<'a>(names: &'a [ExName], typed: &str) -> Result<Option<&'a ExName>, String> {
+    if let Some(exact) = names.iter().find(|n| n.name == typed) {
+        return Ok(Some(exact));
+    }
+    let mut found = names.iter().filter(|n| n.accepts(typed));
+    match (found.next(), found.next()) {
+        (Some(_), Some(_)) => Err(format!(
+            "E464: Ambiguous use of user-defined command: {typed}"
+        )),
+        (name, _) => Ok(name),
+    }
+}
+
+/// Split a command line into range, name, bang and arguments.
+pub(crate) fn parse(line: &str) -> Result<Parsed<'_>, String> {
+    let mut p = Parser { line, pos: 0 };
+    p.skip(|c| c == ':' || c.is_whitespace());
+    let range = p.range()?;
+    p.skip(|c| c == ':' || c.is_whitespace());
+
+    let start = p.pos;
+    match p.peek() {
+        Some(c) if c.is_ascii_alphabetic() => p.skip(|c| c.is_ascii_alphabetic()),
+        Some(_) => p.bump(),
+        None => {}
+    }
+    let name = &line[start..p.pos];
+    let bang = name != "!" && p.eat('!');
+    p.skip(char::is_whitespace);
+    Ok(Parsed {
+        range,
+        name,
+        bang,
+        args: &line[p.pos..],
+    })
+}
+
+struct Parser<'a> {
+    line: &'a str,
+    pos: usize, // byte offset
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.line[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn skip(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        self.skip(|c| c.is_ascii_digit());
+        (self.pos > start).then(|| self.line[start..self.pos].parse().unwrap_or(u32::MAX))
+    }
+
+    fn range(&mut self) -> Result<Vec<LineSpec>, String> {
+        let mut specs = Vec::new();
+        loop {
+            self.skip(char::is_whitespace);
+            let spec = if specs.is_empty() && self.eat('%') {
+                specs.push(LineSpec {
+                    address: Address::Number(1),
+                    offset: 0,
+                    set_cursor: false,
+                });
+                Some(LineSpec {
+                    address: Address::Last,
+                    offset: 0,
+                    set_cursor: false,
+                })
+            } else {
+                self.line_spec()?
+            };
+            self.skip(char::is_whitespace);
+            match self.peek() {
+                Some(sep @ (',' | ';')) => {
+                    self.bump();
+                    // A missing address next to a separator is the cursor line
+                    let mut spec = spec.unwrap_or(LineSpec {
+                        address: Address::Current,
+                        offset: 0,
+                        set_cursor: false,
+                    });
+                    spec.set_cursor = sep == ';';
+                    specs.push(spec);
+                    let next = self.pos;
+                    self.skip(char::is_whitespace);
+                    if !matches!(self.peek(), Some(c) if is_address_start(c)) {
+                        self.pos = next;
+                        specs.push(LineSpec {
+                            address: Address::Current,
+                            offset: 0,
+                            set_cursor: false,
+                        });
+                        return Ok(specs);
+                    }
+                }
+                _ => {
+                    specs.extend(spec);
+                    return Ok(specs);
+                }
+            }
+        }
+    }
+
+    fn line_spec(&mut self) -> Result<Option<LineSpec>, String> {
+        let address = match self.peek() {
+            Some('.') => {
+                self.bump();
+                Address::Current
+            }
+            Some('$') => {
+                self.bump();
+                Address::Last
+            }
+            Some(c) if c.is_ascii_digit() => Address::Number(self.number().unwrap_or(0)),
+            Some('\'') => {
+                self.bump();
+                let Some(name) = self.peek() else {
+                    return Err("E20: Mark not set".to_string());
+                };
+                self.bump();
+                Address::Mark(name)
+            }
+            Some(delim @ ('/' | '?')) => {
+                self.bump();
+                let mut pattern = String::new();
+                while let Some(c) = self.peek() {
+                    self.bump();
+                    if c == delim {
+                        break;
+                    }
+                    if c == '\\' && self.peek() == Some(delim) {
+                        pattern.push(delim);
+                        self.bump();
+                    } else {
+                        pattern.push(c);
+                    }
+                }
+                Address::Search {
+                    pattern,
+                    forward: delim == '/',
+                }
+            }
+            Some('+' | '-') => Address::Current,
+            _ => return Ok(None),
+        };
+
+        let mut offset = 0i64;
+        loop {
+            self.skip(char::is_whitespace);
+            match self.peek() {
+                Some(sign @ ('+' | '-')) => {
+                    self.bump();
+                    let n = i64::from(self.number().unwrap_or(1));
+                    offset += if sign == '+' { n } else { -n };
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    offset += i64::from(self.number().unwrap_or(0));
+                }
+                _ => break,
+            }
+        }
+        Ok(Some(LineSpec {
+            address,
+            offset,
+            set_cursor: false,
+        }))
+    }
+}
+
+fn is_address_start(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | '$' | '\'' | '/' | '?' | '+' | '-')
+}
+
+/// Turn the addresses of a range into one-based line numbers.
+///
+/// `mark` gives the zero-based line of a mark and `last_search` is used for
+/// an empty pattern. The numbers are not checked against the buffer, since
+/// `:{n}` alone clamps where other commands fail; see [`check`].
+pub(crate) fn resolve<T: TextOps>(
+    text: &T,
+    specs: &[LineSpec],
+    cursor_line: u32,
+    mark: impl Fn(char) -> Option<u32>,
+    last_search: Option<&str>,
+) -> Result<Option<(i64, i64)>, String> {
+    let mut cur = i64::from(cursor_line) + 1;
+    let mut lines = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let base = match &spec.address {
+            Address::Current => cur,
+            Address::Last => i64::from(text.line_count()),
+            Address::Number(n) => i64::from(*n),
+            Address::Mark(name) => match mark(*name) {
+                Some(line) => i64::from(line) + 1,
+                None => return Err("E20: Mark not set".to_string()),
+            },
+            Address::Search { pattern, forward } => {
+                let pattern = match (pattern.as_str(), last_search) {
+                    ("", Some(last)) => last,
+                    ("", None) => return Err("E35: No previous regular expression".to_string()),
+                    (pattern, _) => pattern,
+                };
+                let line =
+                    (cur - 1).clamp(0, i64::from(text.line_count().saturating_sub(1))) as u32;
+                let found = if *forward {
+                    let end = Position {
+                        line,
+                        col: text.line_len(line),
+                    };
+                    text.search_forward(end, pattern, true)
+                } else {
+                    text.search_backward(Position { line, col: 0 }, pattern, true)
+                };
+                match found {
+                    Some(pos) => i64::from(pos.line) + 1,
+                    None => return Err(format!("E486: Pattern not found: {pattern}")),
+                }
+            }
+        };
+        let line = base + spec.offset;
+        if spec.set_cursor {
+            cur = line;
+        }
+        lines.push(line);
+    }
+    Ok(match lines.as_slice() {
+        [] => None,
+        [line] => Some((*line, *line)),
+        [.., first, last] => Some((*first, *last)),
+    })
+}
+
+/// Check one-based line numbers against the buffer and make them a
+/// zero-based range. Line 0 is taken as line 1 and a backwards range is
+/// swapped.
+pub(crate) fn check<T: TextOps>(text: &T, (first, last): (i64, i64)) -> Result<LineRange, String> {
+    let count = i64::from(text.line_count());
+    if first < 0 || last < 0 || first > count || last > count {
+        return Err("E16: Invalid range".to_string());
+    }
+    let (first, last) = if first <= last {
+        (first, last)
+    } else {
+        (last, first)
+    };
+    Ok(LineRange {
+        start: (first.max(1) - 1) as u32,
+        end: (last.max(1) - 1) as u32,
+    })
+}
@@ -0,0 +1,53 @@
+//! Indenting lines with `>`, `<` and `=`.
+
+use crate::options::Options;
+use crate::scan;
+use crate::traits::TextOps;
+use crate::types::Position;
+
+/// The indent of `line`: the number of blanks in it and the screen columns
+/// they take.
+pub(crate) fn current<T: TextOps>(text: &T, line: u32, tabstop: u32) -> (u32, u32) {
+    let blanks = scan::first_non_blank(text, line);
+    let width = scan::display_col(text, Position { line, col: blanks }, tabstop.max(1));
+    (blanks, width)
+}
+
+/// An indent of `width` columns shifted `amount` times, right or `left`, by
+/// `shiftwidth`. With `shiftround` the result is a multiple of it, so the
+/// first shift may be a partial one.
+pub(crate) fn shifted(width: u32, amount: u32, left: bool, options: &Options) -> u32 {
+    let step = options.shift_width();
+    if !options.shiftround {
+        return if left {
+            width.saturating_sub(step.saturating_mul(amount))
+        } else {
+            width.saturating_add(step.saturating_mul(amount))
+        };
+    }
+    let steps = width / step;
+    let steps = if left {
+        // Down to the multiple below counts as one shift
+        let amount = if !width.is_multiple_of(step) {
+            amount - 1
+        } else {
+            amount
+        };
+        steps.saturating_sub(amount)
+    } else {
+        steps + amount
+    };
+    steps.saturating_mul(step)
+}
+
+/// Blanks filling `width` screen columns: tabs and then spaces, or only
+/// spaces with `expandtab`.
+pub(crate) fn blanks(width: u32, options: &Options) -> String {
+    if options.expandtab {
+        return " ".repeat(width as usize);
+    }
+    let tabstop = options.tabstop.max(1);
+    let mut indent = "\t".repeat((width / tabstop) as usize);
+    indent.push_str(&" ".repeat((width % tabstop) as usize));
+    indent
+}
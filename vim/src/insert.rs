@@ -0,0 +1,119 @@
+//! Editing keys in insert mode.
+//!
+//! Each function works out what one key changes around the cursor. The
+//! engine emits the edit and keeps track of where the insert started, which
+//! limits how far back Ctrl-W and Ctrl-U delete.
+
+use crate::options::Options;
+use crate::scan::{self, CharClass};
+use crate::traits::TextOps;
+use crate::types::Position;
+
+fn is_blank(g: &str) -> bool {
+    scan::char_class(g, false) == CharClass::Blank
+}
+
+/// The end of the line above, where deleting backwards from column 0 joins
+/// the line to.
+fn line_above<T: TextOps>(text: &T, cursor: Position) -> Option<Position> {
+    let line = cursor.line.checked_sub(1)?;
+    Some(Position {
+        line,
+        col: text.line_len(line),
+    })
+}
+
+/// Where Backspace deletes back to: the character before the cursor.
+pub(crate) fn char_before<T: TextOps>(text: &T, cursor: Position) -> Option<Position> {
+    if cursor.col == 0 {
+        return line_above(text, cursor);
+    }
+    Some(Position {
+        line: cursor.line,
+        col: cursor.col - 1,
+    })
+}
+
+/// Where Ctrl-W deletes back to: the blanks before the cursor and the word
+/// or run of punctuation before them.
+pub(crate) fn word_before<T: TextOps>(text: &T, cursor: Position) -> Option<Position> {
+    if cursor.col == 0 {
+        return line_above(text, cursor);
+    }
+    let graphemes = scan::line_graphemes(text, cursor.line);
+    let class = |col: usize| scan::char_class(&graphemes[col - 1], false);
+    let mut col = (cursor.col as usize).min(graphemes.len());
+    while col > 0 && class(col) == CharClass::Blank {
+        col -= 1;
+    }
+    if col > 0 {
+        let word = class(col);
+        while col > 0 && class(col) == word {
+            col -= 1;
+        }
+    }
+    Some(Position {
+        line: cursor.line,
+        col: col as u32,
+    })
+}
+
+/// Where Ctrl-U deletes back to: the indent of the line, or its start when
+/// the cursor is within the indent.
+pub(crate) fn line_before<T: TextOps>(text: &T, cursor: Position) -> Option<Position> {
+    if cursor.col == 0 {
+        return line_above(text, cursor);
+    }
+    let indent = scan::first_non_blank(text, cursor.line);
+    Some(Position {
+        line: cursor.line,
+        col: if cursor.col > indent { indent } else { 0 },
+    })
+}
+
+/// The text Tab inserts: a tab character, or with `expandtab` the spaces up
+/// to the next tab stop.
+pub(crate) fn tab<T: TextOps>(text: &T, cursor: Position, options: &Options) -> String {
+    if !options.expandtab {
+        return "\t".to_string();
+    }
+    let tabstop = options.tabstop.max(1);
+    let col = scan::display_col(text, cursor, tabstop);
+    " ".repeat((tabstop - col % tabstop) as usize)
+}
+
+/// The indent of a line opened next to `line` with `o` or `O`: a copy of the
+/// indent of `line` with `autoindent`, none without.
+pub(crate) fn open_indent<T: TextOps>(text: &T, line: u32, options: &Options) -> String {
+    if !options.autoindent {
+        return String::new();
+    }
+    scan::line_graphemes(text, line)
+        .iter()
+        .take_while(|g| is_blank(g))
+        .map(String::as_str)
+        .collect()
+}
+
+/// What Enter does at the cursor: the number of blanks after the cursor to
+/// remove and the text to insert. With `autoindent` the new line starts
+/// with the indent of the line it was split from, and the text moved to it
+/// loses its leading blanks.
+pub(crate) fn line_break<T: TextOps>(
+    text: &T,
+    cursor: Position,
+    options: &Options,
+) -> (u32, String) {
+    if !options.autoindent {
+        return (0, "\n".to_string());
+    }
+    let graphemes = scan::line_graphemes(text, cursor.line);
+    let col = (cursor.col as usize).min(graphemes.len());
+    let indent: String = graphemes[..col]
+        .iter()
+        .take_while(|g| is_blank(g))
+        .map(String::as_str)
+        .collect();
+    let blanks = graphemes[col..].iter().take_while(|g| is_blank(g)).count();
+    (blanks as u32, format!("\n{indent}"))
+}
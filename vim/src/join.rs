@@ -0,0 +1,99 @@
+//! Joining lines with `J` and `gJ`.
+
+use crate::scan::{self, CharClass};
+use crate::traits::TextOps;
+use crate::types::{Command, Position, Range};
+
+/// What happens to the white space where two lines are joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Spacing {
+    /// `gJ`: the lines are put together as they are.
+    Keep,
+    /// `J`: the indent is removed and a space put in its place, two after
+    /// the end of a sentence with `joinspaces`.
+    Adjust { joinspaces: bool },
+}
+
+fn is_blank(g: &str) -> bool {
+    scan::char_class(g, false) == CharClass::Blank
+}
+
+/// The blanks `J` puts between `before`, the last character of the text so
+/// far, and `next`, the first one of the joined line. None after a blank, at
+/// the start of an empty line, before `)` or when nothing is joined.
+fn separator(before: Option<&str>, next: Option<&str>, joinspaces: bool) -> &'static str {
+    match (before, next) {
+        (Some(before), Some(next)) if !is_blank(before) && !scan::is_char(next, ')') => {
+            if joinspaces && [".", "!", "?"].contains(&before) {
+                "  "
+            } else {
+                " "
+            }
+        }
+        _ => "",
+    }
+}
+
+/// Join lines `first` to `last` into one. With [`Spacing::Adjust`] the
+/// indent of each joined line is removed and blanks put in its place (see
+/// `separator`). Returns where the last join was made: on the inserted
+/// blanks or on the first character joined, and on the last character of
+/// the line when nothing followed.
+pub(crate) fn join<T: TextOps>(
+    text: &T,
+    first: u32,
+    last: u32,
+    spacing: Spacing,
+) -> (Position, Vec<Command>) {
+    let graphemes = scan::line_graphemes(text, first);
+    let mut len = graphemes.len() as u32;
+    let mut end = graphemes.last().map(|g| g.to_string());
+    let mut at = Position {
+        line: first,
+        col: len,
+    };
+    let mut cmds = Vec::new();
+    let last = last.min(text.line_count().saturating_sub(1));
+    for line in first + 1..=last {
+        let graphemes = scan::line_graphemes(text, line);
+        let indent = match spacing {
+            Spacing::Keep => 0,
+            Spacing::Adjust { .. } => graphemes.iter().take_while(|g| is_blank(g)).count(),
+        };
+        let rest = &graphemes[indent..];
+        // Earlier joins have made the line the one after `first`
+        at = Position {
+            line: first,
+            col: len,
+        };
+        cmds.push(Command::Delete {
+            range: Range {
+                start: at,
+                end: Position {
+                    line: first + 1,
+                    col: indent as u32,
+                },
+            },
+        });
+        let blanks = match spacing {
+            Spacing::Keep => "",
+            Spacing::Adjust { joinspaces } => {
+                separator(end.as_deref(), rest.first().map(String::as_str), joinspaces)
+            }
+        };
+        if !blanks.is_empty() {
+            cmds.push(Command::InsertText {
+                at,
+                text: blanks.to_string(),
+            });
+        }
+        if let Some(g) = rest.last() {
+            end = Some(g.to_string());
+        }
+        len += blanks.len() as u32 + rest.len() as u32;
+    }
+    if at.col >= len {
+        at.col = len.saturating_sub(1);
+    }
+    (at, cmds)
+}
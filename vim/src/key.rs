@@ -11,10 +11,33 @@ pub enum KeyCode {
     Esc,
     /// The Enter/Return key.
     Enter,
-    /// The Backspace key for deleting characters in insert/search modes.
+    /// The Backspace key for deleting characters in insert, search and
+    /// command-line modes.
     Backspace,
-    // navigation keys if host prefers: Up, Down, Left, Right (optional)
-    // but we primarily use Char('h','j','k','l', ...)
+    /// The Delete key, deleting the character under the cursor.
+    Delete,
+    /// The Tab key, the same as Ctrl-I.
+    Tab,
+    /// The Up arrow key.
+    Up,
+    /// The Down arrow key.
+    Down,
+    /// The Left arrow key.
+    Left,
+    /// The Right arrow key.
+    Right,
+    /// The Home key, moving to the start of the line.
+    Home,
+    /// The End key, moving to the end of the line.
+    End,
+    /// The Page Up key, the same as Ctrl-B.
+    PageUp,
+    /// The Page Down key, the same as Ctrl-F.
+    PageDown,
+    /// The Insert key.
+    Insert,
+    /// A function key, `F(1)` to `F(24)`. The engine leaves these to the host.
+    F(u8),
 }
 
 bitflags::bitflags! {
@@ -49,7 +72,97 @@ pub struct KeyEvent {
 pub enum InputEvent {
     /// A key press event, typically used for commands and navigation.
     Key(KeyEvent),
-    /// A character received in text input mode (insert or search).
+    /// A character received in text input mode (insert, replace, search or
+    /// command line).
     /// This allows hosts to handle composed characters and IME input.
     ReceivedChar(char),
 }
+
+/// Render recorded keys as register text, the way Vim shows a macro.
+///
+/// Esc, Enter, Backspace, Delete and Tab become `\x1b`, `\r`, `\x08`, `\x7f`
+/// and `\t`, and Ctrl+letter becomes the matching control character. The
+/// other keys have no character of their own and use one from the Unicode
+/// private use area, so the text still replays as the same keys.
+pub(crate) fn keys_to_text(keys: &[InputEvent]) -> String {
+    keys.iter()
+        .map(|input| match input {
+            InputEvent::ReceivedChar(c) => *c,
+            InputEvent::Key(ke) => match ke.code {
+                KeyCode::Char(c)
+                    if ke.mods.contains(Modifiers::CTRL) && c.is_ascii_alphabetic() =>
+                {
+                    (c.to_ascii_lowercase() as u8 & 0x1f) as char
+                }
+                KeyCode::Char(c) => c,
+                KeyCode::Esc => '\x1b',
+                KeyCode::Enter => '\r',
+                KeyCode::Backspace => '\x08',
+                KeyCode::Delete => '\x7f',
+                KeyCode::Tab => '\t',
+                code => special_char(code),
+            },
+        })
+        .collect()
+}
+
+/// Turn one character of register text back into an input event.
+///
+/// `text_input` is true in insert and search modes, where printable
+/// characters arrive as [`InputEvent::ReceivedChar`].
+pub(crate) fn char_to_event(ch: char, text_input: bool) -> InputEvent {
+    let key = |code, mods| InputEvent::Key(KeyEvent { code, mods });
+    match ch {
+        '\x1b' => key(KeyCode::Esc, Modifiers::empty()),
+        '\r' => key(KeyCode::Enter, Modifiers::empty()),
+        '\x08' => key(KeyCode::Backspace, Modifiers::empty()),
+        '\x7f' => key(KeyCode::Delete, Modifiers::empty()),
+        '\u{e000}'..='\u{e0ff}' => match special_key(ch) {
+            Some(code) => key(code, Modifiers::empty()),
+            None => InputEvent::ReceivedChar(ch),
+        },
+        '\n' if text_input => InputEvent::ReceivedChar(ch),
+        '\n' => key(KeyCode::Enter, Modifiers::empty()),
+        '\x01'..='\x1a' => key(
+            KeyCode::Char((ch as u8 - 1 + b'a') as char),
+            Modifiers::CTRL,
+        ),
+        _ if text_input => InputEvent::ReceivedChar(ch),
+        _ => key(KeyCode::Char(ch), Modifiers::empty()),
+    }
+}
+
+/// First of the private use characters that stand for keys in register text.
+const SPECIAL_BASE: u32 = 0xE000;
+
+/// Keys without a character of their own, in the order of their stand-ins.
+const SPECIAL_KEYS: [KeyCode; 9] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Insert,
+];
+
+/// The stand-in character for a key without one; function keys follow the
+/// keys in [`SPECIAL_KEYS`].
+fn special_char(code: KeyCode) -> char {
+    let index = match code {
+        KeyCode::F(n) => SPECIAL_KEYS.len() as u32 + u32::from(n),
+        code => SPECIAL_KEYS.iter().position(|&k| k == code).unwrap_or(0) as u32,
+    };
+    char::from_u32(SPECIAL_BASE + index).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// The key a stand-in character from [`special_char`] is for.
+fn special_key(ch: char) -> Option<KeyCode> {
+    let index = (ch as u32).checked_sub(SPECIAL_BASE)? as usize;
+    match index.checked_sub(SPECIAL_KEYS.len()) {
+        None => Some(SPECIAL_KEYS[index]),
+        Some(n) => u8::try_from(n).ok().map(KeyCode::F),
+    }
+}
@@ -12,11 +12,24 @@
 //!
 //! ## Key Features
 //!
-//! - **Modes**: Normal, Insert, Visual (character/line), and Search
-//! - **Motions**: `h j k l`, `w b`, `0 $`, `gg G`, `{ }`, `f/t<char>` with counts
-//! - **Operators**: `d` (delete), `y` (yank), `x` (delete char), `p` (paste)
-//! - **Visual Mode**: Character-wise (`v`) and line-wise (`V`) selection
+//! - **Modes**: Normal, Insert, Replace, Visual (character/line/block), Search and Command-line
+//! - **Motions**: `h j k l`, `w b e ge`, `W B E gE`, `0 $`, `gg G`, `( )`, `{ }`, `%`, `N%`, `[( [{ ]) ]}`, `f/t/F/T<char>`, `;` and `,` with counts
+//! - **Operators**: `d` (delete), `c` (change), `y` (yank), `x` (delete char), `p` (paste), `>`/`<` (shift), `=` (indent; see [`EngineBuilder::indent_fn`]), `~`, `g~`, `gu`, `gU` and `g?` (case and rot13), `J` and `gJ` (join lines)
+//! - **Text Objects**: words, quotes, brackets, tags, sentences and paragraphs (`iw a" i( it is ap` ...) after an operator or in visual mode
+//! - **Insert Mode**: `i a I A`, `o`/`O` with `autoindent` and counts, Backspace, Delete, Enter with `autoindent`, Tab with `expandtab`, Ctrl-W and Ctrl-U; see [`Options`]
+//! - **Replace**: `r{char}` with counts and `r<Enter>`, and replace mode (`R`) where Backspace restores the overwritten text
+//! - **Special Keys**: Arrows, Home/End, Page Up/Down, Delete, Insert and Tab as [`KeyCode`]s in normal, visual, insert and replace mode, and the keys that move and delete on the `:` and `/` line
+//! - **Visual Mode**: Character-wise (`v`), line-wise (`V`) and block-wise (Ctrl-V) selection, `o`/`O`, `gv`, and `d y c r J ~ u U > < p P` on the selection
 //! - **Search**: Forward search with `/`, navigate with `n`/`N`
+//! - **Ex Commands**: `:` with ranges; `:w`, `:q` and `:e` and commands registered by the host are emitted as [`Command`]s
+//! - **Substitute**: `:s/pat/repl/[gcinI]` with Vim regex patterns, `:&`, `:&&`, `&` and `g&`; the `c` flag asks before each change
+//! - **Repeat**: `.` repeats the last change, a count replaces the original one
+//! - **Registers**: `"{reg}` selects a named, numbered or clipboard register
+//! - **Macros**: `q{reg}` records keys, `@{reg}` replays them (see [`Engine::step_replay`])
+//! - **Marks**: `m{a-z}`, `'{mark}` and `` `{mark} `` as motions, with the special marks of [`marks`]; marks follow the edits the engine emits
+//! - **Scrolling**: `H M L`, Ctrl-D/U/F/B/E/Y and `zt zz zb` once the host gives its [`Viewport`]; the engine emits [`Command::ScrollTo`]
+//! - **Jumps**: Ctrl-O/Ctrl-I walk the jumplist, `g;`/`g,` the change list and `gi` resumes insert mode
+//! - **Undo**: `u`, Ctrl-R and `U` with the `undo` feature, which records the edits the engine emits
 //! - **Unicode-aware**: All operations work correctly with grapheme clusters (emoji, combining marks)
 //! - **High Performance**: Zero-allocation design, <5ms keystroke latency
 //!
@@ -55,6 +68,7 @@
 //!         Command::Delete { range } => { /* delete text */ },
 //!         Command::InsertText { at, text } => { /* insert text */ },
 //!         Command::SetSelection(sel) => { /* update selection */ },
+//!         _ => { /* ex commands such as :w and :q */ },
 //!     }
 //! }
 //! ```
@@ -64,7 +78,7 @@
 //! To integrate vim_mini into your application:
 //!
 //! 1. **Implement the `TextOps` trait** for your text buffer
-//! 2. **Implement the `Clipboard` trait** for clipboard access
+//! 2. **Implement the `Clipboard` trait** to back the `+` and `*` registers
 //! 3. **Map platform key events** to `InputEvent`
 //! 4. **Apply emitted commands** to update your text buffer
 //! 5. **Render the current mode and selection** in your UI
@@ -74,12 +88,7 @@
 //! ## What's NOT Included
 //!
 //! To keep the library minimal and focused:
-//! - No dot-repeat (`.`)
-//! - No macros or registers (except system clipboard)
-//! - No ex commands (`:`)
-//! - No marks or jumplists
-//! - No text objects beyond basic word/line
-//! - No undo/redo (hosts should implement this)
+//! - No undo/redo by default (enable the `undo` feature or implement it in the host)
 //!
 //! ## Performance
 //!
@@ -94,12 +103,37 @@
 //! - `tui_crossterm.rs` - Terminal integration with crossterm
 //! - `egui_app.rs` - GUI integration with egui
 
+mod block;
+mod case;
 pub mod engine;
+mod ex;
+mod indent;
+mod insert;
+mod join;
 pub mod key;
+pub mod marks;
+pub mod options;
+mod pattern;
+mod prompt;
+pub mod registers;
+mod replace;
+mod scan;
+mod scroll;
+mod substitute;
+mod textobj;
 pub mod traits;
 pub mod types;
+#[cfg(feature = "undo")]
+pub mod undo;
 
 pub use crate::engine::{Engine, EngineBuilder, EngineSnapshot};
 pub use crate::key::{InputEvent, KeyCode, KeyEvent, Modifiers};
+pub use crate::marks::Marks;
+pub use crate::options::Options;
+pub use crate::registers::{Register, RegisterKind, Registers};
 pub use crate::traits::{Clipboard, TextOps};
-pub use crate::types::{Command, Mode, Position, Range, Selection, VisualKind};
+pub use crate::types::{
+    Command, ExCommand, LineRange, Mode, Position, Range, Selection, Viewport, VisualKind,
+};
+#[cfg(feature = "undo")]
+pub use crate::undo::UndoHistory;
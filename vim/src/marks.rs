@@ -0,0 +1,322 @@
+//! Vim-style marks.
+//!
+//! Marks remember positions in the buffer. `m{a-z}` sets a named mark,
+//! `'{mark}` jumps to the first non-blank of its line and `` `{mark} `` to
+//! the exact position. Both also work as motions after an operator: `'`
+//! makes it linewise (`d'a`), `` ` `` characterwise and exclusive.
+//!
+//! | Mark      | Position |
+//! |-----------|----------|
+//! | `a`–`z`   | Set with `m{a-z}` |
+//! | `'`, `` ` `` | Before the latest jump, such as `G`, `n` or `'a` |
+//! | `.`       | Where the last change was made |
+//! | `^`       | Where insert mode was last left |
+//! | `[`, `]`  | First and last character of the last change or yank |
+//! | `<`, `>`  | Start and end of the last visual area |
+//!
+//! The engine moves marks along with the [`Command::Delete`] and
+//! [`Command::InsertText`] commands it emits, so a mark stays on the same
+//! text when lines are added or removed above it. Named marks on lines that
+//! are deleted as a whole are removed.
+//!
+//! Next to the marks live two lists of positions. The jumplist remembers
+//! where each jump started, one entry per line, and is walked with Ctrl-O
+//! and Ctrl-I. The change list remembers where changes were made, one entry
+//! per run of changes in the same line, and is walked with `g;` and `g,`.
+//! Both keep the last [`HISTORY_LEN`] entries.
+
+use crate::types::{Command, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Number of entries kept in the jumplist and the change list.
+pub const HISTORY_LEN: usize = 100;
+
+/// Returns true for names accepted after `'` and `` ` ``.
+pub fn is_valid_mark(name: char) -> bool {
+    name.is_ascii_lowercase() || matches!(name, '\'' | '`' | '.' | '^' | '[' | ']' | '<' | '>')
+}
+
+/// Returns true for names accepted after `m`.
+pub fn is_settable_mark(name: char) -> bool {
+    is_valid_mark(name) && !matches!(name, '.' | '^')
+}
+
+/// The engine's marks for one buffer.
+#[derive(Debug, Clone, Default)]
+pub struct Marks {
+    named: [Option<Position>; 26],
+    context: Option<Position>,      // ' and `
+    last_change: Option<Position>,  // .
+    last_insert: Option<Position>,  // ^
+    change_start: Option<Position>, // [
+    change_end: Option<Position>,   // ]
+    visual_start: Option<Position>, // <
+    visual_end: Option<Position>,   // >
+    jumps: History,
+    changes: History,
+}
+
+/// A bounded list of positions and the entry being visited, where
+/// `index == entries.len()` means none is.
+#[derive(Debug, Clone, Default)]
+struct History {
+    entries: Vec<Position>,
+    index: usize,
+}
+
+impl History {
+    fn push(&mut self, pos: Position) {
+        if self.entries.len() == HISTORY_LEN {
+            self.entries.remove(0);
+        }
+        self.entries.push(pos);
+        self.index = self.entries.len();
+    }
+
+    fn adjust(&mut self, f: impl Fn(Position) -> Position) {
+        for pos in &mut self.entries {
+            *pos = f(*pos);
+        }
+    }
+}
+
+impl Marks {
+    /// The position of a mark, or `None` when it is not set. Marks are not
+    /// clamped to the buffer; the engine does that when it uses them.
+    pub fn get(&self, name: char) -> Option<Position> {
+        match name {
+            'a'..='z' => self.named[(name as u8 - b'a') as usize],
+            '\'' | '`' => self.context,
+            '.' => self.last_change,
+            '^' => self.last_insert,
+            '[' => self.change_start,
+            ']' => self.change_end,
+            '<' => self.visual_start,
+            '>' => self.visual_end,
+            _ => None,
+        }
+    }
+
+    /// Set a mark. Names that are not marks are ignored. Setting `'` or
+    /// `` ` `` also adds the position to the jumplist.
+    pub fn set(&mut self, name: char, pos: Position) {
+        if let Some(slot) = self.slot(name) {
+            *slot = Some(pos);
+        }
+        if matches!(name, '\'' | '`') {
+            self.jumps.entries.retain(|p| p.line != pos.line);
+            self.jumps.push(pos);
+        }
+    }
+
+    /// The jumplist, oldest first.
+    pub fn jumps(&self) -> &[Position] {
+        &self.jumps.entries
+    }
+
+    /// The change list, oldest first.
+    pub fn changes(&self) -> &[Position] {
+        &self.changes.entries
+    }
+
+    /// Go `count` entries back in the jumplist from `from` (Ctrl-O). The
+    /// first step back adds `from`, so Ctrl-I can return to it.
+    pub(crate) fn jump_older(&mut self, from: Position, count: usize) -> Option<Position> {
+        if self.jumps.index == self.jumps.entries.len() {
+            self.set('\'', from);
+            self.jumps.index -= 1;
+        }
+        let target = self.jumps.index.checked_sub(count)?;
+        self.jumps.index = target;
+        Some(self.jumps.entries[target])
+    }
+
+    /// Go `count` entries forward in the jumplist (Ctrl-I).
+    pub(crate) fn jump_newer(&mut self, count: usize) -> Option<Position> {
+        let target = self.jumps.index + count;
+        let pos = *self.jumps.entries.get(target)?;
+        self.jumps.index = target;
+        Some(pos)
+    }
+
+    /// Move `count` entries through the change list, back when negative
+    /// (`g;`) and forward when positive (`g,`). Going past either end stops
+    /// at the last entry, unless it is already there.
+    pub(crate) fn change_at(&mut self, count: i64) -> Result<Position, String> {
+        let len = self.changes.entries.len() as i64;
+        if len == 0 {
+            return Err("E664: Changelist is empty".to_string());
+        }
+        let index = self.changes.index as i64;
+        let target = match index + count {
+            t if t < 0 && index == 0 => return Err("E662: At start of changelist".to_string()),
+            t if t >= len && index >= len - 1 => {
+                return Err("E663: At end of changelist".to_string());
+            }
+            t => t.clamp(0, len - 1),
+        };
+        self.changes.index = target as usize;
+        Ok(self.changes.entries[target as usize])
+    }
+
+    /// Remove a mark.
+    pub fn remove(&mut self, name: char) {
+        if let Some(slot) = self.slot(name) {
+            *slot = None;
+        }
+    }
+
+    fn slot(&mut self, name: char) -> Option<&mut Option<Position>> {
+        Some(match name {
+            'a'..='z' => &mut self.named[(name as u8 - b'a') as usize],
+            '\'' | '`' => &mut self.context,
+            '.' => &mut self.last_change,
+            '^' => &mut self.last_insert,
+            '[' => &mut self.change_start,
+            ']' => &mut self.change_end,
+            '<' => &mut self.visual_start,
+            '>' => &mut self.visual_end,
+            _ => return None,
+        })
+    }
+
+    /// Move the marks for an edit the host made to the buffer.
+    ///
+    /// Edits emitted by the engine are already accounted for; call this only
+    /// for changes made outside of it, such as undo or a file reload.
+    pub fn adjust(&mut self, cmd: &Command) {
+        match cmd {
+            Command::Delete { range } => {
+                let whole_lines =
+                    range.start.col == 0 && range.end.col == 0 && range.end.line > range.start.line;
+                for slot in &mut self.named {
+                    if let Some(pos) = *slot {
+                        let removed = whole_lines
+                            && pos.line >= range.start.line
+                            && pos.line < range.end.line;
+                        *slot = (!removed).then(|| after_delete(pos, *range));
+                    }
+                }
+                for pos in self.special().into_iter().flatten() {
+                    *pos = after_delete(*pos, *range);
+                }
+                self.jumps.adjust(|pos| after_delete(pos, *range));
+                self.changes.adjust(|pos| after_delete(pos, *range));
+            }
+            Command::InsertText { at, text } => {
+                for pos in self.named.iter_mut().flatten() {
+                    *pos = after_insert(*pos, *at, text);
+                }
+                for pos in self.special().into_iter().flatten() {
+                    *pos = after_insert(*pos, *at, text);
+                }
+                self.jumps.adjust(|pos| after_insert(pos, *at, text));
+                self.changes.adjust(|pos| after_insert(pos, *at, text));
+            }
+            _ => {}
+        }
+    }
+
+    fn special(&mut self) -> [&mut Option<Position>; 7] {
+        [
+            &mut self.context,
+            &mut self.last_change,
+            &mut self.last_insert,
+            &mut self.change_start,
+            &mut self.change_end,
+            &mut self.visual_start,
+            &mut self.visual_end,
+        ]
+    }
+
+    /// Follow the edits in `cmds`, update `.`, `[` and `]` for them and add
+    /// them to the change list.
+    /// When `extend` is set the edits continue the previous change, as the
+    /// keys typed in one insert session do.
+    pub(crate) fn record_edits(&mut self, cmds: &[Command], extend: bool) {
+        let mut extend = extend;
+        for cmd in cmds {
+            self.adjust(cmd);
+            let (start, end) = match cmd {
+                Command::Delete { range } => (range.start, range.start),
+                Command::InsertText { at, text } => (*at, last_inserted(*at, text)),
+                _ => continue,
+            };
+            self.last_change = Some(start);
+            if self
+                .changes
+                .entries
+                .last()
+                .is_some_and(|p| p.line == start.line)
+            {
+                self.changes.entries.pop();
+            }
+            self.changes.push(start);
+            let (start, end) = match (extend, self.change_start, self.change_end) {
+                (true, Some(first), Some(last)) => (first.min(start), last.max(end)),
+                _ => (start, end),
+            };
+            self.change_start = Some(start);
+            self.change_end = Some(end);
+            extend = true;
+        }
+    }
+}
+
+/// Where `pos` ends up once `range` is deleted. Positions inside the range
+/// move to its start.
+fn after_delete(pos: Position, range: Range) -> Position {
+    if pos < range.start {
+        pos
+    } else if pos < range.end {
+        range.start
+    } else if pos.line == range.end.line {
+        Position {
+            line: range.start.line,
+            col: range.start.col + (pos.col - range.end.col),
+        }
+    } else {
+        Position {
+            line: pos.line - (range.end.line - range.start.line),
+            col: pos.col,
+        }
+    }
+}
+
+/// Where `pos` ends up once `text` is inserted at `at`. A position at `at`
+/// moves with the text after it.
+fn after_insert(pos: Position, at: Position, text: &str) -> Position {
+    if pos < at {
+        return pos;
+    }
+    let (breaks, tail) = match text.rsplit_once('\n') {
+        Some((before, last)) => (before.matches('\n').count() as u32 + 1, last),
+        None => (0, text),
+    };
+    let tail = tail.graphemes(true).count() as u32;
+    match (pos.line == at.line, breaks) {
+        (true, 0) => Position {
+            line: pos.line,
+            col: pos.col + tail,
+        },
+        (true, _) => Position {
+            line: pos.line + breaks,
+            col: tail + (pos.col - at.col),
+        },
+        (false, _) => Position {
+            line: pos.line + breaks,
+            col: pos.col,
+        },
+    }
+}
+
+/// The last character of `text` inserted at `at`. For text ending in a
+/// line break, as a linewise put, that is the end of the last full line.
+fn last_inserted(at: Position, text: &str) -> Position {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    let end = after_insert(at, at, text);
+    Position {
+        line: end.line,
+        col: end.col.saturating_sub(1),
+    }
+}
@@ -0,0 +1,83 @@
+//! Editor options.
+//!
+//! [`Options`] holds the settings Vim changes with `:set` that affect how
+//! keys edit text. Set them with [`EngineBuilder::options`] or change them
+//! later through [`Engine::options_mut`].
+//!
+//! [`EngineBuilder::options`]: crate::EngineBuilder::options
+//! [`Engine::options_mut`]: crate::Engine::options_mut
+
+/// Settings that change how the engine edits text.
+///
+/// # Examples
+///
+/// ```
+/// use vim_mini::{EngineBuilder, Options};
+///
+/// let engine = EngineBuilder::default()
+///     .options(Options {
+///         tabstop: 4,
+///         expandtab: true,
+///         ..Options::default()
+///     })
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Columns a tab character takes up (`tabstop`). Defaults to 8.
+    pub tabstop: u32,
+    /// Tab in insert mode and indents made by `>` and `<` use spaces instead
+    /// of tab characters (`expandtab`). Off by default.
+    pub expandtab: bool,
+    /// Columns `>` and `<` shift a line by (`shiftwidth`). Zero uses
+    /// `tabstop`. Defaults to 8.
+    pub shiftwidth: u32,
+    /// `>` and `<` round the indent to a multiple of `shiftwidth`
+    /// (`shiftround`). Off by default.
+    pub shiftround: bool,
+    /// `~` is an operator taking a motion, like `g~`, instead of switching
+    /// the case of the characters under the cursor (`tildeop`). Off by
+    /// default.
+    pub tildeop: bool,
+    /// A new line gets the indent of the line it was split from
+    /// (`autoindent`). On by default.
+    pub autoindent: bool,
+    /// Backspace, Ctrl-W and Ctrl-U may delete text from before the start of
+    /// the insert, as with Vim's `backspace=indent,eol,start`. Ctrl-W and
+    /// Ctrl-U still stop once at the start. On by default; when off, nothing
+    /// before the start of the insert can be deleted.
+    pub backspace_start: bool,
+    /// `J` puts two spaces instead of one after a `.`, `!` or `?` that ends
+    /// a line (`joinspaces`). Off by default.
+    pub joinspaces: bool,
+    /// `;` and `,` after a `t` or `T` do not move when the cursor is already
+    /// next to the character, as with `;` in Vim's `cpoptions`. Off by
+    /// default, so that they skip to the next one.
+    pub cpo_semicolon: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tabstop: 8,
+            expandtab: false,
+            shiftwidth: 8,
+            shiftround: false,
+            tildeop: false,
+            autoindent: true,
+            backspace_start: true,
+            joinspaces: false,
+            cpo_semicolon: false,
+        }
+    }
+}
+
+impl Options {
+    /// The columns of one shift: `shiftwidth`, or `tabstop` when it is zero.
+    pub(crate) fn shift_width(&self) -> u32 {
+        match self.shiftwidth {
+            0 => self.tabstop.max(1),
+            width => width,
+        }
+    }
+}
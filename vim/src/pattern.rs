@@ -0,0 +1,564 @@
+//! A small backtracking matcher for the Vim patterns used by `:s`.
+//!
+//! Patterns are matched within one line and use Vim's `magic` syntax:
+//!
+//! | Item | Matches |
+//! |------|---------|
+//! | `.`, `[abc]`, `[^a-z]` | Any character, a character class |
+//! | `\s \S \d \D \w \W \a \A \l \L \u \U` | Blank, digit, word, letter, lower and upper case classes and their negations |
+//! | `*`, `\+`, `\=` or `\?`, `\{n,m}`, `\{-n,m}` | Repeats; `\{-}` is non-greedy |
+//! | `^`, `$`, `\<`, `\>` | Start and end of the line and of a word |
+//! | `\(...\)`, `\%(...\)`, `\|` | Groups, non-capturing groups and alternatives |
+//! | `\c`, `\C` | Ignore or match case for the whole pattern |
+//!
+//! Other punctuation after `\` matches itself, so `\.` and `\/` are literal.
+//! Any other escaped letter or digit, `\@`, `\&` and `\%` not starting a group
+//! are not supported and make the pattern invalid.
+
+/// Start and end (exclusive) char indices of the match and of each group.
+pub(crate) type Captures = Vec<Option<(usize, usize)>>;
+
+/// A compiled pattern.
+#[derive(Debug, Clone)]
+pub(crate) struct Pattern {
+    prog: Vec<Inst>,
+    groups: usize,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    LineStart,
+    LineEnd,
+    WordStart,
+    WordEnd,
+    Group(Option<usize>, Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: u32,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ClassItem {
+    Range(char, char),
+    Blank,
+    Digit,
+    Word,
+    Alpha,
+    Lower,
+    Upper,
+}
+
+impl ClassItem {
+    fn matches(self, c: char, ignore_case: bool) -> bool {
+        match self {
+            ClassItem::Range(lo, hi) => {
+                (lo..=hi).contains(&c)
+                    || (ignore_case && [lower(c), upper(c)].iter().any(|v| (lo..=hi).contains(v)))
+            }
+            ClassItem::Blank => c == ' ' || c == '\t',
+            ClassItem::Digit => c.is_ascii_digit(),
+            ClassItem::Word => is_word(c),
+            ClassItem::Alpha => c.is_alphabetic(),
+            ClassItem::Lower => c.is_lowercase(),
+            ClassItem::Upper => c.is_uppercase(),
+        }
+    }
+}
+
+fn lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn upper(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Pattern {
+    /// Compile `pattern`. `ignore_case` can be overridden by `\c` and `\C`.
+    pub(crate) fn new(pattern: &str, ignore_case: bool) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+            ignore_case: None,
+        };
+        let alts = parser.alternatives(false)?;
+        let mut prog = Vec::new();
+        compile(&mut prog, &Node::Group(Some(0), alts))?;
+        emit(&mut prog, Inst::Match)?;
+        Ok(Self {
+            prog,
+            groups: parser.groups,
+            ignore_case: parser.ignore_case.unwrap_or(ignore_case),
+        })
+    }
+
+    /// The leftmost match starting at or after char `from`.
+    ///
+    /// Choices to go back to are kept on a stack rather than in calls, so
+    /// long lines cannot overflow it, and an instruction already tried at a
+    /// char is not tried there again: it failed the first time and would
+    /// fail again, so nested repeats cannot make the work explode.
+    pub(crate) fn find_at(&self, text: &[char], from: usize) -> Option<Captures> {
+        let mut visited = Visited {
+            from,
+            width: self.prog.len(),
+            bits: Vec::new(),
+        };
+        let mut slots = vec![None; 2 * (self.groups + 1)];
+        let mut jobs = Vec::new();
+        for start in from..=text.len() {
+            jobs.push(Job::Run(0, start));
+            while let Some(job) = jobs.pop() {
+                let (mut pc, mut i) = match job {
+                    Job::Run(pc, i) => (pc, i),
+                    Job::Restore(slot, old) => {
+                        slots[slot] = old;
+                        continue;
+                    }
+                };
+                while visited.insert(pc, i) {
+                    match &self.prog[pc] {
+                        Inst::Step(node) => match self.step(text, node, i) {
+                            Some(j) => (pc, i) = (pc + 1, j),
+                            None => break,
+                        },
+                        Inst::Split(first, second) => {
+                            jobs.push(Job::Run(*second, i));
+                            pc = *first;
+                        }
+                        Inst::Jump(to) => pc = *to,
+                        Inst::Save(slot) => {
+                            jobs.push(Job::Restore(*slot, slots[*slot]));
+                            slots[*slot] = Some(i);
+                            pc += 1;
+                        }
+                        Inst::Match => {
+                            return Some(
+                                slots
+                                    .chunks(2)
+                                    .map(|span| Some((span[0]?, span[1]?)))
+                                    .collect(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Match a node that is not a group or repeat, returning where it ends.
+    fn step(&self, text: &[char], node: &Node, i: usize) -> Option<usize> {
+        let c = text.get(i).copied();
+        let word_before = i > 0 && is_word(text[i - 1]);
+        let word_after = c.is_some_and(is_word);
+        let ok = match node {
+            Node::Char(ch) => {
+                let c = c?;
+                c == *ch || (self.ignore_case && lower(c) == lower(*ch))
+            }
+            Node::Any => c.is_some(),
+            Node::Class { items, negated } => {
+                let c = c?;
+                items.iter().any(|item| item.matches(c, self.ignore_case)) != *negated
+            }
+            Node::LineStart => return (i == 0).then_some(i),
+            Node::LineEnd => return (i == text.len()).then_some(i),
+            Node::WordStart => return (word_after && !word_before).then_some(i),
+            Node::WordEnd => return (word_before && !word_after).then_some(i),
+            Node::Group(..) | Node::Repeat { .. } => false,
+        };
+        ok.then_some(i + 1)
+    }
+}
+
+/// The largest program a pattern compiles to; counted repeats copy the
+/// instructions of what they repeat.
+const MAX_PROGRAM: usize = 50_000;
+
+/// One instruction of a compiled pattern.
+#[derive(Debug, Clone)]
+enum Inst {
+    /// A node that is not a group or repeat.
+    Step(Node),
+    /// Go on at the first instruction, and at the second if that fails.
+    Split(usize, usize),
+    Jump(usize),
+    /// Set a capture slot, the start of group `n` being `2 * n` and its end
+    /// `2 * n + 1`, to the current char.
+    Save(usize),
+    Match,
+}
+
+/// Add `inst` to `prog`, returning its index.
+fn emit(prog: &mut Vec<Inst>, inst: Inst) -> Result<usize, String> {
+    if prog.len() >= MAX_PROGRAM {
+        return Err("E363: Pattern uses more memory than 'maxmempattern'".to_string());
+    }
+    prog.push(inst);
+    Ok(prog.len() - 1)
+}
+
+/// A split to patch once the instructions after it are known.
+fn split(prog: &mut Vec<Inst>) -> Result<usize, String> {
+    emit(prog, Inst::Split(0, 0))
+}
+
+/// Make the split at `at` go on to the next instruction or to `out`,
+/// preferring the next one when `greedy`.
+fn patch(prog: &mut [Inst], at: usize, out: usize, greedy: bool) {
+    prog[at] = if greedy {
+        Inst::Split(at + 1, out)
+    } else {
+        Inst::Split(out, at + 1)
+    };
+}
+
+fn compile(prog: &mut Vec<Inst>, node: &Node) -> Result<(), String> {
+    match node {
+        Node::Group(index, alts) => {
+            if let Some(n) = *index {
+                emit(prog, Inst::Save(2 * n))?;
+            }
+            let mut jumps = Vec::new();
+            for (k, alt) in alts.iter().enumerate() {
+                let choice = if k + 1 < alts.len() {
+                    Some(split(prog)?)
+                } else {
+                    None
+                };
+                for node in alt {
+                    compile(prog, node)?;
+                }
+                if let Some(at) = choice {
+                    jumps.push(emit(prog, Inst::Jump(0))?);
+                    let next = prog.len();
+                    patch(prog, at, next, true);
+                }
+            }
+            let end = prog.len();
+            for at in jumps {
+                prog[at] = Inst::Jump(end);
+            }
+            if let Some(n) = *index {
+                emit(prog, Inst::Save(2 * n + 1))?;
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            let (min, max) = (*min.min(max), *min.max(max));
+            for _ in 0..min {
+                compile(prog, node)?;
+            }
+            if max == u32::MAX {
+                let at = split(prog)?;
+                compile(prog, node)?;
+                emit(prog, Inst::Jump(at))?;
+                let out = prog.len();
+                patch(prog, at, out, *greedy);
+            } else {
+                let mut choices = Vec::new();
+                for _ in min..max {
+                    choices.push(split(prog)?);
+                    compile(prog, node)?;
+                }
+                let out = prog.len();
+                for at in choices {
+                    patch(prog, at, out, *greedy);
+                }
+            }
+        }
+        _ => {
+            emit(prog, Inst::Step(node.clone()))?;
+        }
+    }
+    Ok(())
+}
+
+enum Job {
+    /// Go on at an instruction and a char.
+    Run(usize, usize),
+    /// Put back the old value of a capture slot.
+    Restore(usize, Option<usize>),
+}
+
+/// The instructions tried at each char from `from`, a row of `width` bits
+/// per char, grown as later chars are reached.
+struct Visited {
+    from: usize,
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl Visited {
+    /// Mark `pc` tried at char `i`, returning false if it already was.
+    fn insert(&mut self, pc: usize, i: usize) -> bool {
+        let bit = (i - self.from) * self.width + pc;
+        let word = bit / 64;
+        if word >= self.bits.len() {
+            self.bits.resize(word + 1, 0);
+        }
+        let mask = 1 << (bit % 64);
+        let new = self.bits[word] & mask == 0;
+        self.bits[word] |= mask;
+        new
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+    ignore_case: Option<bool>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek2(&self) -> Option<char> {
+        self.chars.get(self.pos + 1).copied()
+    }
+
+    /// Branches separated by `\|`, up to the end or a closing `\)`.
+    fn alternatives(&mut self, in_group: bool) -> Result<Vec<Vec<Node>>, String> {
+        let mut alts = vec![self.branch()?];
+        loop {
+            match (self.peek(), self.peek2()) {
+                (Some('\\'), Some('|')) => {
+                    self.pos += 2;
+                    alts.push(self.branch()?);
+                }
+                (Some('\\'), Some(')')) if in_group => {
+                    self.pos += 2;
+                    return Ok(alts);
+                }
+                (Some('\\'), Some(')')) => return Err("E55: Unmatched \\)".to_string()),
+                _ if in_group => return Err("E54: Unmatched \\(".to_string()),
+                _ => return Ok(alts),
+            }
+        }
+    }
+
+    fn branch(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == '\\' && matches!(self.peek2(), Some('|' | ')')) {
+                break;
+            }
+            self.pos += 1;
+            let node = match c {
+                '^' if self.pos - 1 == start => Node::LineStart,
+                '$' if self.at_branch_end() => Node::LineEnd,
+                '.' => Node::Any,
+                '[' => self.class().unwrap_or(Node::Char('[')),
+                '*' if !nodes.is_empty() => {
+                    self.quantify(&mut nodes, 0, u32::MAX, true);
+                    continue;
+                }
+                '\\' => {
+                    let Some(e) = self.peek() else {
+                        nodes.push(Node::Char('\\'));
+                        break;
+                    };
+                    self.pos += 1;
+                    match e {
+                        '(' => {
+                            self.groups += 1;
+                            let index = self.groups;
+                            Node::Group(Some(index), self.alternatives(true)?)
+                        }
+                        '%' if self.peek() == Some('(') => {
+                            self.pos += 1;
+                            Node::Group(None, self.alternatives(true)?)
+                        }
+                        '%' => return Err("E71: Invalid character after \\%".to_string()),
+                        '+' | '=' | '?' if !nodes.is_empty() => {
+                            let max = if e == '+' { u32::MAX } else { 1 };
+                            self.quantify(&mut nodes, u32::from(e == '+'), max, true);
+                            continue;
+                        }
+                        '{' if !nodes.is_empty() => {
+                            let (min, max, greedy) = self.brace()?;
+                            self.quantify(&mut nodes, min, max, greedy);
+                            continue;
+                        }
+                        '<' => Node::WordStart,
+                        '>' => Node::WordEnd,
+                        'c' | 'C' => {
+                            self.ignore_case = Some(e == 'c');
+                            continue;
+                        }
+                        't' => Node::Char('\t'),
+                        'e' => Node::Char('\x1b'),
+                        _ => match class_escape(e) {
+                            Some(node) => node,
+                            None if e.is_ascii_alphanumeric() || matches!(e, '@' | '&') => {
+                                return Err(format!("E867: Unknown operator '\\{e}'"));
+                            }
+                            None => Node::Char(e),
+                        },
+                    }
+                }
+                _ => Node::Char(c),
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// True when a `$` just consumed ends the branch.
+    fn at_branch_end(&self) -> bool {
+        matches!(
+            (self.peek(), self.peek2()),
+            (None, _) | (Some('\\'), Some('|' | ')'))
+        )
+    }
+
+    fn quantify(&mut self, nodes: &mut [Node], min: u32, max: u32, greedy: bool) {
+        let last = nodes.last_mut().expect("quantifier follows an atom");
+        let node = std::mem::replace(last, Node::Any);
+        *last = Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        };
+    }
+
+    /// The inside of `\{...}`: `n`, `n,`, `,m`, `n,m` or empty, with an
+    /// optional leading `-` for a non-greedy repeat.
+    fn brace(&mut self) -> Result<(u32, u32, bool), String> {
+        let end = self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '}')
+            .ok_or_else(|| "E60: Missing } after \\{".to_string())?;
+        let inner: String = self.chars[self.pos..self.pos + end].iter().collect();
+        self.pos += end + 1;
+        let inner = inner.trim_end_matches('\\');
+        let (greedy, inner) = match inner.strip_prefix('-') {
+            Some(rest) => (false, rest),
+            None => (true, inner),
+        };
+        let num = |s: &str, default| -> Result<u32, String> {
+            if s.is_empty() {
+                Ok(default)
+            } else {
+                s.parse()
+                    .map_err(|_| format!("E554: Syntax error in \\{{{inner}}}"))
+            }
+        };
+        let (min, max) = match inner.split_once(',') {
+            Some((lo, hi)) => (num(lo, 0)?, num(hi, u32::MAX)?),
+            None if inner.is_empty() => (0, u32::MAX),
+            None => {
+                let n = num(inner, 0)?;
+                (n, n)
+            }
+        };
+        Ok((min, max, greedy))
+    }
+
+    /// A `[...]` collection, with `[` already consumed. Returns `None`, with
+    /// nothing consumed, when there is no closing `]`.
+    fn class(&mut self) -> Option<Node> {
+        let start = self.pos;
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(c) = self.peek() else {
+                self.pos = start;
+                return None;
+            };
+            self.pos += 1;
+            let c = match c {
+                ']' if !first => return Some(Node::Class { items, negated }),
+                '\\' => match self.peek() {
+                    Some(e) => {
+                        self.pos += 1;
+                        match e {
+                            't' => '\t',
+                            'e' => '\x1b',
+                            'd' => {
+                                items.push(ClassItem::Digit);
+                                first = false;
+                                continue;
+                            }
+                            _ => e,
+                        }
+                    }
+                    None => '\\',
+                },
+                c => c,
+            };
+            first = false;
+            if self.peek() == Some('-') && self.peek2().is_some_and(|n| n != ']') {
+                let hi = self.chars[self.pos + 1];
+                self.pos += 2;
+                items.push(ClassItem::Range(c, hi));
+            } else {
+                items.push(ClassItem::Range(c, c));
+            }
+        }
+    }
+}
+
+/// The class for an escape such as `\d`, if it is one.
+fn class_escape(e: char) -> Option<Node> {
+    let (item, negated) = match e {
+        's' => (ClassItem::Blank, false),
+        'S' => (ClassItem::Blank, true),
+        'd' => (ClassItem::Digit, false),
+        'D' => (ClassItem::Digit, true),
+        'w' => (ClassItem::Word, false),
+        'W' => (ClassItem::Word, true),
+        'a' => (ClassItem::Alpha, false),
+        'A' => (ClassItem::Alpha, true),
+        'l' => (ClassItem::Lower, false),
+        'L' => (ClassItem::Lower, true),
+        'u' => (ClassItem::Upper, false),
+        'U' => (ClassItem::Upper, true),
+        _ => return None,
+    };
+    Some(Node::Class {
+        items: vec![item],
+        negated,
+    })
+}
+
+/// Escape `text` so it matches literally.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '.' | '*' | '[' | '~' | '^' | '$') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,45 @@
+//! Editing the line typed after `:` or `/`.
+//!
+//! The engine keeps the text and a cursor in it, counted in graphemes;
+//! these functions apply the keys that type, move the cursor or delete.
+
+use crate::key::KeyCode;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Byte offset of grapheme `col` in `line`, or its length past the end.
+fn offset(line: &str, col: usize) -> usize {
+    line.grapheme_indices(true)
+        .nth(col)
+        .map_or(line.len(), |(i, _)| i)
+}
+
+/// Insert `ch` at the cursor and move the cursor after it.
+pub(crate) fn insert(line: &mut String, col: &mut usize, ch: char) {
+    let before = line.graphemes(true).count();
+    line.insert(offset(line, *col), ch);
+    // A combining character joins the grapheme before it
+    *col += line.graphemes(true).count() - before;
+}
+
+/// Apply a key that moves the cursor or deletes at it: Left, Right, Home,
+/// End, Backspace and Delete. Returns false for any other key.
+pub(crate) fn edit(line: &mut String, col: &mut usize, code: KeyCode) -> bool {
+    let len = line.graphemes(true).count();
+    *col = (*col).min(len);
+    match code {
+        KeyCode::Left => *col = col.saturating_sub(1),
+        KeyCode::Right => *col = (*col + 1).min(len),
+        KeyCode::Home => *col = 0,
+        KeyCode::End => *col = len,
+        KeyCode::Backspace if *col > 0 => {
+            *col -= 1;
+            line.replace_range(offset(line, *col)..offset(line, *col + 1), "");
+        }
+        KeyCode::Delete if *col < len => {
+            line.replace_range(offset(line, *col)..offset(line, *col + 1), "");
+        }
+        KeyCode::Backspace | KeyCode::Delete => {}
+        _ => return false,
+    }
+    true
+}
@@ -0,0 +1,233 @@
+//! Vim-style registers.
+//!
+//! Yanks, deletes and puts go through [`Registers`] rather than the host
+//! clipboard. The host [`Clipboard`](crate::traits::Clipboard) only backs the
+//! selection registers `+` and `*`; everything else lives in the engine.
+//!
+//! | Register | Contents |
+//! |----------|----------|
+//! | `"`      | Unnamed: the text of the last yank, delete or change |
+//! | `a`–`z`  | Named; writing to `A`–`Z` appends |
+//! | `0`      | The last yank |
+//! | `1`–`9`  | Delete history; `1` holds the most recent multi-line delete |
+//! | `-`      | The last delete within one line |
+//! | `_`      | Black hole: writing discards, reading is empty |
+//! | `.`      | Read-only: the last inserted text |
+//! | `/`      | Read-only: the last search pattern |
+//! | `:`      | Read-only: the last command line |
+//! | `+`, `*` | The host clipboard |
+//!
+//! Recording a macro with `q{reg}` stores the keys in a register as text, so
+//! it can be put and edited like any other contents, and any register can be
+//! executed with `@{reg}`.
+
+use crate::key::{self, InputEvent};
+
+/// How register contents are put back into the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    /// Characters, pasted inside the line after the cursor.
+    CharWise,
+    /// Whole lines, pasted below the cursor line. The text ends with `\n`.
+    LineWise,
+    /// A rectangle from visual block mode, one row per line of the text,
+    /// pasted as a block after the cursor column.
+    Block,
+}
+
+/// The contents of a single register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    /// The stored text.
+    pub text: String,
+    /// Whether the text was yanked or deleted linewise.
+    pub kind: RegisterKind,
+    /// The exact events of a recorded macro; `text` is their rendering.
+    pub(crate) keys: Option<Vec<InputEvent>>,
+}
+
+impl Register {
+    /// Create a characterwise register value.
+    pub fn charwise(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: RegisterKind::CharWise,
+            keys: None,
+        }
+    }
+
+    /// Create a linewise register value, adding the trailing newline if missing.
+    pub fn linewise(text: impl Into<String>) -> Self {
+        let mut text = text.into();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        Self {
+            text,
+            kind: RegisterKind::LineWise,
+            keys: None,
+        }
+    }
+
+    /// Create a blockwise register value, one row per line of `text`.
+    pub fn blockwise(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: RegisterKind::Block,
+            keys: None,
+        }
+    }
+
+    /// A register holding a recorded macro.
+    pub(crate) fn recorded(keys: Vec<InputEvent>) -> Self {
+        Self {
+            text: key::keys_to_text(&keys),
+            kind: RegisterKind::CharWise,
+            keys: Some(keys),
+        }
+    }
+
+    /// Guess the kind of text coming from outside the engine, such as the
+    /// system clipboard: text ending in a newline is treated as linewise.
+    pub fn from_external(text: String) -> Self {
+        if text.ends_with('\n') {
+            Self::linewise(text)
+        } else {
+            Self::charwise(text)
+        }
+    }
+}
+
+/// Returns true for names accepted after the `"` prefix.
+pub fn is_valid_register(name: char) -> bool {
+    name.is_ascii_alphanumeric() || matches!(name, '"' | '-' | '_' | '.' | '/' | ':' | '+' | '*')
+}
+
+/// Returns true for names accepted after `q` to record a macro.
+pub fn is_recording_register(name: char) -> bool {
+    name.is_ascii_alphanumeric() || name == '"'
+}
+
+/// Returns true for the registers backed by the host clipboard.
+pub fn is_clipboard_register(name: char) -> bool {
+    matches!(name, '+' | '*')
+}
+
+/// The engine's register file.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    unnamed: Option<Register>,
+    named: [Option<Register>; 26],
+    numbered: [Option<Register>; 10],
+    small_delete: Option<Register>,
+    last_inserted: Option<Register>,
+    last_search: Option<Register>,
+    last_command: Option<Register>,
+}
+
+impl Registers {
+    /// Read a register. The clipboard registers `+` and `*` are not stored
+    /// here and always return `None`; the engine reads them from the host.
+    pub fn get(&self, name: char) -> Option<&Register> {
+        match name {
+            '"' => self.unnamed.as_ref(),
+            'a'..='z' => self.named[(name as u8 - b'a') as usize].as_ref(),
+            'A'..='Z' => self.named[(name as u8 - b'A') as usize].as_ref(),
+            '0'..='9' => self.numbered[(name as u8 - b'0') as usize].as_ref(),
+            '-' => self.small_delete.as_ref(),
+            '.' => self.last_inserted.as_ref(),
+            '/' => self.last_search.as_ref(),
+            ':' => self.last_command.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Write a register directly, as a host would to preload contents.
+    ///
+    /// Uppercase names append, read-only and clipboard registers are ignored,
+    /// and the unnamed register is not updated.
+    pub fn set(&mut self, name: char, value: Register) {
+        match name {
+            '"' => self.unnamed = Some(value),
+            'a'..='z' => self.named[(name as u8 - b'a') as usize] = Some(value),
+            'A'..='Z' => {
+                let slot = &mut self.named[(name as u8 - b'A') as usize];
+                *slot = Some(match slot.take() {
+                    Some(prev) => append(prev, value),
+                    None => value,
+                });
+            }
+            '0'..='9' => self.numbered[(name as u8 - b'0') as usize] = Some(value),
+            '-' => self.small_delete = Some(value),
+            _ => {}
+        }
+    }
+
+    /// Store yanked text in `name`, or in `0` when no register was given.
+    pub(crate) fn record_yank(&mut self, name: Option<char>, value: Register) {
+        match name {
+            Some('_') => {}
+            None | Some('"') => {
+                self.numbered[0] = Some(value.clone());
+                self.unnamed = Some(value);
+            }
+            Some(name) => {
+                self.set(name, value);
+                self.unnamed = self.get(name).cloned();
+            }
+        }
+    }
+
+    /// Store deleted text in `name`, or in the delete history when no register
+    /// was given. Deletes within one line go to the small-delete register.
+    pub(crate) fn record_delete(&mut self, name: Option<char>, value: Register) {
+        match name {
+            Some('_') => {}
+            None | Some('"') => {
+                if value.kind == RegisterKind::CharWise && !value.text.contains('\n') {
+                    self.small_delete = Some(value.clone());
+                } else {
+                    self.numbered[1..].rotate_right(1);
+                    self.numbered[1] = Some(value.clone());
+                }
+                self.unnamed = Some(value);
+            }
+            Some(name) => {
+                self.set(name, value);
+                self.unnamed = self.get(name).cloned();
+            }
+        }
+    }
+
+    pub(crate) fn set_unnamed(&mut self, value: Register) {
+        self.unnamed = Some(value);
+    }
+
+    pub(crate) fn set_last_inserted(&mut self, text: &str) {
+        self.last_inserted = Some(Register::charwise(text));
+    }
+
+    pub(crate) fn set_last_search(&mut self, text: &str) {
+        self.last_search = Some(Register::charwise(text));
+    }
+
+    pub(crate) fn set_last_command(&mut self, text: &str) {
+        self.last_command = Some(Register::charwise(text));
+    }
+}
+
+fn append(prev: Register, value: Register) -> Register {
+    match (prev.kind, value.kind) {
+        (RegisterKind::CharWise, RegisterKind::CharWise) => Register {
+            text: prev.text + &value.text,
+            kind: RegisterKind::CharWise,
+            // Appending to a macro with "qA" keeps the exact keys
+            keys: prev.keys.zip(value.keys).map(|(mut a, b)| {
+                a.extend(b);
+                a
+            }),
+        },
+        // Appending linewise text, or to linewise text, gives whole lines
+        _ => Register::linewise(Register::linewise(prev.text).text + &value.text),
+    }
+}
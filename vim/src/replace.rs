@@ -0,0 +1,111 @@
+//! Replace mode (`R`): typed text overwrites the text under the cursor.
+//!
+//! When replacing starts on a line, the rest of the line is saved, so
+//! Backspace can put back what was overwritten. Text typed past the end of
+//! the line has nothing to put back and is just deleted.
+
+use crate::traits::TextOps;
+use crate::types::{Command, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Where replacing started on a line, and the text it may overwrite.
+#[derive(Debug, Clone)]
+pub(crate) struct Origin {
+    start: Position,
+    original: Vec<String>, // graphemes from `start` to the end of the line
+    split: Option<(Position, String)>, // a line made by Enter: the break and the blanks it removed
+}
+
+impl Origin {
+    /// Start replacing at `start`, saving the rest of its line.
+    pub(crate) fn new<T: TextOps>(text: &T, start: Position) -> Self {
+        let end = Position {
+            line: start.line,
+            col: text.line_len(start.line),
+        };
+        let rest = text.slice_to_string(Range { start, end });
+        Origin {
+            start,
+            original: rest.graphemes(true).map(str::to_string).collect(),
+            split: None,
+        }
+    }
+
+    /// Continue on the line Enter made, which starts at `start`. The break
+    /// was inserted at `at`, where Enter removed the blanks `removed`;
+    /// `rest` is the text it moved to the new line.
+    pub(crate) fn after_break(start: Position, at: Position, removed: String, rest: &str) -> Self {
+        Origin {
+            start,
+            original: rest.graphemes(true).map(str::to_string).collect(),
+            split: Some((at, removed)),
+        }
+    }
+
+    /// What Backspace does at `cursor`: put back the grapheme overwritten
+    /// before it, or undo the line break at the start of the line. `None`
+    /// when the cursor is not after anything replaced from this origin.
+    pub(crate) fn backspace(&self, cursor: Position) -> Option<(Position, Vec<Command>)> {
+        if cursor == self.start {
+            let (at, removed) = self.split.as_ref()?;
+            let mut cmds = vec![Command::Delete {
+                range: Range {
+                    start: *at,
+                    end: cursor,
+                },
+            }];
+            if !removed.is_empty() {
+                cmds.push(Command::InsertText {
+                    at: *at,
+                    text: removed.clone(),
+                });
+            }
+            return Some((*at, cmds));
+        }
+        if cursor.line != self.start.line || cursor.col < self.start.col {
+            return None;
+        }
+        let pos = Position {
+            line: cursor.line,
+            col: cursor.col - 1,
+        };
+        let mut cmds = vec![Command::Delete {
+            range: Range {
+                start: pos,
+                end: cursor,
+            },
+        }];
+        if let Some(g) = self.original.get((pos.col - self.start.col) as usize) {
+            cmds.push(Command::InsertText {
+                at: pos,
+                text: g.clone(),
+            });
+        }
+        Some((pos, cmds))
+    }
+}
+
+/// Overwrite the text at `cursor` with `typed`, one grapheme for each one
+/// typed. Line breaks in `typed` are inserted without overwriting anything,
+/// and past the end of the line the text is added.
+pub(crate) fn overwrite<T: TextOps>(text: &T, cursor: Position, typed: &str) -> Vec<Command> {
+    let width: usize = typed
+        .split('\n')
+        .map(|part| part.graphemes(true).count())
+        .sum();
+    let end = Position {
+        line: cursor.line,
+        col: (cursor.col + width as u32).min(text.line_len(cursor.line)),
+    };
+    let mut cmds = Vec::new();
+    if cursor < end {
+        cmds.push(Command::Delete {
+            range: Range { start: cursor, end },
+        });
+    }
+    cmds.push(Command::InsertText {
+        at: cursor,
+        text: typed.to_string(),
+    });
+    cmds
+}
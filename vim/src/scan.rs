@@ -0,0 +1,670 @@
+use crate::traits::TextOps;
+use crate::types::{Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Character classes used by word-oriented scanning.
+///
+/// Mirrors Vim's `cls()`: blanks (including the end-of-line slot) are class 0,
+/// punctuation is class 1 and keyword characters are class 2. For WORD-based
+/// scanning every non-blank is treated as a keyword character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CharClass {
+    Blank,
+    Punct,
+    Word,
+}
+
+pub(crate) fn char_class(g: &str, bigword: bool) -> CharClass {
+    match g.chars().next() {
+        None => CharClass::Blank,
+        Some(ch) if ch.is_whitespace() => CharClass::Blank,
+        Some(_) if bigword => CharClass::Word,
+        Some(ch) if ch.is_alphanumeric() || ch == '_' => CharClass::Word,
+        Some(_) => CharClass::Punct,
+    }
+}
+
+/// True when the grapheme `g` is exactly the character `ch`.
+pub(crate) fn is_char(g: &str, ch: char) -> bool {
+    let mut chars = g.chars();
+    chars.next() == Some(ch) && chars.next().is_none()
+}
+
+/// Returns the graphemes of a line as owned strings.
+pub(crate) fn line_graphemes<T: TextOps + ?Sized>(text: &T, line: u32) -> Vec<String> {
+    let len = text.line_len(line);
+    if len == 0 {
+        return Vec::new();
+    }
+    let s = text.slice_to_string(Range {
+        start: Position { line, col: 0 },
+        end: Position { line, col: len },
+    });
+    s.graphemes(true).map(str::to_string).collect()
+}
+
+/// The screen columns grapheme `g` takes when it starts at screen column
+/// `vcol`: a tab reaches the next tab stop and wide characters take two.
+pub(crate) fn display_width(g: &str, vcol: u32, tabstop: u32) -> u32 {
+    if is_char(g, '\t') {
+        let tabstop = tabstop.max(1);
+        tabstop - vcol % tabstop
+    } else {
+        (g.width() as u32).max(1)
+    }
+}
+
+/// The screen column of `pos`.
+pub(crate) fn display_col<T: TextOps>(text: &T, pos: Position, tabstop: u32) -> u32 {
+    line_graphemes(text, pos.line)
+        .iter()
+        .take(pos.col as usize)
+        .fold(0, |col, g| col + display_width(g, col, tabstop))
+}
+
+/// The column of the grapheme at screen column `vcol` of `line`, or of the
+/// last one when the line is shorter.
+pub(crate) fn col_at_display<T: TextOps>(text: &T, line: u32, vcol: u32, tabstop: u32) -> u32 {
+    let mut start = 0;
+    for (col, g) in line_graphemes(text, line).iter().enumerate() {
+        start += display_width(g, start, tabstop);
+        if start > vcol {
+            return col as u32;
+        }
+    }
+    text.line_len(line).saturating_sub(1)
+}
+
+/// Returns the column of the first non-blank grapheme on a line.
+///
+/// For lines that are empty or entirely blank this is the line length.
+pub(crate) fn first_non_blank<T: TextOps>(text: &T, line: u32) -> u32 {
+    let end = Position {
+        line,
+        col: text.line_len(line),
+    };
+    first_non_blank_in(&text.slice_to_string(Range {
+        start: Position { line, col: 0 },
+        end,
+    }))
+}
+
+/// [`first_non_blank`] for a line that is already a string.
+pub(crate) fn first_non_blank_in(line: &str) -> u32 {
+    line.graphemes(true)
+        .take_while(|g| char_class(g, false) == CharClass::Blank)
+        .count() as u32
+}
+
+/// How a [`Scanner`] step moved, like the return values of Vim's `inc()`/`dec()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// Moved within the line, not onto the end-of-line slot.
+    InLine,
+    /// Moved onto the end-of-line slot or to another line.
+    LineBreak,
+    /// Already at the start or end of the buffer.
+    Edge,
+}
+
+/// A grapheme cursor that walks the buffer the way Vim's `inc()`/`dec()` do.
+///
+/// Every line has `line_len + 1` slots; the last slot is the end-of-line
+/// position, which classifies as blank. Lines are cached one at a time.
+pub(crate) struct Scanner<'a, T: TextOps + ?Sized> {
+    text: &'a T,
+    line: u32,
+    col: u32,
+    graphemes: Vec<String>,
+}
+
+impl<'a, T: TextOps + ?Sized> Scanner<'a, T> {
+    pub(crate) fn new(text: &'a T, pos: Position) -> Self {
+        let pos = text.clamp(pos);
+        Self {
+            text,
+            line: pos.line,
+            col: pos.col,
+            graphemes: line_graphemes(text, pos.line),
+        }
+    }
+
+    pub(crate) fn pos(&self) -> Position {
+        Position {
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    /// The grapheme under the cursor, or `None` on the end-of-line slot.
+    pub(crate) fn current(&self) -> Option<&str> {
+        self.graphemes.get(self.col as usize).map(String::as_str)
+    }
+
+    /// True when the grapheme under the cursor is `ch`.
+    pub(crate) fn at(&self, ch: char) -> bool {
+        self.current().is_some_and(|g| is_char(g, ch))
+    }
+
+    /// True when the cursor is on the indent of its line.
+    pub(crate) fn in_indent(&self) -> bool {
+        (self.col as usize) < self.graphemes.len()
+            && self.graphemes[..=self.col as usize]
+                .iter()
+                .all(|g| char_class(g, false) == CharClass::Blank)
+    }
+
+    pub(crate) fn class(&self, bigword: bool) -> CharClass {
+        self.current()
+            .map_or(CharClass::Blank, |g| char_class(g, bigword))
+    }
+
+    /// Move forward one slot. Returns false at the end of the buffer.
+    pub(crate) fn next(&mut self) -> bool {
+        if (self.col as usize) < self.graphemes.len() {
+            self.col += 1;
+            return true;
+        }
+        if self.line + 1 >= self.text.line_count() {
+            return false;
+        }
+        self.line += 1;
+        self.col = 0;
+        self.graphemes = line_graphemes(self.text, self.line);
+        true
+    }
+
+    /// Move back one slot. Returns false at the start of the buffer.
+    pub(crate) fn prev(&mut self) -> bool {
+        if self.col > 0 {
+            self.col -= 1;
+            return true;
+        }
+        if self.line == 0 {
+            return false;
+        }
+        self.line -= 1;
+        self.graphemes = line_graphemes(self.text, self.line);
+        self.col = self.graphemes.len() as u32;
+        true
+    }
+
+    /// Move forward one slot, reporting how the move went.
+    pub(crate) fn inc(&mut self) -> Step {
+        let line = self.line;
+        if !self.next() {
+            Step::Edge
+        } else if self.line != line || self.current().is_none() {
+            Step::LineBreak
+        } else {
+            Step::InLine
+        }
+    }
+
+    /// Move back one slot, reporting how the move went.
+    pub(crate) fn dec(&mut self) -> Step {
+        let line = self.line;
+        if !self.prev() {
+            Step::Edge
+        } else if self.line != line {
+            Step::LineBreak
+        } else {
+            Step::InLine
+        }
+    }
+
+    /// Like [`Scanner::dec`], but skips the end-of-line slot of a non-empty line.
+    pub(crate) fn decl(&mut self) -> Step {
+        let step = self.dec();
+        if step == Step::LineBreak && self.col > 0 {
+            self.dec()
+        } else {
+            step
+        }
+    }
+
+    /// Like [`Scanner::inc`], but skips the end-of-line slot of a non-empty line.
+    pub(crate) fn incl(&mut self) -> Step {
+        let step = self.inc();
+        if step == Step::LineBreak && self.col > 0 {
+            self.inc()
+        } else {
+            step
+        }
+    }
+
+    /// Move one grapheme left without leaving the line.
+    pub(crate) fn oneleft(&mut self) -> bool {
+        if self.col == 0 {
+            return false;
+        }
+        self.col -= 1;
+        true
+    }
+
+    /// True on the only slot of an empty line.
+    pub(crate) fn on_empty_line(&self) -> bool {
+        self.col == 0 && self.graphemes.is_empty()
+    }
+
+    /// Move while the class stays `class`. Returns false at a buffer edge.
+    fn skip_class(&mut self, class: CharClass, bigword: bool, forward: bool) -> bool {
+        while self.class(bigword) == class {
+            let step = if forward { self.inc() } else { self.dec() };
+            if step == Step::Edge {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Port of Vim's `back_in_line()`: move to the start of the run of
+    /// graphemes of the current class, without leaving the line.
+    pub(crate) fn back_in_line(&mut self, bigword: bool) {
+        let class = self.class(bigword);
+        while self.col > 0 {
+            self.col -= 1;
+            if self.class(bigword) != class {
+                self.col += 1;
+                break;
+            }
+        }
+    }
+
+    /// Port of Vim's `fwd_word()`: move to the start of the `count`th next word.
+    ///
+    /// With `eol` set the last word stops at the end of its line. Returns
+    /// false when starting on the last grapheme of the buffer.
+    pub(crate) fn fwd_word(&mut self, count: u32, bigword: bool, eol: bool) -> bool {
+        for left in (0..count).rev() {
+            let sclass = self.class(bigword);
+            let last_line = self.line + 1 >= self.text.line_count();
+            let step = self.inc();
+            if step == Step::Edge || (step == Step::LineBreak && last_line) {
+                return false;
+            }
+            let stop_at_eol = eol && left == 0;
+            if step == Step::LineBreak && stop_at_eol {
+                return true;
+            }
+            // Go one past the end of the current word, if any
+            if sclass != CharClass::Blank {
+                while self.class(bigword) == sclass {
+                    let step = self.inc();
+                    if step == Step::Edge || (step == Step::LineBreak && stop_at_eol) {
+                        return true;
+                    }
+                }
+            }
+            // Then to the next non-blank, stopping on an empty line
+            while self.class(bigword) == CharClass::Blank {
+                if self.on_empty_line() {
+                    break;
+                }
+                let step = self.inc();
+                if step == Step::Edge || (step == Step::LineBreak && stop_at_eol) {
+                    return true;
+                }
+            }
+        }
+        true
+    }
+
+    /// Port of Vim's `bck_word()`: move to the start of the `count`th previous word.
+    ///
+    /// With `stop` set, the start of the word under the cursor counts as the
+    /// first one. Returns false when already at the start of the buffer.
+    pub(crate) fn bck_word(&mut self, count: u32, bigword: bool, mut stop: bool) -> bool {
+        'words: for _ in 0..count {
+            let sclass = self.class(bigword);
+            if self.dec() == Step::Edge {
+                return false;
+            }
+            if !stop || sclass == self.class(bigword) || sclass == CharClass::Blank {
+                // Skip the blanks before the word, stopping on an empty line
+                while self.class(bigword) == CharClass::Blank {
+                    if self.on_empty_line() {
+                        stop = false;
+                        continue 'words;
+                    }
+                    if self.dec() == Step::Edge {
+                        return true;
+                    }
+                }
+                let class = self.class(bigword);
+                if !self.skip_class(class, bigword, false) {
+                    return true;
+                }
+            }
+            // Overshot: forward one
+            self.inc();
+            stop = false;
+        }
+        true
+    }
+
+    /// Port of Vim's `bckend_word()`: move to the end of the `count`th previous word.
+    ///
+    /// With `eol` set the move stops when it reaches the end of the
+    /// previous line. Returns false when already at the start of the buffer.
+    pub(crate) fn bckend_word(&mut self, count: u32, bigword: bool, eol: bool) -> bool {
+        for _ in 0..count {
+            let sclass = self.class(bigword);
+            let step = self.dec();
+            if step == Step::Edge {
+                return false;
+            }
+            if eol && step == Step::LineBreak {
+                return true;
+            }
+            let stops = |step| step == Step::Edge || (eol && step == Step::LineBreak);
+            // Move back to before the start of this word
+            if sclass != CharClass::Blank {
+                while self.class(bigword) == sclass {
+                    if stops(self.dec()) {
+                        return true;
+                    }
+                }
+            }
+            // Then back to the end of the previous word
+            while self.class(bigword) == CharClass::Blank {
+                if self.on_empty_line() {
+                    break;
+                }
+                if stops(self.dec()) {
+                    return true;
+                }
+            }
+        }
+        true
+    }
+
+    /// Port of Vim's `end_word()`: move to the end of the `count`th word.
+    ///
+    /// See [`end_word`] for `stop`. With `empty` set an empty line counts as
+    /// a word. Returns false when the end of the buffer is reached first.
+    pub(crate) fn end_word(
+        &mut self,
+        count: u32,
+        bigword: bool,
+        mut stop: bool,
+        empty: bool,
+    ) -> bool {
+        'words: for _ in 0..count {
+            let sclass = self.class(bigword);
+            if self.inc() == Step::Edge {
+                return false;
+            }
+            if self.class(bigword) == sclass && sclass != CharClass::Blank {
+                // In the middle of a word: move to its end
+                if !self.skip_class(sclass, bigword, true) {
+                    return false;
+                }
+            } else if !stop || sclass == CharClass::Blank {
+                // At the end of a word: skip blanks, then to the end of the next word
+                while self.class(bigword) == CharClass::Blank {
+                    if empty && self.on_empty_line() {
+                        stop = false;
+                        continue 'words;
+                    }
+                    if self.inc() == Step::Edge {
+                        return false;
+                    }
+                }
+                let class = self.class(bigword);
+                if !self.skip_class(class, bigword, true) {
+                    return false;
+                }
+            }
+            // Overshot: back one
+            self.dec();
+            stop = false;
+        }
+        true
+    }
+}
+
+/// Port of Vim's `end_word()`: move to the end of the `count`th word.
+///
+/// When `stop` is set and the cursor is already on the end of a word, the
+/// first word is not skipped. This is what makes `cw` behave like `ce`.
+/// Returns `None` when the end of the buffer is reached first.
+pub(crate) fn end_word<T: TextOps + ?Sized>(
+    text: &T,
+    pos: Position,
+    count: u32,
+    bigword: bool,
+    stop: bool,
+) -> Option<Position> {
+    let mut sc = Scanner::new(text, pos);
+    sc.end_word(count, bigword, stop, false).then(|| sc.pos())
+}
+
+/// The start of the `count`th next word (`w`, `W`). After the last word of
+/// the buffer this is the end of its line.
+pub(crate) fn next_word_start<T: TextOps + ?Sized>(
+    text: &T,
+    pos: Position,
+    count: u32,
+    bigword: bool,
+) -> Position {
+    let mut sc = Scanner::new(text, pos);
+    sc.fwd_word(count, bigword, false);
+    sc.pos()
+}
+
+/// The start of the `count`th previous word (`b`, `B`).
+pub(crate) fn prev_word_start<T: TextOps + ?Sized>(
+    text: &T,
+    pos: Position,
+    count: u32,
+    bigword: bool,
+) -> Position {
+    let mut sc = Scanner::new(text, pos);
+    sc.bck_word(count, bigword, false);
+    sc.pos()
+}
+
+/// The end of the `count`th next word (`e`, `E`), or the last character of
+/// the buffer when it comes first.
+pub(crate) fn next_word_end<T: TextOps + ?Sized>(
+    text: &T,
+    pos: Position,
+    count: u32,
+    bigword: bool,
+) -> Position {
+    end_word(text, pos, count, bigword, false).unwrap_or_else(|| {
+        let line = text.line_count().saturating_sub(1);
+        let last = Position {
+            line,
+            col: text.line_len(line).saturating_sub(1),
+        };
+        pos.max(last)
+    })
+}
+
+/// The end of the `count`th previous word (`ge`, `gE`). An empty line
+/// counts as a word.
+pub(crate) fn prev_word_end<T: TextOps + ?Sized>(
+    text: &T,
+    pos: Position,
+    count: u32,
+    bigword: bool,
+) -> Position {
+    let mut sc = Scanner::new(text, pos);
+    sc.bckend_word(count, bigword, false);
+    sc.pos()
+}
+
+/// Find the `open` that is not closed before `pos`, or with `forward` the
+/// `close` that is not opened after it, skipping nested pairs. The grapheme
+/// at `pos` itself is not looked at.
+pub(crate) fn find_unmatched<T: TextOps>(
+    text: &T,
+    pos: Position,
+    open: char,
+    close: char,
+    forward: bool,
+) -> Option<Position> {
+    let (target, nested) = if forward {
+        (close, open)
+    } else {
+        (open, close)
+    };
+    let mut sc = Scanner::new(text, pos);
+    let mut depth = 0u32;
+    loop {
+        let step = if forward { sc.inc() } else { sc.dec() };
+        if step == Step::Edge {
+            return None;
+        }
+        if sc.at(target) {
+            if depth == 0 {
+                return Some(sc.pos());
+            }
+            depth -= 1;
+        } else if sc.at(nested) {
+            depth += 1;
+        }
+    }
+}
+
+/// The bracket pairs `%` jumps between.
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Port of Vim's `%` without a count: the bracket matching the first `(`,
+/// `)`, `[`, `]`, `{` or `}` at or after `pos` on its line, skipping nested
+/// pairs. `None` when the line has no bracket there or it is not matched.
+pub(crate) fn find_match<T: TextOps>(text: &T, pos: Position) -> Option<Position> {
+    let graphemes = line_graphemes(text, pos.line);
+    let (col, (open, close), forward) = graphemes
+        .iter()
+        .enumerate()
+        .skip(pos.col as usize)
+        .find_map(|(col, g)| {
+            BRACKETS.iter().find_map(|&pair| {
+                if is_char(g, pair.0) {
+                    Some((col, pair, true))
+                } else if is_char(g, pair.1) {
+                    Some((col, pair, false))
+                } else {
+                    None
+                }
+            })
+        })?;
+    let at = Position {
+        line: pos.line,
+        col: col as u32,
+    };
+    find_unmatched(text, at, open, close, forward)
+}
+
+/// True for the blanks Vim's sentence and paragraph code looks at: space and tab.
+pub(crate) fn is_white<T: TextOps>(sc: &Scanner<'_, T>) -> bool {
+    sc.at(' ') || sc.at('\t')
+}
+
+/// True when the grapheme under the cursor is one of `chars`.
+fn at_any<T: TextOps>(sc: &Scanner<'_, T>, chars: &str) -> bool {
+    chars.chars().any(|ch| sc.at(ch))
+}
+
+/// Port of Vim's `findsent()`: the start of the `count`th sentence after
+/// `pos`, or before it when `forward` is false (the `)` and `(` motions).
+///
+/// A sentence ends at a `.`, `!` or `?` followed by any closing `)`, `]`,
+/// `"` or `'` and then a blank or the end of the line. Empty lines are
+/// sentence and paragraph boundaries. Returns `None` when the buffer ends
+/// before `count` sentences are found.
+pub(crate) fn find_sentence<T: TextOps>(
+    text: &T,
+    pos: Position,
+    forward: bool,
+    count: u32,
+) -> Option<Position> {
+    let mut sc = Scanner::new(text, pos);
+    let step = |sc: &mut Scanner<'_, T>| if forward { sc.incl() } else { sc.decl() };
+    for left in (0..count).rev() {
+        let mut noskip = false;
+        'found: {
+            if sc.current().is_none() {
+                // On an empty line: skip to a non-empty one
+                while step(&mut sc) != Step::Edge && sc.current().is_none() {}
+                if forward {
+                    break 'found;
+                }
+            } else if !forward {
+                sc.decl();
+            }
+
+            // Go back over blanks and sentence-ending punctuation
+            let mut found_dot = false;
+            while is_white(&sc) || at_any(&sc, ".!?)]\"'") {
+                let mut prev = Scanner::new(text, sc.pos());
+                if prev.decl() == Step::Edge || (prev.on_empty_line() && forward) || found_dot {
+                    break;
+                }
+                if at_any(&sc, ".!?") {
+                    found_dot = true;
+                }
+                if at_any(&sc, ")]\"'") && !at_any(&prev, ".!?)]\"'") {
+                    break;
+                }
+                sc.decl();
+            }
+
+            // Find the end of the sentence
+            let start_line = sc.pos().line;
+            loop {
+                if sc.current().is_none() {
+                    // An empty line ends the sentence
+                    if !forward && sc.pos().line != start_line {
+                        sc = Scanner::new(
+                            text,
+                            Position {
+                                line: sc.pos().line + 1,
+                                col: 0,
+                            },
+                        );
+                    }
+                    break;
+                }
+                if at_any(&sc, ".!?") {
+                    let mut next = Scanner::new(text, sc.pos());
+                    let mut edge = false;
+                    loop {
+                        if next.inc() == Step::Edge {
+                            edge = true;
+                            break;
+                        }
+                        if !at_any(&next, ")]\"'") {
+                            break;
+                        }
+                    }
+                    if edge || next.current().is_none() || is_white(&next) {
+                        sc = next;
+                        if sc.current().is_none() {
+                            sc.inc();
+                        }
+                        break;
+                    }
+                }
+                if step(&mut sc) == Step::Edge {
+                    if left > 0 {
+                        return None;
+                    }
+                    noskip = true;
+                    break;
+                }
+            }
+        }
+        // Skip the blanks before the next sentence
+        while !noskip && is_white(&sc) {
+            if sc.incl() == Step::Edge {
+                break;
+            }
+        }
+    }
+    Some(sc.pos())
+}
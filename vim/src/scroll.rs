@@ -0,0 +1,89 @@
+//! Commands that depend on what the host shows: `H`, `M` and `L`, scrolling
+//! with Ctrl-D, Ctrl-U, Ctrl-F, Ctrl-B, Ctrl-E and Ctrl-Y, and `zt`, `zz` and
+//! `zb`.
+//!
+//! Each function works out the new top line of the viewport and the line the
+//! cursor has to go to; the engine emits the scroll and the cursor move.
+
+use crate::types::Viewport;
+
+/// The last line on screen.
+fn bottom(view: Viewport, line_count: u32) -> u32 {
+    (view.top + view.height.max(1) - 1).min(line_count.saturating_sub(1))
+}
+
+/// The line `H`, `M` or `L` goes to: `count` lines from the top or the
+/// bottom of the screen, or the middle of the lines on it.
+pub(crate) fn screen_line(view: Viewport, line_count: u32, key: char, count: u32) -> u32 {
+    let top = view.top.min(line_count.saturating_sub(1));
+    let bottom = bottom(view, line_count).max(top);
+    match key {
+        'H' => (top + count - 1).min(bottom),
+        'L' => bottom.saturating_sub(count - 1).max(top),
+        _ => top + (bottom - top) / 2,
+    }
+}
+
+/// Scroll `amount` lines `down` or up (Ctrl-E, Ctrl-Y, Ctrl-F, Ctrl-B): the
+/// new top line and where the cursor on `line` has to go to stay on screen.
+/// The last line may scroll up to the top. `None` when there is nothing
+/// more to scroll.
+pub(crate) fn scroll(
+    view: Viewport,
+    line_count: u32,
+    line: u32,
+    amount: u32,
+    down: bool,
+) -> Option<(u32, u32)> {
+    let last = line_count.saturating_sub(1);
+    if down {
+        if view.top >= last {
+            return None;
+        }
+        let top = (view.top + amount).min(last);
+        Some((top, line.max(top)))
+    } else {
+        if view.top == 0 {
+            return None;
+        }
+        let top = view.top.saturating_sub(amount);
+        Some((top, line.min(top + view.height.max(1) - 1)))
+    }
+}
+
+/// Scroll the text and move the cursor on `line` by `amount` lines `down`
+/// or up (Ctrl-D, Ctrl-U). Scrolling down stops once the last line is on
+/// screen, but the cursor goes on to it. `None` when the cursor is already
+/// on the last or the first line.
+pub(crate) fn half_page(
+    view: Viewport,
+    line_count: u32,
+    line: u32,
+    amount: u32,
+    down: bool,
+) -> Option<(u32, u32)> {
+    let last = line_count.saturating_sub(1);
+    if down {
+        if line >= last {
+            return None;
+        }
+        let end_top = line_count.saturating_sub(view.height).max(view.top);
+        Some(((view.top + amount).min(end_top), (line + amount).min(last)))
+    } else {
+        if line == 0 {
+            return None;
+        }
+        Some((view.top.saturating_sub(amount), line.saturating_sub(amount)))
+    }
+}
+
+/// The top line that shows `line` at the top (`zt`), in the middle (`zz`)
+/// or at the bottom (`zb`) of the screen.
+pub(crate) fn place(view: Viewport, line: u32, key: char) -> u32 {
+    let below = view.height.max(1) - 1;
+    match key {
+        't' => line,
+        'z' => line.saturating_sub(below / 2),
+        _ => line.saturating_sub(below),
+    }
+}
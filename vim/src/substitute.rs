@@ -0,0 +1,401 @@
+//! `:s[ubstitute]`: argument parsing, replacement strings and line edits.
+//!
+//! In the replacement, `&` and `\0` stand for the whole match, `\1`–`\9` for
+//! groups and `~` for the previous replacement string. `\r` breaks the line,
+//! `\t` is a tab and `\u`, `\l` (next character) and `\U`, `\L` (until `\e`
+//! or `\E`) change case.
+
+use crate::pattern::{self, Captures, Pattern};
+use crate::traits::TextOps;
+use crate::types::{Command, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Flags after the last delimiter of `:s`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Flags {
+    pub(crate) global: bool,      // g: every match in the line
+    pub(crate) confirm: bool,     // c: ask before each substitution
+    pub(crate) count_only: bool,  // n: report the number of matches only
+    pub(crate) no_error: bool,    // e: no error when nothing matches
+    pub(crate) ignore_case: bool, // i, cleared by I
+}
+
+/// A substitution, remembered for `:s` without a pattern, `:&` and `g&`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Substitute {
+    pub(crate) pattern: String,
+    pub(crate) replacement: String, // with `~` already expanded
+    pub(crate) flags: Flags,
+}
+
+/// Parse the arguments of `:s` (or `:&` when `repeat` is set) into the
+/// substitution to run and the count that may follow the flags.
+///
+/// An empty pattern reuses `last`'s pattern, or the last search. Without a
+/// pattern the last substitution is repeated with the new flags, keeping
+/// the old ones when the flags start with `&`.
+pub(crate) fn parse(
+    args: &str,
+    repeat: bool,
+    last: Option<&Substitute>,
+    last_search: Option<&str>,
+) -> Result<(Substitute, Option<u32>), String> {
+    let no_previous = || "E35: No previous regular expression".to_string();
+    let mut chars = args.chars().peekable();
+
+    let delim = chars.peek().copied().filter(|&c| {
+        !repeat
+            && !c.is_alphanumeric()
+            && !c.is_whitespace()
+            && !matches!(c, '\\' | '"' | '|' | '&')
+    });
+    let (sub, rest) = match delim {
+        Some(delim) => {
+            chars.next();
+            let pattern = until_delimiter(&mut chars, delim);
+            let raw = until_delimiter(&mut chars, delim);
+            let pattern = match (pattern.is_empty(), last, last_search) {
+                (false, ..) => pattern,
+                (true, Some(last), _) => last.pattern.clone(),
+                (true, None, Some(search)) => pattern::escape(search),
+                (true, None, None) => return Err(no_previous()),
+            };
+            let previous = last.map_or("", |l| l.replacement.as_str());
+            let sub = Substitute {
+                pattern,
+                replacement: expand_tilde(&raw, previous),
+                flags: Flags::default(),
+            };
+            (sub, chars.collect::<String>())
+        }
+        None => {
+            let last = last.ok_or_else(no_previous)?;
+            let sub = Substitute {
+                flags: Flags::default(),
+                ..last.clone()
+            };
+            (sub, chars.collect())
+        }
+    };
+    let (mut sub, mut rest) = (sub, rest.as_str());
+    if let Some(after) = rest.strip_prefix('&') {
+        sub.flags = last.map(|l| l.flags).unwrap_or_default();
+        rest = after;
+    }
+    parse_flags(sub, rest)
+}
+
+/// Read up to an unescaped `delim`. A backslash before the delimiter is
+/// dropped; other escapes are kept for the pattern compiler or [`expand`].
+fn until_delimiter(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, delim: char) -> String {
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        if c == delim {
+            break;
+        }
+        if c == '\\'
+            && let Some(&next) = chars.peek()
+        {
+            chars.next();
+            if next != delim {
+                out.push('\\');
+            }
+            out.push(next);
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Replace unescaped `~` with the previous replacement string.
+fn expand_tilde(raw: &str, previous: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '~' => out.push_str(previous),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn parse_flags(mut sub: Substitute, rest: &str) -> Result<(Substitute, Option<u32>), String> {
+    let mut chars = rest.trim_start().char_indices().peekable();
+    while let Some(&(_, c)) = chars.peek() {
+        match c {
+            'g' => sub.flags.global = true,
+            'c' => sub.flags.confirm = true,
+            'n' => sub.flags.count_only = true,
+            'e' => sub.flags.no_error = true,
+            'i' => sub.flags.ignore_case = true,
+            'I' => sub.flags.ignore_case = false,
+            _ => break,
+        }
+        chars.next();
+    }
+    let rest: String = chars.map(|(_, c)| c).collect();
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return Ok((sub, None));
+    }
+    match rest.parse::<u32>() {
+        Ok(0) => Err("E939: Positive count required".to_string()),
+        Ok(count) => Ok((sub, Some(count))),
+        Err(_) => Err(format!("E488: Trailing characters: {rest}")),
+    }
+}
+
+/// One line as chars, with the grapheme column of each char.
+#[derive(Debug, Clone)]
+pub(crate) struct LineText {
+    pub(crate) chars: Vec<char>,
+    cols: Vec<u32>, // one more than chars, for the end of the line
+}
+
+impl LineText {
+    /// The text of line `line` of the buffer.
+    pub(crate) fn of<T: TextOps>(text: &T, line: u32) -> Self {
+        Self::new(&text.slice_to_string(Range {
+            start: text.line_start(line),
+            end: Position {
+                line,
+                col: text.line_len(line),
+            },
+        }))
+    }
+
+    pub(crate) fn new(line: &str) -> Self {
+        let mut chars = Vec::with_capacity(line.len());
+        let mut cols = Vec::with_capacity(line.len() + 1);
+        let mut col = 0;
+        for g in line.graphemes(true) {
+            for c in g.chars() {
+                chars.push(c);
+                cols.push(col);
+            }
+            col += 1;
+        }
+        cols.push(col);
+        Self { chars, cols }
+    }
+
+    /// Grapheme column of char `index`.
+    pub(crate) fn col(&self, index: usize) -> u32 {
+        self.cols[index.min(self.chars.len())]
+    }
+}
+
+/// A match in a line and the text replacing it, in char indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Replacement {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) text: String,
+}
+
+/// The next match at or after char `from` and its replacement.
+pub(crate) fn next_match(
+    pattern: &Pattern,
+    template: &str,
+    line: &LineText,
+    from: usize,
+) -> Option<Replacement> {
+    let caps = pattern.find_at(&line.chars, from)?;
+    let (start, end) = caps[0]?;
+    Some(Replacement {
+        start,
+        end,
+        text: expand(template, &line.chars, &caps),
+    })
+}
+
+/// The match after one that ended at char `end` and was `empty` or not. As
+/// in Vim, the search stops at the end of the line and an empty match where
+/// the last one ended is passed over.
+pub(crate) fn match_after(
+    pattern: &Pattern,
+    template: &str,
+    line: &LineText,
+    end: usize,
+    empty: bool,
+) -> Option<Replacement> {
+    let find = |from: usize| {
+        (from < line.chars.len())
+            .then(|| next_match(pattern, template, line, from))
+            .flatten()
+    };
+    let rep = find(end + usize::from(empty))?;
+    if rep.start == end && rep.end == end {
+        return find(end + 1);
+    }
+    Some(rep)
+}
+
+/// The matches in `line` after `prev`.
+pub(crate) fn matches_after(
+    pattern: &Pattern,
+    template: &str,
+    line: &LineText,
+    prev: &Replacement,
+) -> Vec<Replacement> {
+    let after =
+        |rep: &Replacement| match_after(pattern, template, line, rep.end, rep.end == rep.start);
+    std::iter::successors(after(prev), after).collect()
+}
+
+/// The matches in `line`: the first one, or all of them.
+pub(crate) fn matches_in_line(
+    pattern: &Pattern,
+    template: &str,
+    line: &LineText,
+    global: bool,
+) -> Vec<Replacement> {
+    let Some(first) = next_match(pattern, template, line, 0) else {
+        return Vec::new();
+    };
+    let rest = if global {
+        matches_after(pattern, template, line, &first)
+    } else {
+        Vec::new()
+    };
+    std::iter::once(first).chain(rest).collect()
+}
+
+/// Commands making `reps` on line `line_no`. They are emitted from right to
+/// left so each position still refers to the text before the earlier ones.
+pub(crate) fn edits(line_no: u32, line: &LineText, reps: &[Replacement]) -> Vec<Command> {
+    let mut cmds = Vec::new();
+    for rep in reps.iter().rev() {
+        let start = Position {
+            line: line_no,
+            col: line.col(rep.start),
+        };
+        if rep.end > rep.start {
+            cmds.push(Command::Delete {
+                range: Range {
+                    start,
+                    end: Position {
+                        line: line_no,
+                        col: line.col(rep.end),
+                    },
+                },
+            });
+        }
+        if !rep.text.is_empty() {
+            cmds.push(Command::InsertText {
+                at: start,
+                text: rep.text.clone(),
+            });
+        }
+    }
+    cmds
+}
+
+/// The text of `line` once `reps` are made.
+pub(crate) fn apply(line: &LineText, reps: &[Replacement]) -> String {
+    let mut out = String::new();
+    let mut at = 0;
+    for rep in reps {
+        out.extend(&line.chars[at..rep.start]);
+        out.push_str(&rep.text);
+        at = rep.end;
+    }
+    out.extend(&line.chars[at..]);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+/// Build the replacement for one match.
+fn expand(template: &str, text: &[char], caps: &Captures) -> String {
+    let mut out = String::new();
+    let mut one: Option<Case> = None; // \u, \l
+    let mut all: Option<Case> = None; // \U, \L
+    fn push(out: &mut String, c: char, one: &mut Option<Case>, all: Option<Case>) {
+        match one.take().or(all) {
+            Some(Case::Upper) => out.extend(c.to_uppercase()),
+            Some(Case::Lower) => out.extend(c.to_lowercase()),
+            None => out.push(c),
+        }
+    }
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        let group = match c {
+            '&' => Some(0),
+            '\\' => match chars.next() {
+                Some(d @ '0'..='9') => Some(d as usize - '0' as usize),
+                Some('r') => {
+                    out.push('\n');
+                    None
+                }
+                Some('n') => {
+                    // As in Vim, `\n` inserts a NUL
+                    out.push('\0');
+                    None
+                }
+                Some('t') => {
+                    push(&mut out, '\t', &mut one, all);
+                    None
+                }
+                Some('u') => {
+                    one = Some(Case::Upper);
+                    None
+                }
+                Some('l') => {
+                    one = Some(Case::Lower);
+                    None
+                }
+                Some('U') => {
+                    all = Some(Case::Upper);
+                    None
+                }
+                Some('L') => {
+                    all = Some(Case::Lower);
+                    None
+                }
+                Some('e' | 'E') => {
+                    all = None;
+                    None
+                }
+                Some(other) => {
+                    push(&mut out, other, &mut one, all);
+                    None
+                }
+                None => {
+                    push(&mut out, '\\', &mut one, all);
+                    None
+                }
+            },
+            _ => {
+                push(&mut out, c, &mut one, all);
+                None
+            }
+        };
+        if let Some((start, end)) = group.and_then(|n| caps.get(n).copied().flatten()) {
+            for &c in &text[start..end] {
+                push(&mut out, c, &mut one, all);
+            }
+        }
+    }
+    out
+}
+
+/// Vim's summary, e.g. "3 substitutions on 2 lines".
+pub(crate) fn report(count: u32, lines: u32, what: &str) -> String {
+    let plural = |n: u32, s: &str| if n == 1 { String::new() } else { s.to_string() };
+    format!(
+        "{count} {what}{} on {lines} line{}",
+        plural(count, if what.ends_with('h') { "es" } else { "s" }),
+        plural(lines, "s")
+    )
+}
@@ -0,0 +1,995 @@
+use crate::scan::{self, CharClass, Scanner, Step};
+use crate::traits::TextOps;
+use crate::types::Position;
+
+/// The area a text object covers, in Vim's terms.
+///
+/// `anchor` is where the object starts (the visual anchor in visual mode)
+/// and `cursor` is where the cursor ends up. `cursor` itself is part of the
+/// object only when `inclusive` is set. Linewise objects cover whole lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Selected {
+    pub(crate) anchor: Position,
+    pub(crate) cursor: Position,
+    pub(crate) inclusive: bool,
+    pub(crate) linewise: bool,
+}
+
+/// What an operator applies to once a text object is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Area {
+    /// The half-open range between two positions.
+    Chars(Position, Position),
+    /// Whole lines, from the first to the last.
+    Lines(u32, u32),
+}
+
+impl Selected {
+    /// The area an operator applies to.
+    pub(crate) fn area<T: TextOps>(&self, text: &T) -> Area {
+        let (start, mut end) = if self.anchor <= self.cursor {
+            (self.anchor, self.cursor)
+        } else {
+            (self.cursor, self.anchor)
+        };
+        if self.linewise {
+            return Area::Lines(start.line, end.line);
+        }
+        if self.inclusive {
+            end.col = (end.col + 1).min(text.line_len(end.line));
+        } else if end.col == 0 && end.line > start.line {
+            // An exclusive end at the start of a line stops at the end of the
+            // previous one, and makes the operator linewise when only indent
+            // comes before the start
+            if start.col <= scan::first_non_blank(text, start.line) {
+                return Area::Lines(start.line, end.line - 1);
+            }
+            end = Position {
+                line: end.line - 1,
+                col: text.line_len(end.line - 1),
+            };
+        }
+        Area::Chars(start, end)
+    }
+}
+
+/// Resolve the text object named by `obj` (the key after `i` or `a`).
+///
+/// `visual` is the visual anchor when the object is used in visual mode,
+/// where it extends a selection that is already larger than the cursor.
+/// `around` selects the `a` variant. Returns `None` for unknown objects and
+/// when the object cannot be found.
+pub(crate) fn select<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    obj: char,
+    count: u32,
+    around: bool,
+) -> Option<Selected> {
+    match obj {
+        'w' => current_word(text, visual, cursor, count, around, false),
+        'W' => current_word(text, visual, cursor, count, around, true),
+        '(' | ')' | 'b' => current_block(text, visual, cursor, count, around, '(', ')'),
+        '[' | ']' => current_block(text, visual, cursor, count, around, '[', ']'),
+        '{' | '}' | 'B' => current_block(text, visual, cursor, count, around, '{', '}'),
+        '<' | '>' => current_block(text, visual, cursor, count, around, '<', '>'),
+        '"' | '\'' | '`' => current_quote(text, visual, cursor, count, around, obj),
+        't' => current_tag(text, visual, cursor, count, around),
+        's' => current_sentence(text, visual, cursor, count, around),
+        'p' => current_paragraph(text, visual, cursor, count, around),
+        _ => None,
+    }
+}
+
+/// Port of Vim's `current_word()`: the `iw`, `aw`, `iW` and `aW` objects.
+///
+/// Inner objects count runs of blanks as words of their own. Around objects
+/// take the blanks after the word, or the ones before it when there are none
+/// after, but never the indent of the line.
+fn current_word<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    mut count: u32,
+    include: bool,
+    bigword: bool,
+) -> Option<Selected> {
+    let anchor = visual.filter(|&anchor| anchor != cursor);
+    let mut sc = Scanner::new(text, cursor);
+    let mut start = anchor.unwrap_or(cursor);
+    let mut inclusive = true;
+    let mut include_white = false;
+
+    if anchor.is_none() {
+        // Go to the start of the word or blanks under the cursor
+        sc.back_in_line(bigword);
+        start = sc.pos();
+        if (sc.class(bigword) == CharClass::Blank) == include {
+            // "iw" on a word or "aw" on blanks: up to the end of the word
+            if !sc.end_word(1, bigword, true, true) {
+                return None;
+            }
+        } else {
+            // "aw" on a word or "iw" on blanks: up to the start of the next word
+            sc.fwd_word(1, bigword, true);
+            if sc.pos().col == 0 {
+                sc.decl();
+            } else {
+                sc.oneleft();
+            }
+            include_white = include;
+        }
+        count -= 1;
+    }
+
+    // Each further count adds a word or a run of blanks
+    while count > 0 {
+        inclusive = true;
+        if anchor.is_some_and(|anchor| sc.pos() < anchor) {
+            // Extending a visual selection backwards
+            if sc.dec() == Step::Edge {
+                return None;
+            }
+            if include != (sc.class(bigword) != CharClass::Blank) {
+                if !sc.bck_word(1, bigword, true) {
+                    return None;
+                }
+            } else {
+                if !sc.bckend_word(1, bigword, true) {
+                    return None;
+                }
+                sc.inc();
+            }
+        } else {
+            if sc.inc() == Step::Edge {
+                return None;
+            }
+            if include != (sc.class(bigword) == CharClass::Blank) {
+                if !sc.fwd_word(1, bigword, true) && count > 1 {
+                    return None;
+                }
+                if !sc.oneleft() {
+                    inclusive = false;
+                }
+            } else if !sc.end_word(1, bigword, true, true) {
+                return None;
+            }
+        }
+        count -= 1;
+    }
+
+    if include_white && (sc.class(bigword) != CharClass::Blank || (sc.pos().col == 0 && !inclusive))
+    {
+        // No blanks after the word: take the ones before it, unless that is the indent
+        let mut back = Scanner::new(text, start);
+        if back.oneleft() {
+            back.back_in_line(bigword);
+            if back.class(bigword) == CharClass::Blank && back.pos().col > 0 {
+                start = back.pos();
+            }
+        }
+    }
+
+    Some(Selected {
+        anchor: start,
+        cursor: sc.pos(),
+        inclusive,
+        linewise: false,
+    })
+}
+
+/// Port of Vim's `current_block()`: the `i(`, `i[`, `i{` and `i<` objects,
+/// their `a` variants and the `b`/`B` aliases.
+///
+/// Inner blocks also leave out a line break after the opening bracket and
+/// the indent before the closing one, so that `di{` on a block spread over
+/// several lines deletes whole lines.
+fn current_block<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    count: u32,
+    include: bool,
+    open: char,
+    close: char,
+) -> Option<Selected> {
+    let anchor = visual.filter(|&anchor| anchor != cursor);
+    let (mut old_start, mut old_end) = (cursor, cursor);
+    let mut from = cursor;
+    match anchor {
+        None => {
+            let mut sc = Scanner::new(text, cursor);
+            if open == '{' {
+                // Ignore indent
+                while sc.in_indent() {
+                    if sc.inc() != Step::InLine {
+                        break;
+                    }
+                }
+            }
+            // On an opening bracket, that is the innermost block
+            if sc.at(open) {
+                sc.inc();
+            }
+            from = sc.pos();
+        }
+        Some(anchor) if anchor < cursor => {
+            old_start = anchor;
+            from = anchor;
+        }
+        Some(anchor) => old_end = anchor,
+    }
+
+    let mut start = from;
+    for _ in 0..count {
+        start = scan::find_unmatched(text, start, open, close, false)?;
+    }
+    let mut end = scan::find_unmatched(text, start, open, close, true)?;
+
+    let mut sol = false;
+    if !include {
+        loop {
+            let mut first = Scanner::new(text, start);
+            first.incl();
+            let mut last = Scanner::new(text, end);
+            sol = end.col == 0;
+            last.decl();
+            while last.in_indent() {
+                sol = true;
+                if last.decl() != Step::InLine {
+                    break;
+                }
+            }
+            let (first, last) = (first.pos(), last.pos());
+            if anchor.is_some() && first >= old_start && last <= old_end && first != last {
+                // The selection already covers the inside: go to the enclosing block
+                let mut sc = Scanner::new(text, old_start);
+                sc.decl();
+                start = scan::find_unmatched(text, sc.pos(), open, close, false)?;
+                end = scan::find_unmatched(text, start, open, close, true)?;
+                continue;
+            }
+            (start, end) = (first, last);
+            break;
+        }
+    }
+
+    if visual.is_some() {
+        // Include the line break before an unindented closing bracket
+        let mut sc = Scanner::new(text, end);
+        if sol && sc.current().is_some() {
+            sc.inc();
+        }
+        return Some(Selected {
+            anchor: start,
+            cursor: sc.pos(),
+            inclusive: true,
+            linewise: false,
+        });
+    }
+    Some(if sol {
+        let mut sc = Scanner::new(text, end);
+        sc.incl();
+        Selected {
+            anchor: start,
+            cursor: sc.pos(),
+            inclusive: false,
+            linewise: false,
+        }
+    } else if start <= end {
+        Selected {
+            anchor: start,
+            cursor: end,
+            inclusive: true,
+            linewise: false,
+        }
+    } else {
+        // Nothing between the brackets
+        Selected {
+            anchor: start,
+            cursor: start,
+            inclusive: false,
+            linewise: false,
+        }
+    })
+}
+
+/// Column of the next `quote` at or after `col`, skipping backslash escapes
+/// when `escape` is set.
+fn find_next_quote(line: &[String], mut col: usize, quote: char, escape: bool) -> Option<usize> {
+    loop {
+        let g = line.get(col)?;
+        if escape && scan::is_char(g, '\\') {
+            col += 1;
+            if col >= line.len() {
+                return None;
+            }
+        } else if scan::is_char(g, quote) {
+            return Some(col);
+        }
+        col += 1;
+    }
+}
+
+/// Column of the previous `quote` before `col`, or 0 when there is none.
+fn find_prev_quote(line: &[String], mut col: usize, quote: char, escape: bool) -> usize {
+    while col > 0 {
+        col -= 1;
+        let mut escapes = 0;
+        if escape {
+            while col > escapes && scan::is_char(&line[col - escapes - 1], '\\') {
+                escapes += 1;
+            }
+        }
+        if escapes % 2 == 1 {
+            col -= escapes;
+        } else if scan::is_char(&line[col], quote) {
+            break;
+        }
+    }
+    col
+}
+
+/// Port of Vim's `current_quote()`: the `i"`, `a"`, `i'` and `` i` `` objects.
+///
+/// Quotes never span lines. Which quotes pair up is decided from the start
+/// of the line, and when the cursor is not inside a quoted string the first
+/// one after it is used. The `a` variants take the blanks after the closing
+/// quote, or before the opening one when there are none after.
+fn current_quote<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    count: u32,
+    include: bool,
+    quote: char,
+) -> Option<Selected> {
+    let line = scan::line_graphemes(text, cursor.line);
+    let at = |col: usize| line.get(col).is_some_and(|g| scan::is_char(g, quote));
+    let blank = |col: usize| {
+        line.get(col)
+            .is_some_and(|g| scan::char_class(g, false) == CharClass::Blank)
+    };
+
+    let cur = cursor.col as usize;
+    let mut vis_bef_curs = false;
+    let mut vis_empty = true;
+    let mut inside_quotes = false;
+    let mut selected_quote = false;
+    if let Some(anchor) = visual {
+        // This only works within one line
+        if anchor.line != cursor.line {
+            return None;
+        }
+        vis_bef_curs = anchor < cursor;
+        vis_empty = anchor == cursor;
+        if !vis_empty {
+            let (lo, hi) = if vis_bef_curs {
+                (anchor.col as usize, cur)
+            } else {
+                (cur, anchor.col as usize)
+            };
+            // Does the selection span exactly the text inside quotes?
+            inside_quotes = lo > 0 && at(lo - 1) && at(hi + 1);
+            selected_quote = (lo..=hi.min(line.len())).any(at);
+        }
+    }
+
+    let mut col_start = cur;
+    let mut col_end;
+    if !vis_empty && at(col_start) {
+        // Already selecting something and on a quote: the next quoted string
+        if vis_bef_curs {
+            col_start = find_next_quote(&line, col_start + 1, quote, false)?;
+            match find_next_quote(&line, col_start + 1, quote, true) {
+                Some(col) => col_end = col,
+                None => (col_start, col_end) = (cur, col_start),
+            }
+        } else {
+            col_end = find_prev_quote(&line, col_start, quote, false);
+            if !at(col_end) {
+                return None;
+            }
+            col_start = find_prev_quote(&line, col_end, quote, true);
+            if !at(col_start) {
+                (col_start, col_end) = (col_end, cur);
+            }
+        }
+    } else if at(col_start) || !vis_empty {
+        // On a quote, which may open or close a string: pair the quotes
+        // up from the start of the line to find out
+        let first_col = if vis_empty {
+            col_start
+        } else if vis_bef_curs {
+            find_next_quote(&line, col_start, quote, false)?
+        } else {
+            find_prev_quote(&line, col_start, quote, false)
+        };
+        col_start = 0;
+        loop {
+            col_start = find_next_quote(&line, col_start, quote, false)?;
+            if col_start > first_col {
+                return None;
+            }
+            col_end = find_next_quote(&line, col_start + 1, quote, true)?;
+            if first_col <= col_end {
+                break;
+            }
+            col_start = col_end + 1;
+        }
+    } else {
+        col_start = find_prev_quote(&line, col_start, quote, true);
+        if !at(col_start) {
+            // No quote before the cursor: use the string after it
+            col_start = find_next_quote(&line, col_start, quote, false)?;
+        }
+        col_end = find_next_quote(&line, col_start + 1, quote, true)?;
+    }
+
+    if include {
+        if blank(col_end + 1) {
+            while blank(col_end + 1) {
+                col_end += 1;
+            }
+        } else {
+            while col_start > 0 && blank(col_start - 1) {
+                col_start -= 1;
+            }
+        }
+    }
+
+    // After vi" another i" includes the quotes, and so does a count of 2
+    if !include && count < 2 && (vis_empty || !inside_quotes) {
+        col_start += 1;
+    }
+    let pos = |col: usize| Position {
+        line: cursor.line,
+        col: col as u32,
+    };
+    let mut anchor = pos(col_start);
+    if let Some(vis) = visual {
+        let vcol = vis.col as usize;
+        if !(vis_empty
+            || (vis_bef_curs
+                && !selected_quote
+                && (inside_quotes || (!at(vcol) && (vcol == 0 || !at(vcol - 1))))))
+        {
+            anchor = vis;
+        }
+    }
+
+    // The end is exclusive, so it is after the closing quote when that is included
+    let end = if include || count > 1 || (!vis_empty && inside_quotes) {
+        col_end + 1
+    } else {
+        col_end
+    };
+    if visual.is_none() {
+        return Some(Selected {
+            anchor,
+            cursor: pos(end),
+            inclusive: false,
+            linewise: false,
+        });
+    }
+    let mut cursor = pos(end - 1);
+    if !vis_empty && !vis_bef_curs {
+        // The cursor is at the start of the selection: mostly keep it there
+        let vcol = anchor.col as usize;
+        if inside_quotes || (!selected_quote && !at(vcol) && !at(vcol + 1)) {
+            anchor = cursor;
+        }
+        cursor = pos(col_start);
+    }
+    Some(Selected {
+        anchor,
+        cursor,
+        inclusive: true,
+        linewise: false,
+    })
+}
+
+/// A start tag and its matching end tag.
+#[derive(Debug, Clone, Copy)]
+struct TagBlock {
+    start: Position,       // '<' of the start tag
+    start_close: Position, // '>' of the start tag
+    end_open: Position,    // '<' of the end tag
+    end: Position,         // '>' of the end tag
+}
+
+/// Pair up the HTML/XML tags of the buffer.
+///
+/// Tag names match case-insensitively. Self-closing tags, comments and
+/// start tags without an end tag (such as `<br>`) are left out.
+fn tag_blocks<T: TextOps>(text: &T) -> Vec<TagBlock> {
+    // The buffer as one character per grapheme, with '\n' at line ends
+    let mut chars = Vec::new();
+    for line in 0..text.line_count() {
+        let graphemes = scan::line_graphemes(text, line);
+        for (col, g) in graphemes.iter().enumerate() {
+            let ch = g.chars().next().unwrap_or(' ');
+            chars.push((
+                Position {
+                    line,
+                    col: col as u32,
+                },
+                ch,
+            ));
+        }
+        let col = graphemes.len() as u32;
+        chars.push((Position { line, col }, '\n'));
+    }
+
+    let mut blocks = Vec::new();
+    let mut open: Vec<(String, Position, Position)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].1 != '<' {
+            i += 1;
+            continue;
+        }
+        let end_tag = chars.get(i + 1).is_some_and(|&(_, c)| c == '/');
+        let name_start = i + 1 + end_tag as usize;
+        let mut j = name_start;
+        while let Some(&(_, c)) = chars.get(j)
+            && !(c.is_whitespace() || matches!(c, '>' | '/' | '<' | '!'))
+        {
+            j += 1;
+        }
+        if j == name_start {
+            i += 1;
+            continue;
+        }
+        let name: String = chars[name_start..j]
+            .iter()
+            .map(|&(_, c)| c.to_ascii_lowercase())
+            .collect();
+        let Some(close) = (j..chars.len()).find(|&k| chars[k].1 == '>') else {
+            break;
+        };
+        if end_tag {
+            if let Some(k) = open.iter().rposition(|(n, _, _)| *n == name) {
+                let (_, start, start_close) = open[k].clone();
+                open.truncate(k);
+                blocks.push(TagBlock {
+                    start,
+                    start_close,
+                    end_open: chars[i].0,
+                    end: chars[close].0,
+                });
+            }
+        } else if chars[close - 1].1 != '/' {
+            open.push((name, chars[i].0, chars[close].0));
+        }
+        i = close + 1;
+    }
+    blocks
+}
+
+/// The `it` and `at` objects: the text between a pair of tags, or the
+/// whole element including the tags.
+///
+/// Starting on a tag selects that element. In visual mode a selection that
+/// already covers an object grows to the next larger one.
+fn current_tag<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    count: u32,
+    include: bool,
+) -> Option<Selected> {
+    let anchor = visual.filter(|&anchor| anchor != cursor);
+    let (lo, hi) = match anchor {
+        Some(anchor) => (anchor.min(cursor), anchor.max(cursor)),
+        None => {
+            // Ignore indent
+            let mut sc = Scanner::new(text, cursor);
+            while sc.in_indent() {
+                if sc.inc() != Step::InLine {
+                    break;
+                }
+            }
+            (sc.pos(), sc.pos())
+        }
+    };
+
+    let mut blocks: Vec<TagBlock> = tag_blocks(text)
+        .into_iter()
+        .filter(|b| b.start <= lo && hi <= b.end)
+        .collect();
+    // Innermost first
+    blocks.sort_by_key(|b| std::cmp::Reverse(b.start));
+
+    // Each element offers its inside (for "it") and then the whole of it
+    let areas = blocks.iter().flat_map(|b| {
+        let mut first = Scanner::new(text, b.start_close);
+        first.incl();
+        let mut last = Scanner::new(text, b.end_open);
+        last.dec();
+        let inner = (!include).then_some((first.pos(), last.pos(), b.end_open, false));
+        inner.into_iter().chain([(b.start, b.end, b.end, true)])
+    });
+    let (first, last, end, around) = match anchor {
+        None => areas.filter(|a| a.3 == include).nth(count as usize - 1)?,
+        Some(_) => areas
+            .filter(|&(first, last, _, _)| first <= lo && hi <= last && (first, last) != (lo, hi))
+            .nth(count as usize - 1)?,
+    };
+
+    if first > last {
+        // Nothing between the tags
+        return Some(Selected {
+            anchor: first,
+            cursor: first,
+            inclusive: visual.is_some(),
+            linewise: false,
+        });
+    }
+    Some(match (visual, around) {
+        (Some(_), _) | (None, true) => Selected {
+            anchor: first,
+            cursor: last,
+            inclusive: true,
+            linewise: false,
+        },
+        (None, false) => Selected {
+            anchor: first,
+            cursor: end,
+            inclusive: false,
+            linewise: false,
+        },
+    })
+}
+
+fn white_at<T: TextOps>(text: &T, pos: Position) -> bool {
+    scan::is_white(&Scanner::new(text, pos))
+}
+
+fn incl<T: TextOps>(text: &T, pos: Position) -> (Position, Step) {
+    let mut sc = Scanner::new(text, pos);
+    let step = sc.incl();
+    (sc.pos(), step)
+}
+
+fn decl<T: TextOps>(text: &T, pos: Position) -> Position {
+    let mut sc = Scanner::new(text, pos);
+    sc.decl();
+    sc.pos()
+}
+
+/// The start of the next or previous sentence, or `pos` when there is none.
+fn sentence<T: TextOps>(text: &T, pos: Position, forward: bool) -> Position {
+    scan::find_sentence(text, pos, forward, 1).unwrap_or(pos)
+}
+
+/// Port of Vim's `find_first_blank()`: back to the first of the blanks before `pos`.
+fn find_first_blank<T: TextOps>(text: &T, pos: Position) -> Position {
+    let mut sc = Scanner::new(text, pos);
+    while sc.decl() != Step::Edge {
+        if !scan::is_white(&sc) {
+            sc.incl();
+            break;
+        }
+    }
+    sc.pos()
+}
+
+/// Port of Vim's `findsent_forward()`: move over `count` sentences and the
+/// blanks between them, alternately.
+fn sentence_forward<T: TextOps>(
+    text: &T,
+    mut pos: Position,
+    count: u32,
+    mut at_start_sent: bool,
+) -> Position {
+    for left in (0..count).rev() {
+        pos = sentence(text, pos, true);
+        if at_start_sent {
+            pos = find_first_blank(text, pos);
+        }
+        if left == 0 || at_start_sent {
+            pos = decl(text, pos);
+        }
+        at_start_sent = !at_start_sent;
+    }
+    pos
+}
+
+/// Port of Vim's `current_sent()`: the `is` and `as` objects.
+///
+/// The blanks between sentences count as an object of their own for `is`.
+/// `as` takes the blanks after the sentence, or before it when there are
+/// none after.
+fn current_sentence<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    mut count: u32,
+    include: bool,
+) -> Option<Selected> {
+    let mut cur = sentence(text, cursor, true);
+
+    if let Some(anchor) = visual.filter(|&anchor| anchor != cursor) {
+        // Extend the selection at the cursor's end
+        let mut pos = cursor;
+        if cursor < anchor {
+            // Are we in the blanks before a sentence, or in a sentence?
+            let mut at_start_sent = true;
+            while pos != cur {
+                if !white_at(text, pos) {
+                    at_start_sent = false;
+                    break;
+                }
+                let (next, step) = incl(text, pos);
+                if step == Step::Edge {
+                    break;
+                }
+                pos = next;
+            }
+            if !at_start_sent {
+                cur = sentence(text, cur, false);
+                if cur == cursor {
+                    at_start_sent = true;
+                } else {
+                    cur = sentence(text, cur, true);
+                }
+            }
+            if include {
+                count *= 2;
+            }
+            for _ in 0..count {
+                cur = sentence(text, cur, false);
+                if at_start_sent {
+                    cur = find_first_blank(text, cur);
+                }
+                if !at_start_sent || (!include && !white_at(text, cur)) {
+                    cur = sentence(text, cur, false);
+                }
+                at_start_sent = !at_start_sent;
+            }
+        } else {
+            // Are we just before a sentence, in the blanks before one, or in one?
+            pos = incl(text, pos).0;
+            let mut at_start_sent = true;
+            if pos != cur {
+                at_start_sent = false;
+                while pos < cur {
+                    if !white_at(text, pos) {
+                        at_start_sent = true;
+                        break;
+                    }
+                    let (next, step) = incl(text, pos);
+                    if step == Step::Edge {
+                        break;
+                    }
+                    pos = next;
+                }
+                cur = if at_start_sent {
+                    sentence(text, cur, false)
+                } else {
+                    cursor
+                };
+            }
+            if include {
+                count *= 2;
+            }
+            cur = sentence_forward(text, cur, count, at_start_sent);
+        }
+        return Some(Selected {
+            anchor,
+            cursor: cur,
+            inclusive: true,
+            linewise: false,
+        });
+    }
+
+    // Starting on blanks just before a sentence selects those blanks
+    let mut pos = cursor;
+    while white_at(text, pos) {
+        let (next, step) = incl(text, pos);
+        if step == Step::Edge {
+            break;
+        }
+        pos = next;
+    }
+    let start_blank = pos == cur;
+    let mut start = if start_blank {
+        find_first_blank(text, cursor)
+    } else {
+        cur = sentence(text, cur, false);
+        cur
+    };
+    let ncount = if include {
+        count * 2
+    } else {
+        count - start_blank as u32
+    };
+    cur = if ncount > 0 {
+        sentence_forward(text, cur, ncount, true)
+    } else {
+        decl(text, cur)
+    };
+
+    if include {
+        // Blanks in front of the sentence exclude the ones after it, and
+        // with no blanks after it the ones in front are taken
+        if start_blank {
+            cur = find_first_blank(text, cur);
+            if white_at(text, cur) {
+                cur = decl(text, cur);
+            }
+        } else if !white_at(text, cur) {
+            start = find_first_blank(text, start);
+        }
+    }
+
+    if visual.is_some() {
+        return Some(Selected {
+            anchor: start,
+            cursor: cur,
+            inclusive: true,
+            linewise: false,
+        });
+    }
+    // Include a line break after the sentence, if there is one
+    Some(match incl(text, cur) {
+        (_, Step::Edge) => Selected {
+            anchor: start,
+            cursor: cur,
+            inclusive: true,
+            linewise: false,
+        },
+        (next, _) => Selected {
+            anchor: start,
+            cursor: next,
+            inclusive: false,
+            linewise: false,
+        },
+    })
+}
+
+/// True for empty lines and lines of only blanks.
+fn line_white<T: TextOps>(text: &T, line: u32) -> bool {
+    scan::first_non_blank(text, line) == text.line_len(line)
+}
+
+/// Port of Vim's `current_par()`: the `ip` and `ap` objects.
+///
+/// Both are linewise. `ip` counts a run of blank lines as a paragraph of
+/// its own; `ap` takes the blank lines after the paragraph, or before it
+/// when there are none after.
+fn current_paragraph<T: TextOps>(
+    text: &T,
+    visual: Option<Position>,
+    cursor: Position,
+    count: u32,
+    include: bool,
+) -> Option<Selected> {
+    if let Some(anchor) = visual.filter(|anchor| anchor.line != cursor.line) {
+        return extend_paragraph(text, anchor, cursor.line, count, include);
+    }
+    // Lines are signed here: the end starts out one line above the start
+    let last = i64::from(text.line_count()) - 1;
+    let white = |line: i64| line_white(text, line as u32);
+
+    // First move back to the start of the paragraph or of the blank lines
+    let mut start = i64::from(cursor.line);
+    let white_in_front = white(start);
+    while start > 0 && white(start - 1) == white_in_front {
+        start -= 1;
+    }
+
+    // Then past the end of any blank lines
+    let mut end = start;
+    while end <= last && white(end) {
+        end += 1;
+    }
+    end -= 1;
+
+    let mut left = count;
+    if !include && white_in_front {
+        left -= 1;
+    }
+    let mut do_white = false;
+    while left > 0 {
+        left -= 1;
+        if end == last {
+            return None;
+        }
+        if !include {
+            do_white = white(end + 1);
+        }
+        if include || !do_white {
+            // Skip to the end of the paragraph
+            end += 1;
+            while end < last && !white(end + 1) {
+                end += 1;
+            }
+        }
+        if left == 0 && white_in_front && include {
+            break;
+        }
+        if include || do_white {
+            // Skip to the end of the blank lines after it
+            while end < last && white(end + 1) {
+                end += 1;
+            }
+        }
+    }
+
+    // Without blank lines at the end, "ap" takes the ones at the start
+    if !white_in_front && !white(end) && include {
+        while start > 0 && white(start - 1) {
+            start -= 1;
+        }
+    }
+
+    let (start, end) = (start as u32, end as u32);
+    let anchor = match visual {
+        // A single blank line would give the same area: extend it instead
+        Some(anchor) if anchor.line == start && anchor.line == end => {
+            return extend_paragraph(text, anchor, start, count, include);
+        }
+        Some(anchor) if anchor.line == start => anchor,
+        _ => Position {
+            line: start,
+            col: 0,
+        },
+    };
+    Some(Selected {
+        anchor,
+        cursor: Position { line: end, col: 0 },
+        inclusive: true,
+        linewise: true,
+    })
+}
+
+/// Grow a visual selection by `count` paragraphs from the line the cursor is on.
+fn extend_paragraph<T: TextOps>(
+    text: &T,
+    anchor: Position,
+    from: u32,
+    count: u32,
+    include: bool,
+) -> Option<Selected> {
+    let forward = from >= anchor.line;
+    let limit = if forward {
+        text.line_count().saturating_sub(1)
+    } else {
+        0
+    };
+    let step = |line: u32| if forward { line + 1 } else { line - 1 };
+    let mut line = from;
+    for _ in 0..count {
+        if line == limit {
+            break;
+        }
+        let mut prev_white = None;
+        for _ in 0..2 {
+            let next = step(line);
+            let start_white = line_white(text, next);
+            if prev_white == Some(start_white) {
+                break;
+            }
+            line = next;
+            while line != limit && line_white(text, step(line)) == start_white {
+                line = step(line);
+            }
+            if !include || line == limit {
+                break;
+            }
+            prev_white = Some(start_white);
+        }
+    }
+    if line == from {
+        return None;
+    }
+    Some(Selected {
+        anchor,
+        cursor: Position { line, col: 0 },
+        inclusive: true,
+        linewise: true,
+    })
+}
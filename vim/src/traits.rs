@@ -1,3 +1,4 @@
+use crate::scan;
 use crate::types::{Position, Range};
 
 /// Operations on text buffers required by the vim engine.
@@ -72,6 +73,45 @@ pub trait TextOps {
     /// Find the start of the previous word from the given position.
     fn prev_word_start(&self, pos: Position, count: u32) -> Position;
 
+    /// Find the end of the next word from the given position (`e`).
+    ///
+    /// The default follows Vim: a word is a run of letters, digits and
+    /// underscores, or a run of other non-blank characters. Stops on the last
+    /// character of the buffer.
+    fn next_word_end(&self, pos: Position, count: u32) -> Position {
+        scan::next_word_end(self, pos, count, false)
+    }
+
+    /// Find the end of the previous word from the given position (`ge`).
+    ///
+    /// An empty line counts as a word.
+    fn prev_word_end(&self, pos: Position, count: u32) -> Position {
+        scan::prev_word_end(self, pos, count, false)
+    }
+
+    /// Find the start of the next WORD from the given position (`W`).
+    ///
+    /// A WORD is a run of non-blank characters. After the last WORD of the
+    /// buffer this is the end of its line, one past the last character.
+    fn next_bigword_start(&self, pos: Position, count: u32) -> Position {
+        scan::next_word_start(self, pos, count, true)
+    }
+
+    /// Find the start of the previous WORD from the given position (`B`).
+    fn prev_bigword_start(&self, pos: Position, count: u32) -> Position {
+        scan::prev_word_start(self, pos, count, true)
+    }
+
+    /// Find the end of the next WORD from the given position (`E`).
+    fn next_bigword_end(&self, pos: Position, count: u32) -> Position {
+        scan::next_word_end(self, pos, count, true)
+    }
+
+    /// Find the end of the previous WORD from the given position (`gE`).
+    fn prev_bigword_end(&self, pos: Position, count: u32) -> Position {
+        scan::prev_word_end(self, pos, count, true)
+    }
+
     /// Find the start of the next paragraph.
     ///
     /// Paragraphs are separated by one or more blank lines.
@@ -87,6 +127,25 @@ pub trait TextOps {
     /// - Returns None if the character is not found
     fn find_in_line(&self, pos: Position, ch: char, before: bool, count: u32) -> Option<Position>;
 
+    /// Find the `count`th `ch` before `pos` in its line (`F` and `T`).
+    ///
+    /// Returns the position of the character itself; the engine stops after
+    /// it for `T`. Returns None if there are fewer than `count` of them.
+    fn find_in_line_backward(&self, pos: Position, ch: char, count: u32) -> Option<Position> {
+        let graphemes = scan::line_graphemes(self, pos.line);
+        let end = (pos.col as usize).min(graphemes.len());
+        let (col, _) = graphemes[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, g)| scan::is_char(g, ch))
+            .nth(count.max(1) as usize - 1)?;
+        Some(Position {
+            line: pos.line,
+            col: col as u32,
+        })
+    }
+
     /// Extract text from the buffer as a string.
     ///
     /// Used for yanking (copying) text. The range is half-open [start, end).
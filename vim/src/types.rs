@@ -37,10 +37,17 @@ pub enum Mode {
     Normal,
     /// Insert mode - for typing text.
     Insert,
+    /// Replace mode - typed text overwrites the text under the cursor.
+    Replace,
     /// Visual mode - for selecting text.
     Visual(VisualKind),
     /// Search prompt mode - entering a search query.
     SearchPrompt,
+    /// Command-line mode - entering an ex command after `:`.
+    CommandLine,
+    /// Confirm mode - answering `y`, `n`, `a`, `q` or `l` for each match of
+    /// `:s///c`.
+    Confirm,
 }
 
 /// The type of visual selection.
@@ -50,12 +57,20 @@ pub enum VisualKind {
     CharWise,
     /// Line-wise selection (V).
     LineWise,
+    /// Block-wise selection (Ctrl-V): a rectangle of screen columns.
+    Block,
 }
 
 /// A text selection with its type.
 ///
 /// Selections track both the anchor point and current position,
 /// as well as whether the selection is character or line-wise.
+///
+/// A [`VisualKind::Block`] selection is a rectangle: `start` and `end` give
+/// its top and bottom lines, and their `col` are the first and last *screen
+/// column* it covers rather than grapheme columns, so that it stays straight
+/// over tabs and wide characters. An `end.col` of `u32::MAX` means the block
+/// reaches the end of every line (`$`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Selection {
     /// The start of the selection.
@@ -66,6 +81,43 @@ pub struct Selection {
     pub kind: VisualKind,
 }
 
+/// An inclusive range of lines, as given to an ex command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    /// Zero-based first line.
+    pub start: u32,
+    /// Zero-based last line (inclusive).
+    pub end: u32,
+}
+
+/// The lines the host shows, for `H`, `M`, `L` and the scrolling commands.
+///
+/// Given with [`Engine::set_viewport`](crate::Engine::set_viewport) and kept
+/// up to date by the engine as it emits [`Command::ScrollTo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Viewport {
+    /// Zero-based first line on screen.
+    pub top: u32,
+    /// Number of lines on screen.
+    pub height: u32,
+}
+
+/// An ex command line handed to the host.
+///
+/// Produced for the commands the host registered with
+/// [`EngineBuilder::ex_command`](crate::EngineBuilder::ex_command).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExCommand {
+    /// The full registered name, even when an abbreviation was typed.
+    pub name: String,
+    /// The lines the command applies to, when a range was given.
+    pub range: Option<LineRange>,
+    /// Whether the name was followed by `!`.
+    pub bang: bool,
+    /// Everything after the name, without leading blanks.
+    pub args: String,
+}
+
 /// Commands emitted by the vim engine for the host to execute.
 ///
 /// These commands represent the concrete actions that should be
@@ -82,4 +134,28 @@ pub enum Command {
     Delete { range: Range },
     /// Insert text at the specified position.
     InsertText { at: Position, text: String },
+
+    /// Scroll so that the given zero-based line is the first on screen.
+    ScrollTo(u32),
+
+    /// Write the buffer, or only `range`, to `path` or the current file (`:w`).
+    Write {
+        path: Option<String>,
+        range: Option<LineRange>,
+        force: bool,
+    },
+    /// Close the buffer (`:q`). With `force`, changes are discarded.
+    Quit { force: bool },
+    /// Edit `path`, or reload the current file when it is `None` (`:e`).
+    Edit { path: Option<String>, force: bool },
+    /// Run an ex command registered by the host.
+    Ex(ExCommand),
+    /// An ex command failed; the message should be shown to the user.
+    Error(String),
+    /// Information for the user, such as the number of substitutions made.
+    Message(String),
+    /// `:s///c` asks whether to replace the text in `range` with
+    /// `replacement`. The cursor is already at the match; the host should
+    /// highlight it and prompt for `y/n/a/q/l`.
+    ConfirmSubstitute { range: Range, replacement: String },
 }
@@ -0,0 +1,392 @@
+//! Undo and redo for the edits the engine emits (feature `undo`).
+//!
+//! [`UndoHistory`] records each batch of [`Command::Delete`] and
+//! [`Command::InsertText`] commands together with the text they remove, read
+//! from the buffer with [`TextOps::slice_to_string`] before the batch is
+//! applied. Undoing a change emits the inverse batch; redoing emits the
+//! original one again. A change that leaves the history open, such as the
+//! keys typed in one insert session, is extended by the next batch so it
+//! undoes as one step.
+//!
+//! With the feature enabled the engine keeps a history itself and handles
+//! `u`, Ctrl-R and `U`. A host that edits the buffer on its own can use a
+//! separate `UndoHistory` the same way.
+
+use crate::traits::TextOps;
+use crate::types::{Command, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One edit of a change, with the text needed to invert it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Edit {
+    Insert { at: Position, text: String },
+    Delete { at: Position, text: String },
+}
+
+impl Edit {
+    fn command(&self) -> Command {
+        match self {
+            Edit::Insert { at, text } => Command::InsertText {
+                at: *at,
+                text: text.clone(),
+            },
+            Edit::Delete { at, text } => Command::Delete {
+                range: Range {
+                    start: *at,
+                    end: end_of(*at, text),
+                },
+            },
+        }
+    }
+
+    fn inverse(&self) -> Command {
+        match self {
+            Edit::Insert { at, text } => Edit::Delete {
+                at: *at,
+                text: text.clone(),
+            },
+            Edit::Delete { at, text } => Edit::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+        }
+        .command()
+    }
+}
+
+/// One undo step.
+#[derive(Debug, Clone)]
+struct Change {
+    edits: Vec<Edit>,
+    cursor: Position, // before the change
+}
+
+/// The original text of the line changed last, for `U`.
+#[derive(Debug, Clone)]
+struct LineUndo {
+    line: u32,
+    text: String,
+}
+
+/// A linear undo/redo history of command batches.
+///
+/// # Examples
+///
+/// ```
+/// use vim_mini::undo::UndoHistory;
+/// use vim_mini::{Command, Position};
+/// # use vim_mini::{Range, TextOps};
+/// # fn apply<T: TextOps>(_: &mut T, _: &[Command]) {}
+///
+/// fn edit<T: TextOps>(history: &mut UndoHistory, buffer: &mut T, cursor: Position, cmds: Vec<Command>) {
+///     // Record before applying, while the deleted text is still there
+///     history.record(buffer, cursor, &cmds, false);
+///     apply(buffer, &cmds);
+/// }
+///
+/// fn undo<T: TextOps>(history: &mut UndoHistory, buffer: &mut T) -> Option<Position> {
+///     let (cursor, cmds) = history.undo()?;
+///     apply(buffer, &cmds);
+///     Some(cursor)
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UndoHistory {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    open: Option<Change>, // change still being extended
+    line: Option<LineUndo>,
+}
+
+impl UndoHistory {
+    /// Create an empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a batch of commands before it is applied to `text`.
+    ///
+    /// `cursor` is where the cursor was before the batch and is restored
+    /// by undo. With `keep_open` the change stays open and the next batch
+    /// is added to it; an empty batch with `keep_open` unset closes it.
+    /// Commands other than edits are ignored.
+    pub fn record<T: TextOps>(
+        &mut self,
+        text: &T,
+        cursor: Position,
+        cmds: &[Command],
+        keep_open: bool,
+    ) {
+        let edits = capture(text, cmds);
+        if !edits.is_empty() {
+            self.remember_line(text, &edits);
+            self.redo.clear();
+            self.open
+                .get_or_insert_with(|| Change {
+                    edits: Vec::new(),
+                    cursor,
+                })
+                .edits
+                .extend(edits);
+        }
+        if !keep_open {
+            self.close();
+        }
+    }
+
+    /// Close the open change, if any, so the next batch starts a new one.
+    pub fn close(&mut self) {
+        if let Some(change) = self.open.take() {
+            self.undo.push(change);
+        }
+    }
+
+    /// True when there is a change to undo.
+    pub fn can_undo(&self) -> bool {
+        self.open.is_some() || !self.undo.is_empty()
+    }
+
+    /// True when there is an undone change to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget all changes.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Undo the last change: the cursor it started from and the commands
+    /// that revert it.
+    pub fn undo(&mut self) -> Option<(Position, Vec<Command>)> {
+        self.close();
+        let change = self.undo.pop()?;
+        let cmds = change.edits.iter().rev().map(Edit::inverse).collect();
+        let cursor = change.cursor;
+        self.redo.push(change);
+        self.line = None;
+        Some((cursor, cmds))
+    }
+
+    /// Redo the last undone change: the cursor at its start and the
+    /// commands that make it again.
+    pub fn redo(&mut self) -> Option<(Position, Vec<Command>)> {
+        self.close();
+        let change = self.redo.pop()?;
+        let cmds = change.edits.iter().map(Edit::command).collect();
+        let cursor = match change.edits.first() {
+            Some(Edit::Insert { at, .. } | Edit::Delete { at, .. }) => *at,
+            None => change.cursor,
+        };
+        self.undo.push(change);
+        self.line = None;
+        Some((cursor, cmds))
+    }
+
+    /// Undo all changes on the last changed line (`U`), as long as no other
+    /// line was changed since. Returns the commands restoring the line;
+    /// they are a change of their own, so recording them lets `u` undo the
+    /// `U` and a second `U` redo the changes.
+    pub fn undo_line<T: TextOps>(&mut self, text: &T) -> Option<(Position, Vec<Command>)> {
+        let LineUndo {
+            line,
+            text: original,
+        } = self.line.take()?;
+        if line >= text.line_count() {
+            return None;
+        }
+        let start = Position { line, col: 0 };
+        let end = Position {
+            line,
+            col: text.line_len(line),
+        };
+        let mut cmds = Vec::new();
+        if end.col > 0 {
+            cmds.push(Command::Delete {
+                range: Range { start, end },
+            });
+        }
+        if !original.is_empty() {
+            cmds.push(Command::InsertText {
+                at: start,
+                text: original,
+            });
+        }
+        Some((start, cmds))
+    }
+
+    /// Keep the text the line had before its first change for `U`.
+    fn remember_line<T: TextOps>(&mut self, text: &T, edits: &[Edit]) {
+        let line = match edits {
+            [Edit::Insert { at, .. } | Edit::Delete { at, .. }, ..] => at.line,
+            [] => return,
+        };
+        let one_line = edits.iter().all(|edit| match edit {
+            Edit::Insert { at, text } | Edit::Delete { at, text } => {
+                at.line == line && !text.contains('\n')
+            }
+        });
+        if !one_line {
+            self.line = None;
+        } else if self.line.as_ref().is_none_or(|l| l.line != line) {
+            self.line = Some(LineUndo {
+                line,
+                text: text.slice_to_string(Range {
+                    start: Position { line, col: 0 },
+                    end: Position {
+                        line,
+                        col: text.line_len(line),
+                    },
+                }),
+            });
+        }
+    }
+}
+
+/// The edits of a batch, with the text each delete removes.
+///
+/// Commands apply one after the other, so a delete is read from `text`
+/// directly only when it lies before every earlier edit of the batch, as in
+/// the bottom-up batches of `:s`. Otherwise the batch so far is replayed on
+/// a copy of the buffer.
+fn capture<T: TextOps>(text: &T, cmds: &[Command]) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let mut first: Option<Position> = None; // earliest position edited so far
+    let mut scratch: Option<Scratch> = None;
+    for cmd in cmds {
+        let edit = match cmd {
+            Command::Delete { range } => {
+                let range = Range {
+                    start: clamp(text, range.start.min(range.end)),
+                    end: clamp(text, range.start.max(range.end)),
+                };
+                let deleted = match (&scratch, first) {
+                    (None, None) => text.slice_to_string(range),
+                    (None, Some(first)) if range.end <= first => text.slice_to_string(range),
+                    _ => scratch
+                        .get_or_insert_with(|| Scratch::new(text, &edits))
+                        .slice(range),
+                };
+                Edit::Delete {
+                    at: range.start,
+                    text: deleted,
+                }
+            }
+            Command::InsertText { at, text } => Edit::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+            _ => continue,
+        };
+        let (Edit::Insert { at, text } | Edit::Delete { at, text }) = &edit;
+        if text.is_empty() {
+            continue;
+        }
+        first = Some(first.map_or(*at, |first| first.min(*at)));
+        if let Some(scratch) = scratch.as_mut() {
+            scratch.apply(&edit);
+        }
+        edits.push(edit);
+    }
+    edits
+}
+
+/// `pos` within the buffer; past the last line is the end of the buffer.
+fn clamp<T: TextOps>(text: &T, pos: Position) -> Position {
+    let last = text.line_count().saturating_sub(1);
+    if pos.line > last {
+        Position {
+            line: last,
+            col: text.line_len(last),
+        }
+    } else {
+        text.clamp(pos)
+    }
+}
+
+/// The position just after `text` inserted at `at`.
+fn end_of(at: Position, text: &str) -> Position {
+    match text.rsplit_once('\n') {
+        Some((before, last)) => Position {
+            line: at.line + before.matches('\n').count() as u32 + 1,
+            col: last.graphemes(true).count() as u32,
+        },
+        None => Position {
+            line: at.line,
+            col: at.col + text.graphemes(true).count() as u32,
+        },
+    }
+}
+
+/// A copy of the buffer as lines, to read deletes that follow other edits.
+struct Scratch {
+    lines: Vec<String>,
+}
+
+impl Scratch {
+    fn new<T: TextOps>(text: &T, edits: &[Edit]) -> Self {
+        let last = text.line_count().saturating_sub(1);
+        let all = text.slice_to_string(Range {
+            start: Position::ZERO,
+            end: Position {
+                line: last,
+                col: text.line_len(last),
+            },
+        });
+        let mut scratch = Self {
+            lines: all.split('\n').map(str::to_string).collect(),
+        };
+        for edit in edits {
+            scratch.apply(edit);
+        }
+        scratch
+    }
+
+    /// Line and byte offset of `pos`, clamped to the buffer.
+    fn locate(&self, pos: Position) -> (usize, usize) {
+        let line = (pos.line as usize).min(self.lines.len() - 1);
+        let text = &self.lines[line];
+        let byte = text
+            .grapheme_indices(true)
+            .nth(pos.col as usize)
+            .map_or(text.len(), |(i, _)| i);
+        (line, byte)
+    }
+
+    fn slice(&self, range: Range) -> String {
+        let (l1, b1) = self.locate(range.start);
+        let (l2, b2) = self.locate(range.end);
+        if l1 == l2 {
+            return self.lines[l1][b1..b2.max(b1)].to_string();
+        }
+        let mut out = self.lines[l1][b1..].to_string();
+        for line in &self.lines[l1 + 1..l2] {
+            out.push('\n');
+            out.push_str(line);
+        }
+        out.push('\n');
+        out.push_str(&self.lines[l2][..b2]);
+        out
+    }
+
+    fn apply(&mut self, edit: &Edit) {
+        match edit {
+            Edit::Insert { at, text } => {
+                let (line, byte) = self.locate(*at);
+                let tail = self.lines[line].split_off(byte);
+                let mut new: Vec<String> = text.split('\n').map(str::to_string).collect();
+                new[0].insert_str(0, &self.lines[line]);
+                new.last_mut().expect("split yields a line").push_str(&tail);
+                self.lines.splice(line..=line, new);
+            }
+            Edit::Delete { at, text } => {
+                let (l1, b1) = self.locate(*at);
+                let (l2, b2) = self.locate(end_of(*at, text));
+                let tail = self.lines[l2][b2..].to_string();
+                self.lines[l1].truncate(b1);
+                self.lines[l1].push_str(&tail);
+                self.lines.drain(l1 + 1..=l2);
+            }
+        }
+    }
+}
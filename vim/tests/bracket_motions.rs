@@ -0,0 +1,141 @@
+use vim_mini::{Command, Engine};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn percent_jumps_between_matching_brackets() {
+    let mut buf = MockBuffer::new("if (a[1] == f(b)) {\n  x;\n}");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "%");
+    assert_eq!(cur, pos(0, 16));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(0, 16))]);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "%");
+    assert_eq!(cur, pos(0, 3));
+    // Square brackets, and braces over lines
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 7), "%");
+    assert_eq!(cur, pos(0, 5));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "%");
+    assert_eq!(cur, pos(0, 18));
+    // '%' is a jump
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "''");
+    assert_eq!(cur, pos(2, 0));
+}
+
+#[test]
+fn percent_searches_the_line_for_a_bracket() {
+    let mut buf = MockBuffer::new("let v = f(a, (b));\nnone");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "%");
+    assert_eq!(cur, pos(0, 16));
+    // Nothing after the cursor on the line
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 17), "%");
+    assert_eq!(cur, pos(0, 17));
+    assert!(cmds.is_empty());
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "%");
+    assert_eq!(cur, pos(1, 0));
+
+    // An unmatched bracket goes nowhere
+    let mut buf = MockBuffer::new("((a)");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "%");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn percent_with_a_count_goes_to_a_line() {
+    let lines: Vec<String> = (0..200).map(|i| format!("  {i}")).collect();
+    let mut buf = MockBuffer::new(&lines.join("\n"));
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "50%");
+    assert_eq!(cur, pos(99, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "1%");
+    assert_eq!(cur, pos(1, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "100%");
+    assert_eq!(cur, pos(199, 2));
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "101%");
+    assert_eq!(cur, pos(199, 2));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn operators_with_percent() {
+    let mut buf = MockBuffer::new("f(a, (b)) + c");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Inclusive: both brackets go, from before the first one
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d%");
+    assert_eq!(buf.text(), " + c");
+    assert_eq!(cur, pos(0, 0));
+
+    let mut buf = MockBuffer::new("x = [1, 2];");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 9), "c%y\x1b");
+    assert_eq!(buf.text(), "x = y;");
+
+    // With a count, linewise
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d50%");
+    assert_eq!(buf.text(), "c\nd");
+}
+
+#[test]
+fn unmatched_bracket_motions() {
+    let mut buf = MockBuffer::new("fn f() {\n  if (a && (b || c)) {\n    g(x);\n  }\n}");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 14), "[(");
+    assert_eq!(cur, pos(1, 11));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "[(");
+    assert_eq!(cur, pos(1, 5));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 14), "])");
+    assert_eq!(cur, pos(1, 18));
+    // A count goes out further, as far as there are brackets
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 14), "2])");
+    assert_eq!(cur, pos(1, 19));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 4), "5[{");
+    assert_eq!(cur, pos(0, 7));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 4), "]}");
+    assert_eq!(cur, pos(3, 2));
+    // None to go to
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "[{");
+    assert_eq!(cur, pos(0, 0));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn operators_with_unmatched_bracket_motions() {
+    let mut buf = MockBuffer::new("f(a, b, c)");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Exclusive: the closing bracket stays
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 5), "d])");
+    assert_eq!(buf.text(), "f(a, )");
+    // Backward the bracket goes and the character under the cursor stays
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "d[(");
+    assert_eq!(buf.text(), "f, )");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn bracket_motions_in_visual_mode() {
+    let mut buf = MockBuffer::new("x(a, b) y");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "v%d");
+    assert_eq!(buf.text(), "x y");
+
+    let mut buf = MockBuffer::new("x(a, b) y");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "v[(d");
+    assert_eq!(buf.text(), "x b) y");
+}
@@ -0,0 +1,160 @@
+use vim_mini::{Command, Engine, EngineBuilder, Mode, Options, Range};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn tilde_switches_case_and_moves_right() {
+    let mut buf = MockBuffer::new("abC1d");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "~");
+    assert_eq!(buf.text(), "AbC1d");
+    assert_eq!(cur, pos(0, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3~");
+    assert_eq!(buf.text(), "ABc1d");
+    assert_eq!(cur, pos(0, 4));
+    // A count past the end stops on the last character
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "9~");
+    assert_eq!(buf.text(), "ABc1D");
+    assert_eq!(cur, pos(0, 4));
+}
+
+#[test]
+fn tilde_uses_full_case_mappings() {
+    let mut buf = MockBuffer::new("ßx");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "~");
+    assert_eq!(buf.text(), "SSx");
+    assert_eq!(cur, pos(0, 2));
+    assert_eq!(
+        cmds,
+        vec![
+            Command::Delete {
+                range: Range {
+                    start: pos(0, 0),
+                    end: pos(0, 1),
+                },
+            },
+            Command::InsertText {
+                at: pos(0, 0),
+                text: "SS".into(),
+            },
+        ]
+    );
+
+    // With a count the cursor goes past all the expanded text
+    let mut buf = MockBuffer::new("aßßb");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "2~");
+    assert_eq!(buf.text(), "aSSSSb");
+    assert_eq!(cur, pos(0, 5));
+    // At the end of the line it stops on the last character
+    let mut buf = MockBuffer::new("aß");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "~");
+    assert_eq!(buf.text(), "aSS");
+    assert_eq!(cur, pos(0, 2));
+}
+
+#[test]
+fn tildeop_makes_tilde_an_operator() {
+    let mut buf = MockBuffer::new("one two\nthree");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            tildeop: true,
+            ..Options::default()
+        })
+        .build();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "~w");
+    assert_eq!(buf.text(), "ONE two\nthree");
+    assert_eq!(cur, pos(0, 0));
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "~~");
+    assert_eq!(buf.text(), "ONE two\nTHREE");
+}
+
+#[test]
+fn case_operators_with_motions_and_text_objects() {
+    let mut buf = MockBuffer::new("Hello World foo");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 8), "gUiw");
+    assert_eq!(buf.text(), "Hello WORLD foo");
+    assert_eq!(cur, pos(0, 6));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "gu2w");
+    assert_eq!(buf.text(), "hello world foo");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "g~$");
+    assert_eq!(buf.text(), "HELLO WORLD FOO");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "g?iw");
+    assert_eq!(buf.text(), "HELLO WORLD SBB");
+}
+
+#[test]
+fn case_operators_on_lines() {
+    let mut buf = MockBuffer::new("ab\ncd\nef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "gUU");
+    assert_eq!(buf.text(), "AB\ncd\nef");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2g~~");
+    assert_eq!(buf.text(), "ab\nCD\nef");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "gugu");
+    assert_eq!(buf.text(), "ab\ncd\nef");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "gUj");
+    assert_eq!(buf.text(), "ab\nCD\nEF");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "g??");
+    assert_eq!(buf.text(), "no\nCD\nEF");
+}
+
+#[test]
+fn case_operators_to_the_first_or_last_line() {
+    let mut buf = MockBuffer::new("ab\ncd\nef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 1), "gUG");
+    assert_eq!(buf.text(), "ab\nCD\nEF");
+    assert_eq!(cur, pos(1, 0));
+    // The operator is done: 'k' only moves
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "k");
+    assert_eq!(buf.text(), "ab\nCD\nEF");
+    assert_eq!(cur, pos(0, 0));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 1), "gugg");
+    assert_eq!(buf.text(), "ab\ncd\nef");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "g~gg");
+    assert_eq!(buf.text(), "AB\nCD\nef");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "g?2G");
+    assert_eq!(buf.text(), "NO\nPQ\nef");
+}
+
+#[test]
+fn dot_repeats_a_case_operator() {
+    let mut buf = MockBuffer::new("aa bb cc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "gUiw");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 6), ".");
+    assert_eq!(buf.text(), "AA bb CC");
+}
+
+#[test]
+fn rot13_in_visual_mode() {
+    let mut buf = MockBuffer::new("Hello, World");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "v$g?");
+    assert_eq!(buf.text(), "Uryyb, Jbeyq");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Vg?");
+    assert_eq!(buf.text(), "Hello, World");
+}
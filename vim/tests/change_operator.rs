@@ -0,0 +1,189 @@
+use vim_mini::{
+    Engine,
+    types::{Command, Mode, Position},
+};
+
+mod support;
+use support::feed::{delete_range, feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn cw_changes_to_end_of_word() {
+    let mut buf = MockBuffer::new("hello world rust");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 0);
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "cw");
+    assert_eq!(new_cur, cur);
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    // Like "ce": the trailing space is kept
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(0, 0));
+    assert_eq!(range.end, pos(0, 5));
+}
+
+#[test]
+fn cw_on_last_char_of_word_changes_only_that_char() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 4);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "cw");
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(0, 4));
+    assert_eq!(range.end, pos(0, 5));
+}
+
+#[test]
+fn count_cw_changes_several_words() {
+    let mut buf = MockBuffer::new("one two three four");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 0);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "2cw");
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.end, pos(0, 7));
+}
+
+#[test]
+fn cw_on_blank_behaves_like_dw() {
+    let mut buf = MockBuffer::new("one   two");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 3);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "cw");
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(0, 3));
+    assert_eq!(range.end, pos(0, 6));
+}
+
+#[test]
+fn cc_keeps_indentation() {
+    let mut buf = MockBuffer::new("fn main() {\n    let x = 1;\n}\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(1, 8);
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "cc");
+    assert_eq!(new_cur, pos(1, 4));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(1, 4));
+    assert_eq!(range.end, pos(1, 14));
+}
+
+#[test]
+fn count_cc_joins_lines_into_one_empty_line() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(1, 0);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "2cc");
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(1, 0));
+    // Newline after the last changed line is kept
+    assert_eq!(range.end, pos(2, 1));
+}
+
+#[test]
+fn big_s_is_linewise_change() {
+    let mut buf = MockBuffer::new("  indented text\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 6);
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "S");
+    assert_eq!(new_cur, pos(0, 2));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.end, pos(0, 15));
+}
+
+#[test]
+fn c_dollar_and_big_c_change_to_line_end() {
+    let mut buf = MockBuffer::new("hello world\nnext");
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 6);
+
+    let mut eng = Engine::new();
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "c$");
+    assert_eq!(delete_range(&cmds).unwrap().end, pos(0, 11));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+
+    let mut buf = MockBuffer::new("hello world\nnext");
+    let mut eng = Engine::new();
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "C");
+    assert_eq!(new_cur, cur);
+    assert_eq!(delete_range(&cmds).unwrap().end, pos(0, 11));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn s_with_count_substitutes_characters() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    let cur = pos(0, 1);
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "3s");
+    assert_eq!(new_cur, cur);
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(0, 1));
+    assert_eq!(range.end, pos(0, 4));
+}
+
+#[test]
+fn s_on_empty_line_still_enters_insert() {
+    let mut buf = MockBuffer::new("\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "s");
+    assert!(cmds.is_empty());
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn cf_changes_through_target() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "cfo");
+    assert_eq!(delete_range(&cmds).unwrap().end, pos(0, 5));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn visual_c_changes_selection() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "vllc");
+    assert_eq!(new_cur, Position::ZERO);
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    assert_eq!(delete_range(&cmds).unwrap().end, pos(0, 3));
+    assert!(cmds.contains(&Command::SetSelection(None)));
+}
+
+#[test]
+fn visual_line_c_behaves_like_cc() {
+    let mut buf = MockBuffer::new("  one\n  two\nthree\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "Vjc");
+    assert_eq!(new_cur, pos(0, 2));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    let range = delete_range(&cmds).unwrap();
+    assert_eq!(range.start, pos(0, 2));
+    assert_eq!(range.end, pos(1, 5));
+}
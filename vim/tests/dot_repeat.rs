@@ -0,0 +1,185 @@
+use vim_mini::{
+    Engine, InputEvent, KeyCode,
+    types::{Mode, Position},
+};
+
+mod support;
+use support::feed::key;
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+/// Drives the engine and applies every emitted edit to the buffer.
+struct Session {
+    buf: MockBuffer,
+    eng: Engine,
+    clipboard: MockClipboard,
+    cursor: Position,
+}
+
+impl Session {
+    fn new(text: &str) -> Self {
+        Self {
+            buf: MockBuffer::new(text),
+            eng: Engine::new(),
+            clipboard: MockClipboard::new(),
+            cursor: Position::ZERO,
+        }
+    }
+
+    fn send(&mut self, input: InputEvent) {
+        let (cursor, cmds) =
+            self.eng
+                .handle_event(&self.buf, &mut self.clipboard, self.cursor, input);
+        for cmd in &cmds {
+            self.buf.apply(cmd);
+        }
+        self.cursor = cursor;
+    }
+
+    fn keys(&mut self, keys: &str) {
+        for c in keys.chars() {
+            self.send(key(KeyCode::Char(c)));
+        }
+    }
+
+    fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.send(InputEvent::ReceivedChar(c));
+        }
+        self.send(key(KeyCode::Esc));
+    }
+}
+
+#[test]
+fn dot_repeats_dw() {
+    let mut s = Session::new("one two three four");
+    s.keys("dw");
+    assert_eq!(s.buf.text(), "two three four");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "three four");
+}
+
+#[test]
+fn dot_repeats_x_with_original_count() {
+    let mut s = Session::new("abcdefgh");
+    s.keys("2x");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "efgh");
+}
+
+#[test]
+fn count_on_dot_replaces_original_count() {
+    let mut s = Session::new("abcdefghij");
+    s.keys("2x");
+    s.keys("3.");
+    assert_eq!(s.buf.text(), "fghij");
+    // The new count sticks for later repeats
+    s.keys(".");
+    assert_eq!(s.buf.text(), "ij");
+}
+
+#[test]
+fn dot_replaces_count_of_operator_motion() {
+    let mut s = Session::new("a b c d e f");
+    s.keys("d2w");
+    assert_eq!(s.buf.text(), "c d e f");
+    s.keys("3.");
+    assert_eq!(s.buf.text(), "f");
+}
+
+#[test]
+fn dot_repeats_change_with_inserted_text() {
+    let mut s = Session::new("foo bar baz");
+    s.keys("cw");
+    s.type_text("qux");
+    assert_eq!(s.buf.text(), "qux bar baz");
+    assert_eq!(s.eng.snapshot().mode, Mode::Normal);
+
+    s.cursor = Position { line: 0, col: 4 };
+    s.keys(".");
+    assert_eq!(s.buf.text(), "qux qux baz");
+    assert_eq!(s.eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn dot_repeats_plain_insert() {
+    let mut s = Session::new("x\ny");
+    s.keys("i");
+    s.type_text("ab");
+    s.cursor = Position { line: 1, col: 0 };
+    s.keys(".");
+    assert_eq!(s.buf.text(), "abx\naby");
+    assert_eq!(s.cursor, Position { line: 1, col: 2 });
+}
+
+#[test]
+fn dot_repeats_multiline_insert() {
+    let mut s = Session::new("end");
+    s.keys("i");
+    s.type_text("a\nb");
+    s.cursor = Position::ZERO;
+    s.keys(".");
+    assert_eq!(s.buf.text(), "a\nba\nbend");
+}
+
+#[test]
+fn dot_repeats_dd() {
+    let mut s = Session::new("1\n2\n3\n4\n");
+    s.keys("dd.");
+    assert_eq!(s.buf.text(), "3\n4\n");
+}
+
+#[test]
+fn yank_is_not_a_change() {
+    let mut s = Session::new("one two three");
+    s.keys("x");
+    s.keys("yw");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "e two three");
+}
+
+#[test]
+fn dot_without_previous_change_does_nothing() {
+    let mut s = Session::new("abc");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "abc");
+    assert_eq!(s.cursor, Position::ZERO);
+}
+
+#[test]
+fn dot_as_find_target_is_not_a_repeat() {
+    let mut s = Session::new("a.b.c");
+    s.keys("x");
+    s.keys("f.");
+    assert_eq!(s.cursor, Position { line: 0, col: 2 });
+    assert_eq!(s.buf.text(), ".b.c");
+}
+
+#[test]
+fn dot_repeats_visual_change_over_same_size() {
+    let mut s = Session::new("abcdef\nghijkl");
+    s.keys("vld");
+    assert_eq!(s.buf.text(), "cdef\nghijkl");
+    s.cursor = Position { line: 1, col: 1 };
+    s.keys(".");
+    assert_eq!(s.buf.text(), "cdef\ngjkl");
+}
+
+#[test]
+fn dot_repeats_linewise_visual_over_same_line_count() {
+    let mut s = Session::new("1\n2\n3\n4\n5\n");
+    s.keys("Vjd");
+    assert_eq!(s.buf.text(), "3\n4\n5\n");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "5\n");
+}
+
+#[test]
+fn escape_cancels_pending_operator() {
+    let mut s = Session::new("one two");
+    s.keys("d");
+    s.send(key(KeyCode::Esc));
+    s.keys("w");
+    assert_eq!(s.buf.text(), "one two");
+    assert_eq!(s.cursor, Position { line: 0, col: 4 });
+}
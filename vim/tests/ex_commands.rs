@@ -0,0 +1,294 @@
+use vim_mini::{
+    Command, Engine, EngineBuilder, ExCommand, InputEvent, KeyCode, LineRange, Mode, Position,
+};
+
+mod support;
+use support::feed::{ex, feed, key, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+fn error(cmds: &[Command]) -> Option<&str> {
+    cmds.iter().find_map(|c| match c {
+        Command::Error(message) => Some(message.as_str()),
+        _ => None,
+    })
+}
+
+const TEXT: &str = "one\ntwo\n  three\nfour\nfive";
+
+#[test]
+fn colon_enters_command_line_mode() {
+    let buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = eng.handle_event(&buf, &mut cb, Position::ZERO, key(KeyCode::Char(':')));
+    assert_eq!(eng.snapshot().mode, Mode::CommandLine);
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some(""));
+
+    let (cur, _) = eng.handle_event(&buf, &mut cb, cur, InputEvent::ReceivedChar('w'));
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some("w"));
+
+    let (_, cmds) = eng.handle_event(&buf, &mut cb, cur, key(KeyCode::Esc));
+    assert!(cmds.is_empty());
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+    assert_eq!(eng.snapshot().command_line, None);
+}
+
+#[test]
+fn backspace_edits_and_leaves_an_empty_command_line() {
+    let buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = eng.handle_event(&buf, &mut cb, Position::ZERO, key(KeyCode::Char(':')));
+    let (cur, _) = eng.handle_event(&buf, &mut cb, cur, InputEvent::ReceivedChar('q'));
+    let (cur, _) = eng.handle_event(&buf, &mut cb, cur, key(KeyCode::Backspace));
+    assert_eq!(eng.snapshot().mode, Mode::CommandLine);
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some(""));
+
+    eng.handle_event(&buf, &mut cb, cur, key(KeyCode::Backspace));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn line_number_moves_to_first_non_blank() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "3");
+    assert_eq!(cur, pos(2, 2));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(2, 2))]);
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, cur, "$");
+    assert_eq!(cur, pos(4, 0));
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, cur, "-3");
+    assert_eq!(cur, pos(1, 0));
+
+    // Past the end stops on the last line
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, cur, "99");
+    assert_eq!(cur, pos(4, 0));
+}
+
+#[test]
+fn write_quit_and_edit_are_left_to_the_host() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    let cur = Position::ZERO;
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "w");
+    assert_eq!(
+        cmds,
+        vec![Command::Write {
+            path: None,
+            range: None,
+            force: false
+        }]
+    );
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "2,3write! part.txt");
+    assert_eq!(
+        cmds,
+        vec![Command::Write {
+            path: Some("part.txt".to_string()),
+            range: Some(LineRange { start: 1, end: 2 }),
+            force: true
+        }]
+    );
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "q!");
+    assert_eq!(cmds, vec![Command::Quit { force: true }]);
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "x");
+    assert_eq!(
+        cmds,
+        vec![
+            Command::Write {
+                path: None,
+                range: None,
+                force: false
+            },
+            Command::Quit { force: false }
+        ]
+    );
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "e other.rs");
+    assert_eq!(
+        cmds,
+        vec![Command::Edit {
+            path: Some("other.rs".to_string()),
+            force: false
+        }]
+    );
+}
+
+#[test]
+fn errors_are_reported() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    let cur = Position::ZERO;
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "frobnicate");
+    assert_eq!(
+        error(&cmds),
+        Some("E492: Not an editor command: frobnicate")
+    );
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "2q");
+    assert_eq!(error(&cmds), Some("E481: No range allowed"));
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "1,9d");
+    assert_eq!(error(&cmds), Some("E16: Invalid range"));
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "'ad");
+    assert_eq!(error(&cmds), Some("E20: Mark not set"));
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, cur, "/nothere/d");
+    assert_eq!(error(&cmds), Some("E486: Pattern not found: nothere"));
+    assert_eq!(buf.text(), TEXT);
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn delete_range_of_lines() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "1,2d");
+    assert_eq!(buf.text(), "  three\nfour\nfive");
+    assert_eq!(cur, pos(0, 2));
+    assert_eq!(eng.registers().get('"').unwrap().text, "one\ntwo\n");
+}
+
+#[test]
+fn delete_with_offsets_register_and_count() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    ex(&mut eng, &mut buf, &mut cb, pos(1, 0), ".+1d a");
+    assert_eq!(buf.text(), "one\ntwo\nfour\nfive");
+    assert_eq!(eng.registers().get('a').unwrap().text, "  three\n");
+
+    // A count starts at the last line of the range
+    ex(&mut eng, &mut buf, &mut cb, pos(0, 0), "d 2");
+    assert_eq!(buf.text(), "four\nfive");
+}
+
+#[test]
+fn yank_whole_buffer_keeps_cursor() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = ex(&mut eng, &mut buf, &mut cb, pos(1, 0), "%y");
+    assert!(cmds.is_empty());
+    assert_eq!(cur, pos(1, 0));
+    assert_eq!(eng.registers().get('0').unwrap().text, "a\nb\nc\n");
+}
+
+#[test]
+fn pattern_addresses_search_from_the_cursor_line() {
+    let mut buf = MockBuffer::new("foo 1\nbar\nfoo 2\nbaz\nfoo 3");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Forward search skips the cursor line
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "/foo/");
+    assert_eq!(cur.line, 2);
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, cur, "?bar?");
+    assert_eq!(cur.line, 1);
+
+    // With ';' the second search starts from the first match
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "/bar/;/foo/d");
+    assert_eq!(buf.text(), "foo 1\nbaz\nfoo 3");
+}
+
+#[test]
+fn visual_colon_uses_the_selected_lines() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "Vj:");
+    assert!(cmds.contains(&Command::SetSelection(None)));
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some("'<,'>"));
+
+    feed(&mut eng, &mut buf, &mut cb, cur, "d\r");
+    assert_eq!(buf.text(), "one\nfour\nfive");
+}
+
+#[test]
+fn count_prefills_a_range() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, Position::ZERO, "3:");
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some(".,.+2"));
+}
+
+#[test]
+fn host_commands_are_emitted_with_their_full_name() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default().ex_command("Fo[rmat]").build();
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, pos(1, 0), ".,$Fo! --check");
+    assert_eq!(
+        cmds,
+        vec![Command::Ex(ExCommand {
+            name: "Format".to_string(),
+            range: Some(LineRange { start: 1, end: 4 }),
+            bang: true,
+            args: "--check".to_string(),
+        })]
+    );
+
+    // Shorter than the registered abbreviation
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "F");
+    assert_eq!(error(&cmds), Some("E492: Not an editor command: F"));
+}
+
+#[test]
+fn host_command_prefixes_must_be_unambiguous() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    eng.register_ex_command("Make");
+    eng.register_ex_command("Man");
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "Mak");
+    assert!(matches!(&cmds[..], [Command::Ex(cmd)] if cmd.name == "Make" && cmd.range.is_none()));
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "Ma");
+    assert_eq!(
+        error(&cmds),
+        Some("E464: Ambiguous use of user-defined command: Ma")
+    );
+}
+
+#[test]
+fn last_command_line_is_stored_and_repeated() {
+    let mut buf = MockBuffer::new(TEXT);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "d");
+    assert_eq!(eng.registers().get(':').unwrap().text, "d");
+    assert_eq!(buf.text(), "two\n  three\nfour\nfive");
+
+    let (mut cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "@:");
+    while let Some((next, cmds)) = eng.step_replay(&buf, &mut cb, cur) {
+        cmds.iter().for_each(|cmd| buf.apply(cmd));
+        cur = next;
+    }
+    assert_eq!(buf.text(), "  three\nfour\nfive");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
@@ -0,0 +1,150 @@
+use vim_mini::{Command, Engine, EngineBuilder, Options, TextOps};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn default_method_finds_backward() {
+    let buf = MockBuffer::new("a.b.c.d");
+
+    assert_eq!(
+        buf.find_in_line_backward(pos(0, 6), '.', 1),
+        Some(pos(0, 5))
+    );
+    assert_eq!(
+        buf.find_in_line_backward(pos(0, 6), '.', 3),
+        Some(pos(0, 1))
+    );
+    assert_eq!(buf.find_in_line_backward(pos(0, 6), '.', 4), None);
+    // The character under the cursor is not looked at
+    assert_eq!(buf.find_in_line_backward(pos(0, 1), '.', 1), None);
+}
+
+#[test]
+fn capital_f_and_t_search_backward() {
+    let mut buf = MockBuffer::new("one two three");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "Fo");
+    assert_eq!(cur, pos(0, 6));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(0, 6))]);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "To");
+    assert_eq!(cur, pos(0, 7));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "2Fo");
+    assert_eq!(cur, pos(0, 0));
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "Fz");
+    assert_eq!(cur, pos(0, 12));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn semicolon_and_comma_repeat_the_last_find() {
+    let mut buf = MockBuffer::new("a.b.c.d");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Nothing to repeat yet
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ";");
+    assert_eq!(cur, pos(0, 0));
+    assert!(cmds.is_empty());
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "f.");
+    assert_eq!(cur, pos(0, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ";");
+    assert_eq!(cur, pos(0, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ";");
+    assert_eq!(cur, pos(0, 5));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ",");
+    assert_eq!(cur, pos(0, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2;");
+    assert_eq!(cur, pos(0, 3));
+
+    // After 'F', ';' goes on backward and ',' turns forward
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "F.;");
+    assert_eq!(cur, pos(0, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ",");
+    assert_eq!(cur, pos(0, 5));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2;");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn repeating_t_skips_the_character_next_to_the_cursor() {
+    let mut buf = MockBuffer::new("a.b.c");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "t.");
+    assert_eq!(cur, pos(0, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ";");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "T.");
+    assert_eq!(cur, pos(0, 4));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, ";");
+    assert_eq!(cur, pos(0, 2));
+
+    // With cpo_semicolon the cursor stays
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            cpo_semicolon: true,
+            ..Options::default()
+        })
+        .build();
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "t.;");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn operators_with_find_motions() {
+    let mut buf = MockBuffer::new("one two three");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Backward the motion is exclusive
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "dFo");
+    assert_eq!(buf.text(), "one twe");
+    assert_eq!(cur, pos(0, 6));
+    let mut buf = MockBuffer::new("one two three");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 12), "dTo");
+    assert_eq!(buf.text(), "one twoe");
+
+    // A count before or after the operator
+    let mut buf = MockBuffer::new("a.b.c.d");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d2f.");
+    assert_eq!(buf.text(), "c.d");
+    let mut buf = MockBuffer::new("a.b.c.d");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2dt.");
+    assert_eq!(buf.text(), ".c.d");
+}
+
+#[test]
+fn operators_with_semicolon_and_comma() {
+    let mut buf = MockBuffer::new("a.b.c.d.e");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "f.d;");
+    assert_eq!(buf.text(), "ac.d.e");
+    assert_eq!(cur, pos(0, 1));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d2;");
+    assert_eq!(buf.text(), "e");
+
+    let mut buf = MockBuffer::new("a.b.c");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "d,");
+    assert_eq!(buf.text(), "a.bc");
+}
+
+#[test]
+fn find_motions_in_visual_mode() {
+    let mut buf = MockBuffer::new("a.b.c.d");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "vf.;d");
+    assert_eq!(buf.text(), "c.d");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "vF.d");
+    assert_eq!(buf.text(), "c");
+}
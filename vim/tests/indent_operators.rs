@@ -0,0 +1,226 @@
+use vim_mini::{Engine, EngineBuilder, Options, Range, RegisterKind, TextOps};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+fn engine(shiftwidth: u32, expandtab: bool, shiftround: bool) -> Engine {
+    EngineBuilder::default()
+        .options(Options {
+            shiftwidth,
+            expandtab,
+            shiftround,
+            ..Options::default()
+        })
+        .build()
+}
+
+#[test]
+fn shift_lines_right_and_left() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">>");
+    assert_eq!(buf.text(), "\ta\nb\nc");
+    // The cursor goes to the first non-blank
+    assert_eq!(cur, pos(0, 1));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3>>");
+    assert_eq!(buf.text(), "\t\ta\n\tb\n\tc");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "2<<");
+    assert_eq!(buf.text(), "\ta\nb\n\tc");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn shiftwidth_and_expandtab() {
+    let mut buf = MockBuffer::new("a\n  b");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(4, true, false);
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">j");
+    assert_eq!(buf.text(), "    a\n      b");
+
+    // Without expandtab whole tab stops become tabs
+    let mut eng = engine(4, false, false);
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ">>");
+    assert_eq!(buf.text(), "    a\n\t  b");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">>");
+    assert_eq!(buf.text(), "\ta\n\t  b");
+}
+
+#[test]
+fn zero_shiftwidth_uses_tabstop() {
+    let mut buf = MockBuffer::new("a");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            shiftwidth: 0,
+            tabstop: 2,
+            expandtab: true,
+            ..Options::default()
+        })
+        .build();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">>");
+    assert_eq!(buf.text(), "  a");
+}
+
+#[test]
+fn shiftround_rounds_to_a_multiple() {
+    let mut buf = MockBuffer::new("   a");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(4, true, true);
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">>");
+    assert_eq!(buf.text(), "    a");
+
+    let mut buf = MockBuffer::new("      a");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<<");
+    assert_eq!(buf.text(), "    a");
+
+    // Without it the indent keeps its odd width
+    let mut eng = engine(4, true, false);
+    let mut buf = MockBuffer::new("   a");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">>");
+    assert_eq!(buf.text(), "       a");
+}
+
+#[test]
+fn shift_with_motions_and_text_objects() {
+    let mut buf = MockBuffer::new("a b\nc\n\nd\ne");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(2, true, false);
+
+    // A word motion stays on its line
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">w");
+    assert_eq!(buf.text(), "  a b\nc\n\nd\ne");
+    // Empty lines are not indented
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ">2j");
+    assert_eq!(buf.text(), "  a b\n  c\n\n  d\ne");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "<ip");
+    assert_eq!(buf.text(), "a b\nc\n\n  d\ne");
+    feed(&mut eng, &mut buf, &mut cb, pos(4, 0), "<k");
+    assert_eq!(buf.text(), "a b\nc\n\nd\ne");
+}
+
+#[test]
+fn shift_in_visual_mode() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(4, true, false);
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "vj>");
+    assert_eq!(buf.text(), "    a\n    b\nc");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "Vj3>");
+    assert_eq!(buf.text(), "    a\n                b\n            c");
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "V2<");
+    assert_eq!(buf.text(), "    a\n                b\n    c");
+}
+
+#[test]
+fn dot_repeats_a_shift() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(2, true, false);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2>>");
+    feed(&mut eng, &mut buf, &mut cb, cur, ".");
+    assert_eq!(buf.text(), "    a\n    b\nc");
+}
+
+#[test]
+fn equal_indents_with_the_host_function() {
+    let mut buf = MockBuffer::new("fn f() {\nx\n}\n    y");
+    let mut cb = MockClipboard::new();
+    // Lines after a '{' line are indented by four columns
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            expandtab: true,
+            ..Options::default()
+        })
+        .indent_fn(|text: &dyn TextOps, line| {
+            let above = line.checked_sub(1)?;
+            let end = text.line_len(above);
+            let prev = text.slice_to_string(Range {
+                start: pos(above, 0),
+                end: pos(above, end),
+            });
+            Some(if prev.ends_with('{') { 4 } else { 0 })
+        })
+        .build();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "=ip");
+    assert_eq!(buf.text(), "fn f() {\n    x\n}\ny");
+    assert_eq!(cur, pos(0, 0));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "Vk=");
+    assert_eq!(buf.text(), "fn f() {\n    x\n}\ny");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 4), "2==");
+    assert_eq!(cur, pos(1, 4));
+}
+
+#[test]
+fn equal_without_a_function_keeps_the_indent() {
+    let mut buf = MockBuffer::new("  a\n\tb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(1, 1), "=k");
+    assert_eq!(buf.text(), "  a\n\tb");
+    assert_eq!(cur, pos(0, 2));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn shift_and_indent_to_the_first_or_last_line() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(2, true, false);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ">G");
+    assert_eq!(buf.text(), "a\n  b\n  c\n  d");
+    assert_eq!(cur, pos(1, 2));
+    // The operator is done: 'k' only moves
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "k");
+    assert_eq!(buf.text(), "a\n  b\n  c\n  d");
+    assert_eq!(cur, pos(0, 0));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "<gg");
+    assert_eq!(buf.text(), "a\nb\nc\n  d");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ">3G");
+    assert_eq!(buf.text(), "  a\n  b\n  c\n  d");
+
+    let mut eng = EngineBuilder::default()
+        .indent_fn(|_: &dyn TextOps, line| Some(line))
+        .build();
+    feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "gg=G");
+    assert_eq!(buf.text(), "a\n b\n  c\n   d");
+}
+
+#[test]
+fn delete_to_the_first_or_last_line() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "dG");
+    let deleted = eng.registers().get('"').unwrap();
+    assert_eq!(deleted.text, "c\nd\n");
+    assert_eq!(deleted.kind, RegisterKind::LineWise);
+    // The operator is done: 'k' only moves
+    let left = buf.text();
+    feed(&mut eng, &mut buf, &mut cb, cur, "k");
+    assert_eq!(buf.text(), left);
+
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "dgg");
+    assert_eq!(buf.text(), "c\nd");
+    assert_eq!(cur, pos(0, 0));
+
+    // Anything else cancels the operator
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "dxj");
+    assert_eq!(buf.text(), "c\nd");
+    assert_eq!(cur, pos(1, 0));
+}
@@ -0,0 +1,230 @@
+use vim_mini::{Command, Engine, EngineBuilder, Options};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn backspace_deletes_the_character_before_the_cursor() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "axy\x08\x08\x08");
+    assert_eq!(buf.text(), "ab");
+    assert_eq!(cur, pos(0, 2));
+}
+
+#[test]
+fn backspace_at_column_zero_joins_lines() {
+    let mut buf = MockBuffer::new("one\ntwo");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "i\x08");
+    assert_eq!(buf.text(), "onetwo");
+    assert_eq!(cur, pos(0, 3));
+
+    // Nothing before the start of the buffer
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "\x1bi\x08");
+    assert_eq!(cur, pos(0, 0));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn backspace_is_grapheme_aware() {
+    let mut buf = MockBuffer::new("a👍🏽b");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "i\x08");
+    assert_eq!(buf.text(), "ab");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn ctrl_h_is_backspace() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "a<C-h>");
+    assert_eq!(buf.text(), "ab");
+}
+
+#[test]
+fn enter_splits_the_line_and_keeps_the_indent() {
+    let mut buf = MockBuffer::new("    foo(bar)");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 8), "i\r");
+    assert_eq!(buf.text(), "    foo(\n    bar)");
+    assert_eq!(cur, pos(1, 4));
+    assert_eq!(
+        cmds,
+        vec![Command::InsertText {
+            at: pos(0, 8),
+            text: "\n    ".into()
+        }]
+    );
+
+    // Blanks after the cursor do not add to the indent
+    let mut buf = MockBuffer::new("\tx = 1;   y = 2;");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 7), "\x1bi\r");
+    assert_eq!(buf.text(), "\tx = 1;\n\ty = 2;");
+    assert_eq!(cur, pos(1, 1));
+}
+
+#[test]
+fn an_indent_nothing_is_typed_after_goes_again() {
+    let mut buf = MockBuffer::new("  ab");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "A\r\rx\x1b");
+    assert_eq!(buf.text(), "  ab\n\n  x");
+    assert_eq!(cur, pos(2, 3));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "A\r\x1b");
+    assert_eq!(buf.text(), "  ab\n\n\n  x");
+
+    // The text typed before is kept for '.'
+    let mut buf = MockBuffer::new("  ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Ac\r\x1b");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "k.");
+    assert_eq!(buf.text(), "  abcc\n\n");
+
+    // Text moved to the new line keeps it
+    let mut buf = MockBuffer::new("  ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "i\r\x1b");
+    assert_eq!(buf.text(), "  a\n  b");
+}
+
+#[test]
+fn enter_without_autoindent() {
+    let mut buf = MockBuffer::new("  ab");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            autoindent: false,
+            ..Options::default()
+        })
+        .build();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "i\r");
+    assert_eq!(buf.text(), "  a\nb");
+    assert_eq!(cur, pos(1, 0));
+}
+
+#[test]
+fn tab_honors_expandtab_and_tabstop() {
+    let mut buf = MockBuffer::new("ab");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "i\t");
+    assert_eq!(buf.text(), "a\tb");
+
+    let mut buf = MockBuffer::new("ab\tc");
+    eng.options_mut().expandtab = true;
+    eng.options_mut().tabstop = 4;
+    // From screen column 1 to the tab stop at 4
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "\x1bi\t");
+    assert_eq!(buf.text(), "a   b\tc");
+    assert_eq!(cur, pos(0, 4));
+    // A tab before the cursor counts up to its tab stop
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "\x1bi<C-i>");
+    assert_eq!(buf.text(), "a   b\t    c");
+}
+
+#[test]
+fn delete_removes_the_character_under_the_cursor() {
+    let mut buf = MockBuffer::new("ab\ncd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "i\x7f");
+    assert_eq!(buf.text(), "a\ncd");
+    assert_eq!(cur, pos(0, 1));
+
+    // At the end of a line it joins the next one
+    feed(&mut eng, &mut buf, &mut cb, cur, "\x7f");
+    assert_eq!(buf.text(), "acd");
+}
+
+#[test]
+fn ctrl_w_deletes_the_word_before_the_cursor() {
+    let mut buf = MockBuffer::new("");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ifoo.bar  <C-w>");
+    assert_eq!(buf.text(), "foo.");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-w>");
+    assert_eq!(buf.text(), "foo");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-w>");
+    assert_eq!(buf.text(), "");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn ctrl_w_and_ctrl_u_stop_once_at_the_insert_start() {
+    let mut buf = MockBuffer::new("one two");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 7), "axyz<C-w>");
+    assert_eq!(buf.text(), "one two");
+    assert_eq!(cur, pos(0, 7));
+    feed(&mut eng, &mut buf, &mut cb, cur, "<C-w>");
+    assert_eq!(buf.text(), "one ");
+
+    let mut buf = MockBuffer::new("  abc");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "\x1bixy<C-u>");
+    assert_eq!(buf.text(), "  abc");
+    // Then up to the indent and to the start of the line
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-u>");
+    assert_eq!(buf.text(), "  c");
+    feed(&mut eng, &mut buf, &mut cb, cur, "<C-u>");
+    assert_eq!(buf.text(), "c");
+}
+
+#[test]
+fn without_backspace_start_nothing_before_the_insert_is_deleted() {
+    let mut buf = MockBuffer::new("ab\ncd");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            backspace_start: false,
+            ..Options::default()
+        })
+        .build();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "ix\x08\x08");
+    assert_eq!(buf.text(), "ab\ncd");
+    assert_eq!(cur, pos(1, 0));
+
+    // A line break typed in the same insert can be deleted
+    feed(&mut eng, &mut buf, &mut cb, cur, "\r\x08<C-u>");
+    assert_eq!(buf.text(), "ab\ncd");
+}
+
+#[test]
+fn dot_repeats_the_text_left_after_deleting() {
+    let mut buf = MockBuffer::new("x\ny");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(
+        &mut eng,
+        &mut buf,
+        &mut cb,
+        pos(0, 0),
+        "iabc\x08d one<C-w>\x1b",
+    );
+    assert_eq!(buf.text(), "abd x\ny");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ".");
+    assert_eq!(buf.text(), "abd x\nabd y");
+    assert_eq!(eng.registers().get('.').unwrap().text, "abd ");
+}
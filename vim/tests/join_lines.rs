@@ -0,0 +1,143 @@
+use vim_mini::{Command, Engine, EngineBuilder, Mode, Options, Range};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn join_removes_the_indent_and_puts_a_space() {
+    let mut buf = MockBuffer::new("foo\n    bar\nbaz");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "J");
+    assert_eq!(buf.text(), "foo bar\nbaz");
+    // The cursor is on the space
+    assert_eq!(cur, pos(0, 3));
+    assert_eq!(
+        cmds,
+        vec![
+            Command::Delete {
+                range: Range {
+                    start: pos(0, 3),
+                    end: pos(1, 4),
+                },
+            },
+            Command::InsertText {
+                at: pos(0, 3),
+                text: " ".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn join_with_a_count() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3J");
+    assert_eq!(buf.text(), "a b c\nd");
+    assert_eq!(cur, pos(0, 3));
+    // A count of one joins two lines, as without one
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "1J");
+    assert_eq!(buf.text(), "a b c d");
+
+    // A count past the end joins the lines there are
+    let mut buf = MockBuffer::new("a\nb");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "5J");
+    assert_eq!(buf.text(), "a b");
+    // On the last line there is nothing to join
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "J");
+    assert_eq!(cur, pos(0, 1));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn join_puts_no_space_where_none_belongs() {
+    let mut buf = MockBuffer::new("f(\n  )");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Before ')'
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "J");
+    assert_eq!(buf.text(), "f()");
+    assert_eq!(cur, pos(0, 2));
+
+    // After a blank
+    let mut buf = MockBuffer::new("a \nb");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "J");
+    assert_eq!(buf.text(), "a b");
+
+    // Joining to or from an empty line
+    let mut buf = MockBuffer::new("\n  b\nc\n");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "J");
+    assert_eq!(buf.text(), "b\nc\n");
+    assert_eq!(cur, pos(0, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "J");
+    assert_eq!(buf.text(), "b\nc");
+    assert_eq!(cur, pos(1, 0));
+}
+
+#[test]
+fn joinspaces_puts_two_spaces_after_a_sentence() {
+    let mut buf = MockBuffer::new("End.\nNext?\nLast");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            joinspaces: true,
+            ..Options::default()
+        })
+        .build();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3J");
+    assert_eq!(buf.text(), "End.  Next?  Last");
+    assert_eq!(cur, pos(0, 11));
+
+    let mut eng = Engine::new();
+    let mut buf = MockBuffer::new("End.\nNext");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "J");
+    assert_eq!(buf.text(), "End. Next");
+}
+
+#[test]
+fn gj_joins_without_changing_white_space() {
+    let mut buf = MockBuffer::new("a\n  b\nc \nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "gJ");
+    assert_eq!(buf.text(), "a  b\nc \nd");
+    assert_eq!(cur, pos(0, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3gJ");
+    assert_eq!(buf.text(), "a  bc d");
+    assert_eq!(cur, pos(0, 6));
+}
+
+#[test]
+fn join_in_visual_mode() {
+    let mut buf = MockBuffer::new("a\n b\n c\nd\ne");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "VjjJ");
+    assert_eq!(buf.text(), "a b c\nd\ne");
+    assert_eq!(cur, pos(0, 3));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+    // A single line is joined with the next one
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "vgJ");
+    assert_eq!(buf.text(), "a b c\nde");
+}
+
+#[test]
+fn dot_repeats_a_join() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd\ne");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3J");
+    feed(&mut eng, &mut buf, &mut cb, cur, ".");
+    assert_eq!(buf.text(), "a b c d e");
+}
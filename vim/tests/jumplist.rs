@@ -0,0 +1,188 @@
+use vim_mini::{Command, Engine, Mode, Position};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+fn lines(n: usize) -> MockBuffer {
+    let text: Vec<String> = (0..n).map(|i| format!("line {i}")).collect();
+    MockBuffer::new(&text.join("\n"))
+}
+
+#[test]
+fn ctrl_o_and_ctrl_i_walk_the_jumplist() {
+    let mut buf = lines(10);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 3), "G5gg");
+    assert_eq!(cur, pos(4, 0));
+    assert_eq!(eng.marks().jumps(), &[pos(2, 3), pos(9, 0)]);
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(9, 0));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(9, 0))]);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(2, 3));
+
+    // Nothing older: the cursor stays
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(2, 3));
+    assert!(cmds.is_empty());
+
+    // Ctrl-I comes back to where Ctrl-O started
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2<C-i>");
+    assert_eq!(cur, pos(4, 0));
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-i>");
+    assert_eq!(cur, pos(4, 0));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn ctrl_o_takes_a_count() {
+    let mut buf = lines(10);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "3G6G9G");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3<C-o>");
+    assert_eq!(cur, pos(1, 0));
+}
+
+#[test]
+fn searches_and_paragraphs_are_jumps() {
+    let mut buf = MockBuffer::new("a\n\nb x\n\nc x");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "}");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "/x\r");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "n");
+    assert_eq!(cur, pos(4, 2));
+    // "/" and "n" both jumped from line 2, which keeps only the newer entry
+    assert_eq!(eng.marks().jumps(), &[pos(0, 0), pos(2, 2)]);
+
+    // Plain motions are not
+    feed(&mut eng, &mut buf, &mut cb, cur, "khjw");
+    assert_eq!(eng.marks().jumps().len(), 2);
+}
+
+#[test]
+fn one_entry_per_line() {
+    let mut buf = lines(5);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "G");
+    feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "gg");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 3), "G");
+    assert_eq!(eng.marks().jumps(), &[pos(3, 0), pos(1, 3)]);
+}
+
+#[test]
+fn new_jump_after_going_back_is_added_at_the_end() {
+    let mut buf = lines(10);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "5G9G");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o><C-o>");
+    assert_eq!(cur, pos(0, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3G");
+    assert_eq!(eng.marks().jumps(), &[pos(4, 0), pos(8, 0), pos(0, 0)]);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn jumplist_is_bounded() {
+    let mut buf = lines(200);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let mut cur = Position::ZERO;
+    for line in 1..=150 {
+        cur = feed(&mut eng, &mut buf, &mut cb, cur, &format!("{line}G")).0;
+    }
+    let jumps = eng.marks().jumps();
+    assert_eq!(jumps.len(), 100);
+    assert_eq!(jumps[0], pos(49, 0));
+    assert_eq!(jumps[99], pos(148, 0));
+}
+
+#[test]
+fn jumplist_follows_edits() {
+    let mut buf = lines(6);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(4, 2), "gg");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "dd");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(3, 2));
+    assert_eq!(buf.text().lines().nth(3), Some("line 4"));
+}
+
+#[test]
+fn g_semicolon_and_g_comma_walk_the_change_list() {
+    let mut buf = lines(6);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "g;");
+    assert_eq!(
+        cmds,
+        vec![Command::Error("E664: Changelist is empty".into())]
+    );
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "x");
+    feed(&mut eng, &mut buf, &mut cb, pos(3, 1), "x");
+    // Several changes in one line make one entry
+    feed(&mut eng, &mut buf, &mut cb, pos(5, 4), "xx");
+    assert_eq!(eng.marks().changes(), &[pos(1, 2), pos(3, 1), pos(5, 4)]);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "g;");
+    assert_eq!(cur, pos(5, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2g;");
+    assert_eq!(cur, pos(1, 2));
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "g;");
+    assert_eq!(cur, pos(1, 2));
+    assert_eq!(
+        cmds,
+        vec![Command::Error("E662: At start of changelist".into())]
+    );
+
+    // A count past the end stops at the newest entry
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "9g,");
+    assert_eq!(cur, pos(5, 3));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "g,");
+    assert_eq!(
+        cmds,
+        vec![Command::Error("E663: At end of changelist".into())]
+    );
+}
+
+#[test]
+fn gi_inserts_where_insert_mode_was_left() {
+    let mut buf = MockBuffer::new("abc\ndef\nghi");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 1), "iXY\x1b");
+    assert_eq!(cur, pos(1, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 2), "gg");
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "gi");
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    assert_eq!(cur, pos(1, 3));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(1, 3))]);
+
+    feed(&mut eng, &mut buf, &mut cb, cur, "Z\x1b");
+    assert_eq!(buf.text(), "abc\ndXYZef\nghi");
+
+    // Without a previous insert, gi inserts at the cursor
+    let mut eng = Engine::new();
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 1), "gi");
+    assert_eq!(cur, pos(2, 1));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
@@ -0,0 +1,182 @@
+use vim_mini::{
+    Engine, InputEvent, KeyCode, Register,
+    traits::Clipboard,
+    types::{Mode, Position},
+};
+
+mod support;
+use support::feed::key;
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+/// Drives the engine like a host: applies edits, then pumps macro playback.
+struct Session {
+    buf: MockBuffer,
+    eng: Engine,
+    clipboard: MockClipboard,
+    cursor: Position,
+}
+
+impl Session {
+    fn new(text: &str) -> Self {
+        Self {
+            buf: MockBuffer::new(text),
+            eng: Engine::new(),
+            clipboard: MockClipboard::new(),
+            cursor: Position::ZERO,
+        }
+    }
+
+    fn send(&mut self, input: InputEvent) {
+        let (cursor, cmds) =
+            self.eng
+                .handle_event(&self.buf, &mut self.clipboard, self.cursor, input);
+        for cmd in &cmds {
+            self.buf.apply(cmd);
+        }
+        self.cursor = cursor;
+        while let Some((cursor, cmds)) =
+            self.eng
+                .step_replay(&self.buf, &mut self.clipboard, self.cursor)
+        {
+            for cmd in &cmds {
+                self.buf.apply(cmd);
+            }
+            self.cursor = cursor;
+        }
+    }
+
+    fn keys(&mut self, keys: &str) {
+        for c in keys.chars() {
+            self.send(key(KeyCode::Char(c)));
+        }
+    }
+
+    fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.send(InputEvent::ReceivedChar(c));
+        }
+        self.send(key(KeyCode::Esc));
+    }
+}
+
+#[test]
+fn record_and_replay() {
+    let mut s = Session::new("one two three four");
+    s.keys("qa");
+    assert_eq!(s.eng.snapshot().recording, Some('a'));
+    s.keys("dwq");
+    assert_eq!(s.eng.snapshot().recording, None);
+    assert_eq!(s.buf.text(), "two three four");
+
+    s.keys("@a");
+    assert_eq!(s.buf.text(), "three four");
+}
+
+#[test]
+fn recorded_keys_are_stored_as_register_text() {
+    let mut s = Session::new("abc");
+    s.keys("qbi");
+    s.type_text("x");
+    s.keys("q");
+    assert_eq!(s.eng.registers().get('b').unwrap().text, "ix\x1b");
+}
+
+#[test]
+fn count_replays_several_times() {
+    let mut s = Session::new("abcdefgh");
+    s.keys("qaxq");
+    s.keys("3@a");
+    assert_eq!(s.buf.text(), "efgh");
+}
+
+#[test]
+fn at_at_repeats_last_macro() {
+    let mut s = Session::new("a1\na2\na3\na4\n");
+    s.keys("qqxjq");
+    s.keys("@q");
+    s.keys("@@");
+    assert_eq!(s.buf.text(), "1\n2\n3\na4\n");
+}
+
+#[test]
+fn replay_stops_on_failed_motion() {
+    let mut s = Session::new("a1\na2\na3");
+    s.keys("qaxjq");
+    // Only two lines are left; "j" fails on the last one
+    s.keys("10@a");
+    assert_eq!(s.buf.text(), "1\n2\n3");
+    assert!(!s.eng.is_replaying());
+}
+
+#[test]
+fn replay_stops_when_find_fails() {
+    let mut s = Session::new("a,b,c d");
+    s.keys("qaf,xq");
+    s.keys("5@a");
+    assert_eq!(s.buf.text(), "abc d");
+}
+
+#[test]
+fn macro_with_insert_mode() {
+    let mut s = Session::new("x\ny\nz");
+    s.keys("qaI");
+    s.type_text("- ");
+    s.keys("jq");
+    s.keys("2@a");
+    assert_eq!(s.buf.text(), "- x\n- y\n- z");
+    assert_eq!(s.eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn uppercase_register_appends_to_macro() {
+    let mut s = Session::new("abcdef");
+    s.keys("qaxq");
+    s.keys("qAxq");
+    assert_eq!(s.eng.registers().get('a').unwrap().text, "xx");
+    s.keys("@a");
+    assert_eq!(s.buf.text(), "ef");
+}
+
+#[test]
+fn yanked_text_can_be_executed() {
+    let mut s = Session::new("dw\none two three");
+    s.eng.registers_mut().set('c', Register::charwise("dw"));
+    s.cursor = Position { line: 1, col: 0 };
+    s.keys("@c");
+    assert_eq!(s.buf.text(), "dw\ntwo three");
+}
+
+#[test]
+fn clipboard_register_can_be_executed() {
+    let mut s = Session::new("abc");
+    s.clipboard.set("x".to_string());
+    s.keys("@+");
+    assert_eq!(s.buf.text(), "bc");
+}
+
+#[test]
+fn replayed_keys_are_not_recorded() {
+    let mut s = Session::new("abcdef");
+    s.keys("qaxq");
+    s.keys("qb@aq");
+    assert_eq!(s.eng.registers().get('b').unwrap().text, "@a");
+}
+
+#[test]
+fn macro_changes_can_be_dot_repeated() {
+    let mut s = Session::new("one two three four");
+    s.keys("qadwq");
+    s.keys("@a");
+    s.keys(".");
+    assert_eq!(s.buf.text(), "four");
+}
+
+#[test]
+fn empty_register_does_nothing() {
+    let mut s = Session::new("abc");
+    s.keys("@z");
+    s.keys("@@");
+    assert_eq!(s.buf.text(), "abc");
+    assert!(!s.eng.is_replaying());
+}
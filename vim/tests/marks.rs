@@ -0,0 +1,239 @@
+use vim_mini::{Command, Engine, Range, RegisterKind, Selection, VisualKind};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn quote_goes_to_line_and_backtick_to_position() {
+    let mut buf = MockBuffer::new("one\n  two words\nthree");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 6), "ma");
+    assert_eq!(eng.marks().get('a'), Some(pos(1, 6)));
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(2, 3), "'a");
+    assert_eq!(cur, pos(1, 2));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(1, 2))]);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "`a");
+    assert_eq!(cur, pos(1, 6));
+}
+
+#[test]
+fn unset_and_unknown_marks_are_errors() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "'b");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(cmds, vec![Command::Error("E20: Mark not set".into())]);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "`!");
+    assert_eq!(cmds, vec![Command::Error("E78: Unknown mark".into())]);
+
+    // A failed mark motion cancels the operator
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "d'bx");
+    assert_eq!(
+        cmds,
+        vec![Command::Delete {
+            range: Range {
+                start: pos(0, 1),
+                end: pos(0, 2)
+            }
+        }]
+    );
+}
+
+#[test]
+fn quote_motion_is_linewise_with_operators() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "ma");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "y'a");
+    let reg = eng.registers().get('"').unwrap();
+    assert_eq!(reg.text, "a\nb\nc\n");
+    assert_eq!(reg.kind, RegisterKind::LineWise);
+
+    // Backwards from below the mark
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "d'a");
+    assert_eq!(buf.text(), "a\nb\n");
+    assert_eq!(cur, pos(2, 0));
+}
+
+#[test]
+fn backtick_motion_is_exclusive_with_operators() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "mx");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d`x");
+    assert_eq!(buf.text(), "world");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn marks_follow_lines_inserted_and_deleted_above() {
+    let mut buf = MockBuffer::new("one\ntwo\nthree\nfour");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 2), "ma");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "dd");
+    assert_eq!(eng.marks().get('a'), Some(pos(1, 2)));
+
+    // Two new lines typed above the mark
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ix\ny\n\x1b");
+    assert_eq!(buf.text(), "x\ny\ntwo\nthree\nfour");
+    assert_eq!(eng.marks().get('a'), Some(pos(3, 2)));
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "`a");
+    assert_eq!(cur, pos(3, 2));
+}
+
+#[test]
+fn marks_follow_edits_in_their_line() {
+    let mut buf = MockBuffer::new("abc def");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "ma");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2x");
+    assert_eq!(eng.marks().get('a'), Some(pos(0, 2)));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "i👍\x1b");
+    assert_eq!(eng.marks().get('a'), Some(pos(0, 3)));
+
+    // Edits after the mark leave it alone
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 5), "x");
+    assert_eq!(eng.marks().get('a'), Some(pos(0, 3)));
+}
+
+#[test]
+fn marks_on_deleted_lines_are_removed() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "ma");
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "mb");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "dd");
+    assert_eq!(eng.marks().get('a'), None);
+    assert_eq!(eng.marks().get('b'), Some(pos(1, 0)));
+}
+
+#[test]
+fn jumps_set_the_previous_context_mark() {
+    let mut buf = MockBuffer::new("a\nb\nc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "G");
+    assert_eq!(cur, pos(3, 0));
+    assert_eq!(eng.marks().get('\''), Some(pos(1, 0)));
+
+    // '' jumps back and remembers where it came from
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "''");
+    assert_eq!(cur, pos(1, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "``");
+    assert_eq!(cur, pos(3, 0));
+
+    // Small motions are not jumps
+    feed(&mut eng, &mut buf, &mut cb, cur, "k");
+    assert_eq!(eng.marks().get('`'), Some(pos(1, 0)));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), ":1\r");
+    assert_eq!(eng.marks().get('`'), Some(pos(2, 0)));
+}
+
+#[test]
+fn change_marks() {
+    let mut buf = MockBuffer::new("one two three\nfour");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // '[ and '] cover the text typed in the insert session
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "cwTWO!\x1b");
+    assert_eq!(buf.text(), "one TWO! three\nfour");
+    assert_eq!(eng.marks().get('['), Some(pos(0, 4)));
+    assert_eq!(eng.marks().get(']'), Some(pos(0, 7)));
+    assert_eq!(eng.marks().get('.'), Some(pos(0, 7)));
+    assert_eq!(eng.marks().get('^'), Some(pos(0, 8)));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "2yy");
+    assert_eq!(eng.marks().get('['), Some(pos(0, 0)));
+    assert_eq!(eng.marks().get(']'), Some(pos(1, 3)));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 1), "x");
+    assert_eq!(eng.marks().get('.'), Some(pos(1, 1)));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "`.");
+    assert_eq!(cur, pos(1, 1));
+}
+
+#[test]
+fn visual_marks() {
+    let mut buf = MockBuffer::new("one\ntwo\nthree");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "vh\x1b");
+    assert_eq!(eng.marks().get('<'), Some(pos(1, 1)));
+    assert_eq!(eng.marks().get('>'), Some(pos(1, 2)));
+
+    // Linewise areas cover whole lines
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "Vj\x1b");
+    assert_eq!(eng.marks().get('<'), Some(pos(0, 0)));
+    assert_eq!(eng.marks().get('>'), Some(pos(1, 2)));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), ":'<,'>d\r");
+    assert_eq!(buf.text(), "three");
+}
+
+#[test]
+fn marks_in_visual_mode() {
+    let mut buf = MockBuffer::new("abc\ndef\nghi");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 1), "ma");
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "v`a");
+    assert_eq!(cur, pos(2, 1));
+    assert_eq!(
+        cmds,
+        vec![
+            Command::SetCursor(pos(2, 1)),
+            Command::SetSelection(Some(Selection {
+                start: pos(0, 1),
+                end: pos(2, 1),
+                kind: VisualKind::CharWise,
+            })),
+        ]
+    );
+
+    feed(&mut eng, &mut buf, &mut cb, cur, "mb\x1b");
+    assert_eq!(eng.marks().get('b'), Some(pos(2, 1)));
+}
+
+#[test]
+fn host_can_set_and_adjust_marks() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    eng.marks_mut().set('z', pos(2, 0));
+    let edit = Command::InsertText {
+        at: pos(0, 0),
+        text: "new\n".into(),
+    };
+    buf.apply(&edit);
+    eng.marks_mut().adjust(&edit);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "'z");
+    assert_eq!(cur, pos(3, 0));
+}
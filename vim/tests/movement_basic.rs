@@ -1,25 +1,12 @@
 use vim_mini::{
-    Engine, InputEvent, KeyCode, KeyEvent, Modifiers,
+    Engine, InputEvent, KeyCode,
     types::{Command, Mode, Position},
 };
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: Modifiers::empty(),
-    })
-}
-
-fn esc() -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Esc,
-        mods: Modifiers::empty(),
-    })
-}
-
 #[test]
 fn hjkl_moves() {
     let buf = MockBuffer::new("abc\nxyz\n");
@@ -28,26 +15,26 @@ fn hjkl_moves() {
     let mut cur = Position { line: 0, col: 0 };
 
     // Move right with l
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     cur = c;
     assert_eq!(cur, Position { line: 0, col: 1 });
     assert_eq!(cmds.len(), 1);
     assert!(matches!(&cmds[0], Command::SetCursor(p) if *p == cur));
 
     // Move down with j
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('j'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j')));
     cur = c;
     assert_eq!(cur, Position { line: 1, col: 1 });
     assert_eq!(cmds.len(), 1);
 
     // Move left with h
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('h'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('h')));
     cur = c;
     assert_eq!(cur, Position { line: 1, col: 0 });
     assert_eq!(cmds.len(), 1);
 
     // Move up with k
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('k'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('k')));
     cur = c;
     assert_eq!(cur, Position { line: 0, col: 0 });
     assert_eq!(cmds.len(), 1);
@@ -63,12 +50,12 @@ fn zero_and_dollar() {
     let cur = Position { line: 0, col: 3 };
 
     // 0 goes to start of line
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('0'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('0')));
     assert_eq!(c, Position { line: 0, col: 0 });
     assert_eq!(cmds.len(), 1);
 
     // $ goes to end of line (last character)
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('$'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('$')));
     assert_eq!(c, Position { line: 0, col: 5 }); // 'f' is at index 5
     assert_eq!(cmds.len(), 1);
 }
@@ -81,15 +68,15 @@ fn g_and_big_g() {
     let cur = Position { line: 2, col: 0 };
 
     // G goes to last line
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('G'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('G')));
     assert_eq!(c.line, 3); // 0-indexed, so line 4 is index 3
     assert_eq!(c.col, 0);
     assert_eq!(cmds.len(), 1);
 
     // gg goes to first line
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key('g'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('g')));
     assert_eq!(c.line, 3); // no change yet, g is pending
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('g'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('g')));
     assert_eq!(c.line, 0);
     assert_eq!(c.col, 0);
     assert_eq!(cmds.len(), 1);
@@ -103,22 +90,22 @@ fn counts_with_movements() {
     let mut cur = Position { line: 0, col: 0 };
 
     // 3l moves right 3
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(c, Position { line: 0, col: 3 });
     assert_eq!(cmds.len(), 1);
     cur = c;
 
     // 2j moves down 2
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('2'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('j'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('2')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j')));
     assert_eq!(c, Position { line: 2, col: 3 });
     assert_eq!(cmds.len(), 1);
     cur = c;
 
     // 2h moves left 2
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('2'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('h'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('2')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('h')));
     assert_eq!(c, Position { line: 2, col: 1 });
     assert_eq!(cmds.len(), 1);
 }
@@ -131,15 +118,15 @@ fn count_with_g_motions() {
     let cur = Position { line: 0, col: 0 };
 
     // 3G goes to line 3 (0-indexed: line 2)
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('G'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('G')));
     assert_eq!(c.line, 2);
     assert_eq!(cmds.len(), 1);
 
     // 2gg goes to line 2 (0-indexed: line 1)
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key('2'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key('g'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('g'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('2')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('g')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('g')));
     assert_eq!(c.line, 1);
     assert_eq!(cmds.len(), 1);
 }
@@ -152,35 +139,45 @@ fn insert_mode_transitions() {
 
     // i enters insert mode at current position
     let cur = Position { line: 0, col: 5 }; // at space
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('i'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('i')));
     assert_eq!(c, cur); // no movement
     assert!(cmds.is_empty());
     assert!(matches!(eng.snapshot().mode, Mode::Insert));
 
     // Esc returns to normal mode
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, esc());
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Esc));
     assert_eq!(c, cur);
     assert!(cmds.is_empty());
     assert!(matches!(eng.snapshot().mode, Mode::Normal));
 
     // a enters insert mode after current position
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('a'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('a')));
     assert_eq!(c, Position { line: 0, col: 6 });
     assert_eq!(cmds.len(), 1);
     assert!(matches!(eng.snapshot().mode, Mode::Insert));
 
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, esc());
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Esc));
 
     // I enters insert at beginning of line
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, Position { line: 0, col: 5 }, key('I'));
+    let (c, cmds) = eng.handle_event(
+        &buf,
+        &mut clipboard,
+        Position { line: 0, col: 5 },
+        key(KeyCode::Char('I')),
+    );
     assert_eq!(c, Position { line: 0, col: 0 });
     assert_eq!(cmds.len(), 1);
     assert!(matches!(eng.snapshot().mode, Mode::Insert));
 
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, esc());
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Esc));
 
     // A enters insert at end of line
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, Position { line: 0, col: 5 }, key('A'));
+    let (c, cmds) = eng.handle_event(
+        &buf,
+        &mut clipboard,
+        Position { line: 0, col: 5 },
+        key(KeyCode::Char('A')),
+    );
     assert_eq!(c, Position { line: 0, col: 11 }); // past 'd', ready to append
     assert_eq!(cmds.len(), 1);
     assert!(matches!(eng.snapshot().mode, Mode::Insert));
@@ -194,7 +191,7 @@ fn insert_mode_text_input() {
 
     // Enter insert mode
     let cur = Position { line: 0, col: 1 };
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key('i'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('i')));
 
     // Type 'x'
     let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, InputEvent::ReceivedChar('x'));
@@ -217,15 +214,15 @@ fn zero_as_motion_vs_count() {
     let cur = Position { line: 0, col: 5 };
 
     // 0 alone is a motion to start of line
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('0'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('0')));
     assert_eq!(c, Position { line: 0, col: 0 });
     assert_eq!(cmds.len(), 1);
 
     // 10l is count 10 with motion l
     let cur = Position { line: 0, col: 0 };
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('1'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('0'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('1')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('0')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(c, Position { line: 0, col: 10 }); // moves to position 10 (past end)
     assert_eq!(cmds.len(), 1);
 }
@@ -238,14 +235,14 @@ fn edge_cases() {
 
     // Moving on empty line
     let cur = Position { line: 1, col: 0 };
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(c, cur); // can't move right on empty line
 
     // Moving up/down preserves column preference
     let cur = Position { line: 0, col: 0 };
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key('j')); // to empty line
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j'))); // to empty line
     assert_eq!(c, Position { line: 1, col: 0 });
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key('j')); // to 'y' line
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('j'))); // to 'y' line
     assert_eq!(c, Position { line: 2, col: 0 });
 }
 
@@ -259,17 +256,17 @@ fn unicode_grapheme_handling() {
     let cur = Position { line: 0, col: 0 };
 
     // Move right past emoji (single grapheme)
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(c, Position { line: 0, col: 1 }); // at 👍
 
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key('l'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('l')));
     assert_eq!(c, Position { line: 0, col: 2 }); // at 'b'
 
     // Move to next line with flag emoji
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key('j'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('j')));
     assert_eq!(c, Position { line: 1, col: 2 }); // at flag
 
     // $ goes to end (last grapheme 'f')
-    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key('$'));
+    let (c, _) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('$')));
     assert_eq!(c, Position { line: 1, col: 2 }); // at 'f'
 }
@@ -0,0 +1,136 @@
+use vim_mini::{Command, Engine, EngineBuilder, Mode, Options};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn o_opens_a_line_below_with_the_indent() {
+    let mut buf = MockBuffer::new("  foo\nbar");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "o");
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    assert_eq!(cur, pos(1, 2));
+    assert_eq!(
+        cmds,
+        vec![Command::InsertText {
+            at: pos(0, 5),
+            text: "\n  ".into(),
+        }]
+    );
+    feed(&mut eng, &mut buf, &mut cb, cur, "xy\x1b");
+    assert_eq!(buf.text(), "  foo\n  xy\nbar");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn capital_o_opens_a_line_above() {
+    let mut buf = MockBuffer::new("a\n\tfoo");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "O");
+    assert_eq!(cur, pos(1, 1));
+    feed(&mut eng, &mut buf, &mut cb, cur, "xy\x1b");
+    assert_eq!(buf.text(), "a\n\txy\n\tfoo");
+
+    // On the first line
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Oz\x1b");
+    assert_eq!(buf.text(), "z\na\n\txy\n\tfoo");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn no_indent_without_autoindent() {
+    let mut buf = MockBuffer::new("    foo");
+    let mut cb = MockClipboard::new();
+    let mut eng = EngineBuilder::default()
+        .options(Options {
+            autoindent: false,
+            ..Options::default()
+        })
+        .build();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ox\x1bOy\x1b");
+    assert_eq!(buf.text(), "    foo\ny\nx");
+}
+
+#[test]
+fn a_count_repeats_the_typed_text_on_new_lines() {
+    let mut buf = MockBuffer::new("  a\nb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3oabc\x1b");
+    assert_eq!(buf.text(), "  a\n  abc\n  abc\n  abc\nb");
+    assert_eq!(cur, pos(3, 5));
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(4, 0), "2Ox\x1b");
+    assert_eq!(buf.text(), "  a\n  abc\n  abc\n  abc\nx\nx\nb");
+    assert_eq!(cur, pos(5, 1));
+
+    // Line breaks typed in the text are repeated too
+    let mut buf = MockBuffer::new("z");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2oa\rb\x1b");
+    assert_eq!(buf.text(), "z\na\nb\na\nb");
+}
+
+#[test]
+fn a_count_without_text_opens_empty_lines() {
+    let mut buf = MockBuffer::new("a\nb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3o\x1b");
+    assert_eq!(buf.text(), "a\n\n\n\nb");
+}
+
+#[test]
+fn the_indent_goes_when_nothing_is_typed() {
+    let mut buf = MockBuffer::new("  ab");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "o\x1b");
+    assert_eq!(buf.text(), "  ab\n");
+    assert_eq!(cur, pos(1, 0));
+
+    let mut buf = MockBuffer::new("  ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3o\x1b");
+    assert_eq!(buf.text(), "  ab\n\n\n");
+    let mut buf = MockBuffer::new("  ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2O\x1b");
+    assert_eq!(buf.text(), "\n\n  ab");
+    // and when the change is repeated
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), ".");
+    assert_eq!(buf.text(), "\n\n\n\n  ab");
+
+    // Moving to another line removes it too, moving along the line does not
+    let mut buf = MockBuffer::new("  ab");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "o");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Up>");
+    assert_eq!(buf.text(), "  ab\n");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "\x1bo");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Left>");
+    feed(&mut eng, &mut buf, &mut cb, cur, "\x1b");
+    assert_eq!(buf.text(), "  ab\n  \n");
+}
+
+#[test]
+fn dot_repeats_open_line_with_its_count() {
+    let mut buf = MockBuffer::new("a\nb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2ox\x1b");
+    assert_eq!(buf.text(), "a\nx\nx\nb");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(3, 0), ".");
+    assert_eq!(buf.text(), "a\nx\nx\nb\nx\nx");
+    assert_eq!(cur, pos(5, 1));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "1.");
+    assert_eq!(buf.text(), "a\nx\nx\nx\nb\nx\nx");
+}
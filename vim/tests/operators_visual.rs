@@ -1,26 +1,13 @@
 use vim_mini::{
-    Engine, InputEvent, KeyCode, KeyEvent,
+    Engine, KeyCode,
     types::{Command, Mode, Position, VisualKind},
 };
 
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: vim_mini::key::Modifiers::empty(),
-    })
-}
-
-fn esc() -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Esc,
-        mods: vim_mini::key::Modifiers::empty(),
-    })
-}
-
 #[test]
 fn test_dd_deletes_line() {
     let buf = MockBuffer::new("line one\nline two\nline three\n");
@@ -29,10 +16,10 @@ fn test_dd_deletes_line() {
     let cur = Position { line: 1, col: 0 };
 
     // dd on second line
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
     assert_eq!(cmds.len(), 0); // Operator pending
 
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
     assert_eq!(new_cur.line, 1);
     assert_eq!(new_cur.col, 0);
     assert_eq!(cmds.len(), 1);
@@ -47,9 +34,9 @@ fn test_count_dd_deletes_multiple_lines() {
     let cur = Position { line: 1, col: 0 };
 
     // 2dd should delete lines 2 and 3
-    eng.handle_event(&buf, &mut clipboard, cur, key('2'));
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('2')));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
 
     assert_eq!(new_cur.line, 1);
     assert_eq!(cmds.len(), 1);
@@ -66,7 +53,7 @@ fn test_x_deletes_character() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('x'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('x')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -83,8 +70,8 @@ fn test_count_x_deletes_multiple() {
     let cur = Position { line: 0, col: 0 };
 
     // 3x should delete "hel"
-    eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('x'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('x')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -100,7 +87,7 @@ fn test_x_at_end_of_line() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 2 }; // Past last character
 
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('x'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('x')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 0); // Nothing to delete
 }
@@ -112,8 +99,8 @@ fn test_dh_deletes_left() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 5 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('h'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('h')));
     assert_eq!(new_cur.col, 4);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -129,8 +116,8 @@ fn test_dl_deletes_right() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -146,8 +133,8 @@ fn test_dj_deletes_down() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('j'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -163,8 +150,8 @@ fn test_d0_deletes_to_line_start() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 5 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('0'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('0')));
     assert_eq!(new_cur.col, 0);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -180,8 +167,8 @@ fn test_d_dollar_deletes_to_line_end() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 5 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('$'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('$')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
     if let Command::Delete { range } = &cmds[0] {
@@ -197,7 +184,7 @@ fn test_visual_charwise_mode() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
     assert_eq!(cmds.len(), 1);
     if let Command::SetSelection(Some(sel)) = &cmds[0] {
         assert_eq!(sel.start, cur);
@@ -217,7 +204,7 @@ fn test_visual_linewise_mode() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 2 };
 
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('V'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('V')));
     assert_eq!(cmds.len(), 1);
     if let Command::SetSelection(Some(sel)) = &cmds[0] {
         assert_eq!(sel.start.line, 0);
@@ -235,10 +222,10 @@ fn test_visual_movement_updates_selection() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter visual mode
-    eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
 
     // Move right
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(new_cur.col, 1);
     assert_eq!(cmds.len(), 2); // SetCursor and SetSelection
 
@@ -256,8 +243,8 @@ fn test_visual_escape_exits() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('v'));
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, esc());
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Esc));
     assert_eq!(cmds.len(), 1);
     assert!(matches!(cmds[0], Command::SetSelection(None)));
     assert_eq!(eng.snapshot().mode, Mode::Normal);
@@ -270,8 +257,8 @@ fn test_visual_v_toggles_off() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('v'));
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
     assert_eq!(cmds.len(), 1);
     assert!(matches!(cmds[0], Command::SetSelection(None)));
     assert_eq!(eng.snapshot().mode, Mode::Normal);
@@ -285,14 +272,14 @@ fn test_visual_delete() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter visual mode
-    eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
 
     // Move to select "hello"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('4'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('4')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
 
     // Delete selection
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
     assert_eq!(new_cur.col, 0);
 
     // Should have Delete and SetSelection(None) commands
@@ -315,13 +302,13 @@ fn test_visual_line_delete() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter visual line mode
-    eng.handle_event(&buf, &mut clipboard, cur, key('V'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('V')));
 
     // Move down to select two lines
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('j'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j')));
 
     // Delete selection
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
     assert_eq!(new_cur.line, 0);
     assert_eq!(new_cur.col, 0);
 
@@ -339,8 +326,8 @@ fn test_operator_escape_cancels() {
     let mut clipboard = MockClipboard::new();
     let cur = Position { line: 0, col: 0 };
 
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, esc());
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Esc));
     assert_eq!(cmds.len(), 0); // No delete should happen
     assert_eq!(eng.snapshot().mode, Mode::Normal);
 }
@@ -353,11 +340,11 @@ fn test_gg_in_visual_mode() {
     let cur = Position { line: 2, col: 0 };
 
     // Enter visual mode at last line
-    eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
 
     // gg should move to first line
-    eng.handle_event(&buf, &mut clipboard, cur, key('g'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('g'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('g')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('g')));
     assert_eq!(new_cur.line, 0);
 
     // Check selection spans from first to last line
@@ -376,9 +363,9 @@ fn test_operator_pending_with_count() {
     let cur = Position { line: 0, col: 0 };
 
     // d3l should delete 3 characters
-    eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
     assert_eq!(new_cur, cur);
     assert_eq!(cmds.len(), 1);
 
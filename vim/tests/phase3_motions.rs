@@ -1,17 +1,11 @@
 use vim_mini::types::Position;
-use vim_mini::{Engine, InputEvent, KeyCode, KeyEvent};
+use vim_mini::{Engine, KeyCode};
 
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: vim_mini::Modifiers::empty(),
-    })
-}
-
 #[test]
 fn word_forward_basic() {
     let buf = MockBuffer::new("hello world rust\nprogramming is fun");
@@ -20,17 +14,17 @@ fn word_forward_basic() {
     let mut cur = Position { line: 0, col: 0 };
 
     // Move to next word "world"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 6 });
     cur = c;
 
     // Move to next word "rust"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 12 });
     cur = c;
 
     // Move to next line "programming"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 1, col: 0 });
 }
 
@@ -42,8 +36,8 @@ fn word_forward_with_count() {
     let cur = Position { line: 0, col: 0 };
 
     // Move forward 3 words
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 14 }); // at "four"
 }
 
@@ -55,15 +49,15 @@ fn word_backward_basic() {
     let cur = Position { line: 1, col: 15 }; // at 'f' in "fun"
 
     // Move back to "is"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('b'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('b')));
     assert_eq!(c, Position { line: 1, col: 12 });
 
     // Move back to "programming"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('b'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('b')));
     assert_eq!(c, Position { line: 1, col: 0 });
 
     // Move back to previous line "rust"
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('b'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('b')));
     assert_eq!(c, Position { line: 0, col: 12 });
 }
 
@@ -75,10 +69,10 @@ fn word_with_punctuation() {
     let cur = Position { line: 0, col: 0 };
 
     // 'w' should stop at punctuation
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 7 }); // at "world"
 
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 14 }); // at "test"
 }
 
@@ -92,11 +86,11 @@ fn paragraph_forward() {
     let cur = Position { line: 0, col: 0 };
 
     // Move to start of second paragraph
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('}'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('}')));
     assert_eq!(c, Position { line: 3, col: 0 });
 
     // Move to start of third paragraph
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('}'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('}')));
     assert_eq!(c, Position { line: 7, col: 0 });
 }
 
@@ -110,11 +104,11 @@ fn paragraph_backward() {
     let cur = Position { line: 7, col: 0 }; // at "Third"
 
     // Move to start of second paragraph
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('{'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('{')));
     assert_eq!(c, Position { line: 3, col: 0 });
 
     // Move to start of first paragraph
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('{'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('{')));
     assert_eq!(c, Position { line: 0, col: 0 });
 }
 
@@ -126,8 +120,8 @@ fn find_char_forward() {
     let cur = Position { line: 0, col: 0 };
 
     // Find 'o' (first occurrence)
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('f'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('o'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('f')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('o')));
     assert_eq!(c, Position { line: 0, col: 4 }); // at 'o' in "hello"
 }
 
@@ -139,9 +133,9 @@ fn find_char_forward_with_count() {
     let cur = Position { line: 0, col: 0 };
 
     // Find 3rd 'o'
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('f'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('o'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('f')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('o')));
     assert_eq!(c, Position { line: 0, col: 14 }); // at 'o' in "look"
 }
 
@@ -153,8 +147,8 @@ fn till_char_forward() {
     let cur = Position { line: 0, col: 0 };
 
     // Till 'w' (stop before it)
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('t'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('t')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 5 }); // at space before 'w'
 }
 
@@ -166,8 +160,8 @@ fn find_char_not_found() {
     let cur = Position { line: 0, col: 0 };
 
     // Try to find 'z' which doesn't exist
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('f'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('z'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('f')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('z')));
     assert_eq!(c, cur); // cursor should not move
 }
 
@@ -179,8 +173,8 @@ fn delete_word() {
     let cur = Position { line: 0, col: 0 };
 
     // Delete word "hello "
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 0 });
     assert_eq!(cmds.len(), 1);
     match &cmds[0] {
@@ -200,8 +194,8 @@ fn delete_paragraph() {
     let cur = Position { line: 0, col: 0 };
 
     // Delete to next paragraph
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('}'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('}')));
     assert_eq!(c, Position { line: 0, col: 0 });
     match &cmds[0] {
         vim_mini::types::Command::Delete { range } => {
@@ -220,9 +214,9 @@ fn delete_find() {
     let cur = Position { line: 0, col: 0 };
 
     // Delete up to and including 'w'
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('f'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('f')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 0 });
     match &cmds[0] {
         vim_mini::types::Command::Delete { range } => {
@@ -241,9 +235,9 @@ fn delete_till() {
     let cur = Position { line: 0, col: 0 };
 
     // Delete up to (but not including) 'w'
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('d'));
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key('t'));
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('d')));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('t')));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 0 });
     match &cmds[0] {
         vim_mini::types::Command::Delete { range } => {
@@ -262,11 +256,11 @@ fn visual_word_selection() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter visual mode
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('v'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
     assert_eq!(cmds.len(), 1);
 
     // Select to next word
-    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key('w'));
+    let (c, cmds) = eng.handle_event(&buf, &mut clipboard, c, key(KeyCode::Char('w')));
     assert_eq!(c, Position { line: 0, col: 6 });
     assert_eq!(cmds.len(), 2); // SetCursor and SetSelection
 
@@ -287,7 +281,7 @@ fn word_motion_at_end_of_buffer() {
     let cur = Position { line: 0, col: 6 }; // at 'w'
 
     // Try to move forward when at last word
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
     assert_eq!(c.line, 0); // should stay on same line
 }
 
@@ -299,6 +293,6 @@ fn paragraph_motion_with_multiple_blanks() {
     let cur = Position { line: 0, col: 0 };
 
     // Should skip all blank lines
-    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('}'));
+    let (c, _cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('}')));
     assert_eq!(c, Position { line: 5, col: 0 });
 }
@@ -1,18 +1,12 @@
 use proptest::prelude::*;
 use vim_mini::types::{Command, Position};
-use vim_mini::{Engine, InputEvent, KeyCode, KeyEvent, Modifiers, TextOps};
+use vim_mini::{Engine, KeyCode, TextOps};
 
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: Modifiers::empty(),
-    })
-}
-
 // Strategy for generating text content with various edge cases
 fn text_strategy() -> impl Strategy<Value = String> {
     prop_oneof![
@@ -75,12 +69,12 @@ proptest! {
         // Apply count if > 0
         if count > 0 && count <= 9 {
             for digit in count.to_string().chars() {
-                let _ = eng.handle_event(&buf, &mut clipboard, start, key(digit));
+                let _ = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(digit)));
             }
         }
 
         // Apply motion - should not panic
-        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(motion));
+        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(motion)));
 
         // Verify position is within bounds
         assert!(new_pos.line < buf.line_count() || (new_pos.line == 0 && buf.line_count() == 0));
@@ -103,7 +97,7 @@ proptest! {
         let start = Position { line: start_line, col: start_col };
 
         // Motion from potentially invalid position should not panic
-        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(motion));
+        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(motion)));
 
         // Result should be valid
         assert!(new_pos.line < buf.line_count() || (new_pos.line == 0 && buf.line_count() == 0));
@@ -124,15 +118,15 @@ proptest! {
 
         // Set count
         if count > 1 {
-            let _ = eng.handle_event(&buf, &mut clipboard, start, key(char::from_digit(count, 10).unwrap_or('1')));
+            let _ = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(char::from_digit(count, 10).unwrap_or('1'))));
         }
 
         // f or t
         let find_key = if before { 't' } else { 'f' };
-        let _ = eng.handle_event(&buf, &mut clipboard, start, key(find_key));
+        let _ = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(find_key)));
 
         // Target character - should not panic even if not found
-        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(target));
+        let (new_pos, _cmds) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(target)));
 
         // Position should be valid
         assert!(new_pos.line < buf.line_count() || (new_pos.line == 0 && buf.line_count() == 0));
@@ -150,10 +144,10 @@ proptest! {
         let start = Position { line: 0, col: 0 };
 
         // Enter delete operator
-        let _ = eng.handle_event(&buf, &mut clipboard, start, key('d'));
+        let _ = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char('d')));
 
         // Apply motion
-        let (_pos, cmds) = eng.handle_event(&buf, &mut clipboard, start, key(motion));
+        let (_pos, cmds) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(motion)));
 
         // Check all delete commands have valid ranges
         for cmd in cmds {
@@ -184,10 +178,10 @@ proptest! {
         let start = Position { line: 0, col: 0 };
 
         // Move word forward - should handle emoji correctly
-        let (pos1, _) = eng.handle_event(&buf, &mut clipboard, start, key('w'));
+        let (pos1, _) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char('w')));
 
         // Move word forward again
-        let (pos2, _) = eng.handle_event(&buf, &mut clipboard, pos1, key('w'));
+        let (pos2, _) = eng.handle_event(&buf, &mut clipboard, pos1, key(KeyCode::Char('w')));
 
         // Both positions should be valid
         assert!(pos1.line < buf.line_count());
@@ -213,7 +207,7 @@ proptest! {
         let start = Position { line: 0, col: 0 };
 
         // Move to next paragraph - should not panic with any number of blanks
-        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, start, key('}'));
+        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char('}')));
 
         assert!(new_pos.line < buf.line_count());
     }
@@ -233,11 +227,11 @@ proptest! {
         // Apply large count (only use first 2 digits to avoid overflow)
         let count_str = count.to_string();
         for digit in count_str.chars().take(2) {
-            let _ = eng.handle_event(&buf, &mut clipboard, start, key(digit));
+            let _ = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(digit)));
         }
 
         // Motion with large count should clamp, not panic
-        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, start, key(motion));
+        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, start, key(KeyCode::Char(motion)));
 
         assert!(new_pos.line < buf.line_count() || (new_pos.line == 0 && buf.line_count() == 0));
     }
@@ -254,12 +248,12 @@ proptest! {
         let mut pos = Position { line: 0, col: 0 };
 
         // Enter visual mode
-        let (p, _) = eng.handle_event(&buf, &mut clipboard, pos, key('v'));
+        let (p, _) = eng.handle_event(&buf, &mut clipboard, pos, key(KeyCode::Char('v')));
         pos = p;
 
         // Apply series of motions
         for motion in motions {
-            let (p, cmds) = eng.handle_event(&buf, &mut clipboard, pos, key(motion));
+            let (p, cmds) = eng.handle_event(&buf, &mut clipboard, pos, key(KeyCode::Char(motion)));
             pos = p;
 
             // Check selection is valid
@@ -285,7 +279,7 @@ proptest! {
 
         // Apply sequence of motions
         for motion in motions {
-            let (p, _) = eng.handle_event(&buf, &mut clipboard, pos, key(motion));
+            let (p, _) = eng.handle_event(&buf, &mut clipboard, pos, key(KeyCode::Char(motion)));
             pos = p;
             assert!(pos.line < buf.line_count() || (pos.line == 0 && buf.line_count() == 0));
         }
@@ -302,7 +296,7 @@ fn empty_buffer_motions() {
 
     // All motions should handle empty buffer gracefully
     for motion in ['h', 'j', 'k', 'l', 'w', 'b', '{', '}', '0', '$', 'G'] {
-        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, pos, key(motion));
+        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, pos, key(KeyCode::Char(motion)));
         assert_eq!(new_pos, Position { line: 0, col: 0 });
     }
 }
@@ -324,7 +318,7 @@ fn single_char_buffer_motions() {
     ];
 
     for (motion, expected) in cases {
-        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, pos, key(motion));
+        let (new_pos, _) = eng.handle_event(&buf, &mut clipboard, pos, key(KeyCode::Char(motion)));
         assert_eq!(new_pos, expected, "Motion '{}' failed", motion);
     }
 }
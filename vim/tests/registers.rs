@@ -0,0 +1,223 @@
+use vim_mini::{
+    Engine, Register, RegisterKind,
+    traits::Clipboard,
+    types::{Command, Position},
+};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+fn reg(eng: &Engine, name: char) -> Option<&str> {
+    eng.registers().get(name).map(|r| r.text.as_str())
+}
+
+#[test]
+fn yank_fills_unnamed_and_zero_but_not_clipboard() {
+    let mut buf = MockBuffer::new("hello world\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "yw");
+    assert_eq!(reg(&eng, '"'), Some("hello "));
+    assert_eq!(reg(&eng, '0'), Some("hello "));
+    assert_eq!(clipboard.get(), None);
+}
+
+#[test]
+fn named_register_yank_and_put() {
+    let mut buf = MockBuffer::new("alpha beta\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"ayw");
+    assert_eq!(reg(&eng, 'a'), Some("alpha "));
+    // Named yanks leave register 0 alone
+    assert_eq!(reg(&eng, '0'), None);
+
+    // Overwrite the unnamed register, then put from "a
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "x");
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"ap");
+    assert_eq!(
+        cmds,
+        vec![Command::InsertText {
+            at: pos(0, 1),
+            text: "alpha ".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn uppercase_register_appends() {
+    let mut buf = MockBuffer::new("one two three\nfour\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"ayw");
+    feed(&mut eng, &mut buf, &mut clipboard, pos(0, 4), "\"Ayw");
+    assert_eq!(reg(&eng, 'a'), Some("one two "));
+    assert_eq!(reg(&eng, '"'), Some("one two "));
+
+    // Appending a linewise yank turns the register linewise
+    feed(&mut eng, &mut buf, &mut clipboard, pos(1, 0), "\"Ayy");
+    let a = eng.registers().get('a').unwrap();
+    assert_eq!(a.text, "one two \nfour\n");
+    assert_eq!(a.kind, RegisterKind::LineWise);
+}
+
+#[test]
+fn line_deletes_shift_through_numbered_registers() {
+    let mut buf = MockBuffer::new("first\nsecond\nthird\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "dd");
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "dd");
+    assert_eq!(reg(&eng, '1'), Some("second\n"));
+    assert_eq!(reg(&eng, '2'), Some("first\n"));
+    assert_eq!(reg(&eng, '"'), Some("second\n"));
+}
+
+#[test]
+fn dot_after_numbered_put_moves_to_the_next_register() {
+    let mut buf = MockBuffer::new("one\ntwo\nthree\nend");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "dddddd");
+    assert_eq!(buf.text(), "end");
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"1p");
+    assert_eq!(buf.text(), "end\nthree");
+    feed(&mut eng, &mut buf, &mut clipboard, pos(1, 0), ".");
+    assert_eq!(buf.text(), "end\nthree\ntwo");
+    feed(&mut eng, &mut buf, &mut clipboard, pos(2, 0), ".");
+    assert_eq!(buf.text(), "end\nthree\ntwo\none");
+
+    // Other registers repeat as they are
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"ayy");
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "G\"ap");
+    feed(&mut eng, &mut buf, &mut clipboard, pos(4, 0), ".");
+    assert_eq!(buf.text(), "end\nthree\ntwo\none\nend\nend");
+}
+
+#[test]
+fn small_delete_goes_to_minus_register() {
+    let mut buf = MockBuffer::new("hello world\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "dw");
+    assert_eq!(reg(&eng, '-'), Some("hello "));
+    assert_eq!(reg(&eng, '1'), None);
+    assert_eq!(reg(&eng, '"'), Some("hello "));
+}
+
+#[test]
+fn yank_register_survives_deletes() {
+    let mut buf = MockBuffer::new("keep this\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "yw");
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "x");
+    assert_eq!(reg(&eng, '0'), Some("keep "));
+    assert_eq!(reg(&eng, '"'), Some("k"));
+}
+
+#[test]
+fn black_hole_register_discards() {
+    let mut buf = MockBuffer::new("abc def\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "yw");
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"_dw");
+    assert!(matches!(cmds[0], Command::Delete { .. }));
+    assert_eq!(reg(&eng, '"'), Some("abc "));
+    assert_eq!(reg(&eng, '-'), None);
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"_p");
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn clipboard_registers_use_host_clipboard() {
+    let mut buf = MockBuffer::new("copy me\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"+yy");
+    assert_eq!(clipboard.get(), Some("copy me\n".to_string()));
+
+    clipboard.set("from host".to_string());
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"*p");
+    assert_eq!(
+        cmds,
+        vec![Command::InsertText {
+            at: pos(0, 1),
+            text: "from host".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn read_only_registers() {
+    let mut buf = MockBuffer::new("some text\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    // "." holds the last inserted text
+    feed(
+        &mut eng,
+        &mut buf,
+        &mut clipboard,
+        Position::ZERO,
+        "inew\x1b",
+    );
+    assert_eq!(reg(&eng, '.'), Some("new"));
+
+    // "/" holds the last search pattern
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "/x\r");
+    assert_eq!(reg(&eng, '/'), Some("x"));
+
+    // Writing to a read-only register is ignored
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\".yw");
+    assert_eq!(reg(&eng, '.'), Some("new"));
+    assert_eq!(reg(&eng, ':'), None);
+}
+
+#[test]
+fn linewise_put_after_last_line_without_newline() {
+    let mut buf = MockBuffer::new("a\nb");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    eng.registers_mut().set('x', Register::linewise("new"));
+
+    let (new_cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(1, 0), "\"xp");
+    assert_eq!(new_cur, pos(2, 0));
+    assert_eq!(
+        cmds,
+        vec![Command::InsertText {
+            at: pos(1, 1),
+            text: "\nnew".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn register_applies_only_to_next_command() {
+    let mut buf = MockBuffer::new("one two three\n");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    // "a followed by a motion drops the register
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"al");
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "yw");
+    assert_eq!(reg(&eng, 'a'), None);
+    assert_eq!(reg(&eng, '0'), Some("one "));
+
+    // A count between the register and the operator keeps it
+    feed(&mut eng, &mut buf, &mut clipboard, Position::ZERO, "\"b2yw");
+    assert_eq!(reg(&eng, 'b'), Some("one two "));
+}
@@ -0,0 +1,164 @@
+use vim_mini::{Engine, Mode};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn r_replaces_count_characters() {
+    let mut buf = MockBuffer::new("abcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "rx");
+    assert_eq!(buf.text(), "xbcd");
+    assert_eq!(cur, pos(0, 0));
+    // The cursor ends on the last replaced character
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "3ry");
+    assert_eq!(buf.text(), "xyyy");
+    assert_eq!(cur, pos(0, 3));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    // Fewer characters than the count: nothing changes
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "2rz");
+    assert_eq!(buf.text(), "xyyy");
+    assert_eq!(cur, pos(0, 3));
+    assert!(cmds.is_empty());
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ".");
+    assert_eq!(buf.text(), "yyyy");
+}
+
+#[test]
+fn r_escape_cancels() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "r\x1bx");
+    assert_eq!(buf.text(), "ac");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(cmds.len(), 1);
+}
+
+#[test]
+fn r_enter_splits_the_line() {
+    let mut buf = MockBuffer::new("  ab cd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // The new line keeps the indent
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "r\r");
+    assert_eq!(buf.text(), "  ab\n  cd");
+    assert_eq!(cur, pos(1, 2));
+
+    // A count replaces that many characters with one line break
+    let mut buf = MockBuffer::new("abcd");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "2r\r");
+    assert_eq!(buf.text(), "a\nd");
+    assert_eq!(cur, pos(1, 0));
+}
+
+#[test]
+fn replace_mode_overwrites_and_appends() {
+    let mut buf = MockBuffer::new("abc\ndef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "Rx");
+    assert_eq!(eng.snapshot().mode, Mode::Replace);
+    assert_eq!(buf.text(), "axc\ndef");
+    assert_eq!(cur, pos(0, 2));
+    // Past the end of the line the text is added
+    feed(&mut eng, &mut buf, &mut cb, cur, "yz\x1b");
+    assert_eq!(buf.text(), "axyz\ndef");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn backspace_restores_the_overwritten_text() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "Rxy\x08");
+    assert_eq!(buf.text(), "abxdef");
+    assert_eq!(cur, pos(0, 3));
+    // Before where replacing started Backspace only moves
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "\x08\x08");
+    assert_eq!(buf.text(), "abcdef");
+    assert_eq!(cur, pos(0, 1));
+    feed(&mut eng, &mut buf, &mut cb, cur, "\x1b");
+
+    // Text added past the end is just deleted
+    let mut buf = MockBuffer::new("ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "Rxyz\x08\x08");
+    assert_eq!(buf.text(), "ax");
+}
+
+#[test]
+fn backspace_undoes_a_line_break() {
+    let mut buf = MockBuffer::new("ab  cd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // Enter inserts a break and drops the blanks after it
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "R\rX");
+    assert_eq!(buf.text(), "ab\nXd");
+    assert_eq!(cur, pos(1, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "\x08\x08");
+    assert_eq!(buf.text(), "ab  cd");
+    assert_eq!(cur, pos(0, 2));
+}
+
+#[test]
+fn insert_key_switches_between_insert_and_replace() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ix<Insert>y");
+    assert_eq!(eng.snapshot().mode, Mode::Replace);
+    assert_eq!(buf.text(), "xybc");
+    feed(&mut eng, &mut buf, &mut cb, cur, "<Insert>z\x1b");
+    assert_eq!(buf.text(), "xyzbc");
+
+    // Insert in normal mode starts insert mode
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<Insert><Insert>");
+    assert_eq!(eng.snapshot().mode, Mode::Replace);
+}
+
+#[test]
+fn dot_repeats_replace_mode() {
+    let mut buf = MockBuffer::new("aaaa bbbb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Rxy\x1b");
+    assert_eq!(buf.text(), "xyaa bbbb");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 5), ".");
+    assert_eq!(buf.text(), "xyaa xybb");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn replace_mode_repeats_the_typed_text_for_a_count() {
+    let mut buf = MockBuffer::new("abcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2RXY\x1b");
+    assert_eq!(buf.text(), "XYXY");
+    assert_eq!(cur, pos(0, 4));
+
+    // The copies run past the end of the line
+    let mut buf = MockBuffer::new("ab\ncd");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "3Rxy\x1b");
+    assert_eq!(buf.text(), "xyxyxy\ncd");
+    assert_eq!(cur, pos(0, 6));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ".");
+    assert_eq!(buf.text(), "xyxyxy\nxyxyxy");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
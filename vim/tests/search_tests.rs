@@ -1,37 +1,10 @@
-use vim_mini::{Engine, InputEvent, KeyCode, KeyEvent};
+use vim_mini::{Engine, InputEvent, KeyCode};
 
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: vim_mini::Modifiers::empty(),
-    })
-}
-
-fn key_esc() -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Esc,
-        mods: vim_mini::Modifiers::empty(),
-    })
-}
-
-fn key_enter() -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Enter,
-        mods: vim_mini::Modifiers::empty(),
-    })
-}
-
-fn key_backspace() -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Backspace,
-        mods: vim_mini::Modifiers::empty(),
-    })
-}
-
 fn char(c: char) -> InputEvent {
     InputEvent::ReceivedChar(c)
 }
@@ -43,7 +16,7 @@ fn enter_search_mode() {
     let mut clipboard = MockClipboard::new();
     let cur = vim_mini::Position { line: 0, col: 0 };
 
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 0);
 
@@ -59,7 +32,7 @@ fn search_forward_basic() {
     let cur = vim_mini::Position { line: 0, col: 0 };
 
     // Enter search mode
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
 
     // Type "world"
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('w'));
@@ -69,7 +42,7 @@ fn search_forward_basic() {
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('d'));
 
     // Press Enter to search
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should find "world" on first line
     assert_eq!(cur.line, 0);
@@ -88,27 +61,27 @@ fn search_with_n_next() {
     let cur = vim_mini::Position { line: 0, col: 0 };
 
     // Search for "world"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('w'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('o'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('r'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('d'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // First match at line 0, col 6
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 6);
 
     // Press 'n' to find next match
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
 
     // Should find "world" on third line
     assert_eq!(cur.line, 2);
     assert_eq!(cur.col, 4);
 
     // Press 'n' again - should wrap to first match
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 6);
 }
@@ -121,20 +94,20 @@ fn search_with_capital_n_reverse() {
     let cur = vim_mini::Position { line: 0, col: 0 };
 
     // Search for "world"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('w'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('o'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('r'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('d'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // First match at line 0, col 6
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 6);
 
     // Press 'N' to find previous match (reverse direction)
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('N'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('N')));
 
     // Should wrap to last match
     assert_eq!(cur.line, 2);
@@ -149,14 +122,14 @@ fn search_cancel_with_esc() {
     let cur = vim_mini::Position { line: 1, col: 2 };
 
     // Enter search mode
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
 
     // Type partial query
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('w'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('o'));
 
     // Cancel with Esc
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_esc());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Esc));
 
     // Should be back at original position
     assert_eq!(cur.line, 1);
@@ -167,7 +140,7 @@ fn search_cancel_with_esc() {
     assert!(matches!(snapshot.mode, vim_mini::Mode::Normal));
 
     // 'n' should not work (no last search)
-    let (cur2, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur2, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
     assert_eq!(cur2, cur);
 }
 
@@ -179,7 +152,7 @@ fn search_with_backspace() {
     let cur = vim_mini::Position { line: 0, col: 0 };
 
     // Enter search mode
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
 
     // Type "hello" but then backspace to "hel"
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('h'));
@@ -187,18 +160,18 @@ fn search_with_backspace() {
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('o'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_backspace());
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_backspace());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Backspace));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Backspace));
 
     // Search for "hel"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should find "help" on second line (skipping "hello" at current position)
     assert_eq!(cur.line, 1);
     assert_eq!(cur.col, 0);
 
     // Press 'n' to find next "hel" (wraps back to "hello")
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 0);
 }
@@ -211,11 +184,11 @@ fn search_not_found() {
     let cur = vim_mini::Position { line: 0, col: 5 };
 
     // Search for something that doesn't exist
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('x'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('y'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('z'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should stay at original position
     assert_eq!(cur.line, 0);
@@ -232,19 +205,19 @@ fn search_wrap_around() {
     let cur = vim_mini::Position { line: 1, col: 8 };
 
     // Search for "line" - should wrap to first line
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('i'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('n'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('e'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should wrap around to first line
     assert_eq!(cur.line, 2);
     assert_eq!(cur.col, 6);
 
     // Next match should wrap to first line
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 6);
 }
@@ -257,8 +230,8 @@ fn search_empty_query() {
     let cur = vim_mini::Position { line: 0, col: 5 };
 
     // Enter search mode and immediately press Enter
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should stay at original position
     assert_eq!(cur.line, 0);
@@ -273,20 +246,20 @@ fn search_unicode() {
     let cur = vim_mini::Position { line: 0, col: 0 };
 
     // Search for "world"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('/'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('/')));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('w'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('o'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('r'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('l'));
     let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, char('d'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key_enter());
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Enter));
 
     // Should find "world" after the emoji
     assert_eq!(cur.line, 0);
     assert_eq!(cur.col, 8);
 
     // Next match
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('n'));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('n')));
     assert_eq!(cur.line, 1);
     assert_eq!(cur.col, 3);
 }
@@ -0,0 +1,189 @@
+use vim_mini::{
+    Engine,
+    types::{Mode, Position, VisualKind},
+};
+
+mod support;
+use support::feed::{edit, feed, pos, selection};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+/// Where the cursor lands after `keys`.
+fn motion(text: &str, cur: Position, keys: &str) -> Position {
+    let mut buf = MockBuffer::new(text);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    feed(&mut eng, &mut buf, &mut clipboard, cur, keys).0
+}
+
+const PROSE: &str = "One two. Three four! Five?  Six.";
+
+#[test]
+fn close_paren_moves_to_next_sentence() {
+    assert_eq!(motion(PROSE, pos(0, 0), ")"), pos(0, 9));
+    assert_eq!(motion(PROSE, pos(0, 0), "2)"), pos(0, 21));
+    // Two blanks after "Five?"
+    assert_eq!(motion(PROSE, pos(0, 21), ")"), pos(0, 28));
+}
+
+#[test]
+fn open_paren_moves_to_sentence_start() {
+    assert_eq!(motion(PROSE, pos(0, 14), "("), pos(0, 9));
+    assert_eq!(motion(PROSE, pos(0, 9), "("), pos(0, 0));
+    assert_eq!(motion(PROSE, pos(0, 30), "2("), pos(0, 21));
+}
+
+#[test]
+fn closing_punctuation_belongs_to_the_sentence() {
+    let text = "He said (\"stop.\") Then left.";
+    assert_eq!(motion(text, pos(0, 0), ")"), pos(0, 18));
+}
+
+#[test]
+fn dot_without_blank_does_not_end_sentence() {
+    assert_eq!(motion("Use v1.2 now. Next", pos(0, 0), ")"), pos(0, 14));
+}
+
+#[test]
+fn empty_line_is_a_sentence_boundary() {
+    let text = "First line\n\nSecond para.";
+    assert_eq!(motion(text, pos(0, 0), ")"), pos(1, 0));
+    assert_eq!(motion(text, pos(1, 0), ")"), pos(2, 0));
+}
+
+#[test]
+fn paren_motion_fails_at_buffer_edge() {
+    let mut buf = MockBuffer::new("Only one.");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 0), "(");
+    assert_eq!(cur, pos(0, 0));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn d_close_paren_deletes_to_next_sentence() {
+    assert_eq!(edit(PROSE, pos(0, 0), "d)"), "Three four! Five?  Six.");
+}
+
+#[test]
+fn dis_deletes_sentence_but_not_blanks() {
+    assert_eq!(edit(PROSE, pos(0, 12), "dis"), "One two.  Five?  Six.");
+}
+
+#[test]
+fn das_deletes_sentence_and_trailing_blanks() {
+    assert_eq!(edit(PROSE, pos(0, 12), "das"), "One two. Five?  Six.");
+}
+
+#[test]
+fn das_on_last_sentence_takes_leading_blanks() {
+    assert_eq!(edit(PROSE, pos(0, 30), "das"), "One two. Three four! Five?");
+}
+
+#[test]
+fn count_is_counts_blanks_as_sentences() {
+    // "One two.", the blank and "Three four!"
+    assert_eq!(edit(PROSE, pos(0, 0), "d3is"), " Five?  Six.");
+    assert_eq!(edit(PROSE, pos(0, 0), "d2as"), "Five?  Six.");
+}
+
+#[test]
+fn is_on_blanks_selects_the_blanks() {
+    assert_eq!(
+        edit(PROSE, pos(0, 27), "dis"),
+        "One two. Three four! Five?Six."
+    );
+}
+
+#[test]
+fn das_on_whole_line_sentence_deletes_line() {
+    let text = "Intro here.\nSecond line.\n";
+    assert_eq!(edit(text, pos(0, 3), "das"), "Second line.\n");
+}
+
+#[test]
+fn vis_selects_sentence_and_extends() {
+    let mut buf = MockBuffer::new(PROSE);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 1), "vis");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 0), pos(0, 7)));
+    // The blank and the next sentence
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "as");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 0), pos(0, 19)));
+}
+
+const PARAS: &str = "a1\na2\n\n\nb1\nb2\n\nc1\n";
+
+#[test]
+fn dip_deletes_paragraph_lines() {
+    assert_eq!(edit(PARAS, pos(0, 1), "dip"), "\n\nb1\nb2\n\nc1\n");
+}
+
+#[test]
+fn dap_deletes_paragraph_and_following_blank_lines() {
+    assert_eq!(edit(PARAS, pos(4, 0), "dap"), "a1\na2\n\n\nc1\n");
+}
+
+#[test]
+fn dap_on_last_paragraph_takes_preceding_blank_lines() {
+    let text = "a1\na2\n\n\nb1\nb2\n\nc1";
+    assert_eq!(edit(text, pos(7, 0), "dap"), "a1\na2\n\n\nb1\nb2\n");
+}
+
+#[test]
+fn ip_on_blank_lines_selects_them() {
+    assert_eq!(edit(PARAS, pos(2, 0), "dip"), "a1\na2\nb1\nb2\n\nc1\n");
+}
+
+#[test]
+fn count_ip_counts_blank_runs() {
+    assert_eq!(edit(PARAS, pos(0, 0), "d3ip"), "\nc1\n");
+    assert_eq!(edit(PARAS, pos(0, 0), "d2ap"), "c1\n");
+}
+
+#[test]
+fn yip_yanks_lines_linewise() {
+    let mut buf = MockBuffer::new(PARAS);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, pos(5, 1), "yip");
+    assert_eq!(cur, pos(4, 0));
+    let reg = eng.registers().get('0').unwrap();
+    assert_eq!(reg.text, "b1\nb2\n");
+    assert_eq!(reg.kind, vim_mini::RegisterKind::LineWise);
+}
+
+#[test]
+fn cip_changes_paragraph_lines() {
+    let mut buf = MockBuffer::new(PARAS);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, pos(4, 0), "cip");
+    assert_eq!(cur, pos(4, 0));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn vip_selects_lines_and_extends() {
+    let mut buf = MockBuffer::new(PARAS);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 1), "vip");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::LineWise));
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start.line, sel.end.line), (0, 1));
+    // "ip" again adds the blank lines, then the next paragraph
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, cur, "ip");
+    assert_eq!(cur.line, 3);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, cur, "ip");
+    assert_eq!(cur.line, 5);
+}
@@ -0,0 +1,267 @@
+use vim_mini::{Command, Engine, Mode, Register};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn arrows_move_like_hjkl() {
+    let mut buf = MockBuffer::new("abc\ndef\nghi");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<Down><Right>");
+    assert_eq!(cur, pos(1, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2<Right><Up>");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Left>");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn shift_and_ctrl_arrows_move_by_words() {
+    let mut buf = MockBuffer::new("one two three");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<S-Right><C-Right>");
+    assert_eq!(cur, pos(0, 8));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<S-Left>");
+    assert_eq!(cur, pos(0, 4));
+}
+
+#[test]
+fn home_and_end() {
+    let mut buf = MockBuffer::new("  hello");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "<End>");
+    assert_eq!(cur, pos(0, 6));
+    // A count does not turn Home into a count digit
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3<Home>");
+    assert_eq!(cur, pos(0, 0));
+    assert_eq!(eng.snapshot().pending_count, None);
+}
+
+#[test]
+fn navigation_keys_work_with_operators() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "d<End>");
+    assert_eq!(buf.text(), "ab");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "d<Left>");
+    assert_eq!(buf.text(), "b");
+}
+
+#[test]
+fn delete_key_in_normal_and_visual_mode() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2<Del>");
+    assert_eq!(buf.text(), "cdef");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vl<Del>");
+    assert_eq!(buf.text(), "cf");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn insert_key_starts_insert_mode() {
+    let mut buf = MockBuffer::new("ac");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<Insert>b<Esc>");
+    assert_eq!(buf.text(), "abc");
+}
+
+#[test]
+fn special_keys_cancel_a_command_waiting_for_a_character() {
+    let mut buf = MockBuffer::new("a<b");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "f<Left>");
+    assert_eq!(cur, pos(0, 0));
+    assert!(cmds.is_empty());
+    // The next key is a command again
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "l");
+    assert_eq!(cur, pos(0, 1));
+}
+
+#[test]
+fn function_keys_are_left_to_the_host() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    for mode in ["", "i"] {
+        let (cur, cmds) = feed(
+            &mut eng,
+            &mut buf,
+            &mut cb,
+            pos(0, 1),
+            &format!("{mode}<F1>"),
+        );
+        assert_eq!(cur, pos(0, 1));
+        assert!(cmds.is_empty());
+    }
+    assert_eq!(buf.text(), "abc");
+}
+
+#[test]
+fn arrows_move_in_insert_mode() {
+    let mut buf = MockBuffer::new("abc\nd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "i<Right>");
+    assert_eq!(cur, pos(0, 2));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(0, 2))]);
+    // Right may go past the last character, Down keeps the column
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Right><Right>");
+    assert_eq!(cur, pos(0, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Down>");
+    assert_eq!(cur, pos(1, 1));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Up>");
+    assert_eq!(cur, pos(0, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Home>x<End>y");
+    assert_eq!(buf.text(), "xabcy\nd");
+    assert_eq!(cur, pos(0, 5));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn arrows_in_insert_mode_start_a_new_change() {
+    let mut buf = MockBuffer::new("");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "iab<Left>c<Esc>");
+    assert_eq!(buf.text(), "acb");
+    // Only the text typed after the arrow is repeated
+    feed(&mut eng, &mut buf, &mut cb, cur, "$.");
+    assert_eq!(buf.text(), "accb");
+    assert_eq!(eng.registers().get('.').unwrap().text, "c");
+}
+
+#[cfg(feature = "undo")]
+#[test]
+fn arrows_in_insert_mode_break_the_undo_group() {
+    let mut buf = MockBuffer::new("");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "iab<Left>c<Esc>");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "ab");
+    feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "");
+}
+
+#[test]
+fn keys_move_and_delete_in_the_search_prompt() {
+    let mut buf = MockBuffer::new("dfe de dxe");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "/de<Left>f");
+    assert_eq!(eng.snapshot().prompt_cursor, Some(2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<CR>");
+    assert_eq!(cur, pos(0, 0));
+    assert_eq!(eng.snapshot().prompt_cursor, None);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "n");
+    assert_eq!(cur, pos(0, 0));
+
+    // Home, End, Delete and Backspace at the cursor
+    let (cur, _) = feed(
+        &mut eng,
+        &mut buf,
+        &mut cb,
+        pos(0, 0),
+        "/xde<Home><Del><End>x<Left><BS><Right>e<CR>",
+    );
+    assert_eq!(cur, pos(0, 7));
+}
+
+#[test]
+fn keys_move_and_delete_on_the_command_line() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(
+        &mut eng,
+        &mut buf,
+        &mut cb,
+        pos(0, 0),
+        ":d<Home>2<End><Left><Right>",
+    );
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some("2d"));
+    assert_eq!(eng.snapshot().prompt_cursor, Some(2));
+    feed(
+        &mut eng,
+        &mut buf,
+        &mut cb,
+        pos(0, 0),
+        "<Left><Left><Del>1<CR>",
+    );
+    assert_eq!(buf.text(), "b\nc");
+
+    // The range typed for a count or a selection puts the cursor after it
+    let mut buf = MockBuffer::new("a\nb\nc");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "2:");
+    assert_eq!(eng.snapshot().prompt_cursor, Some(5));
+    feed(
+        &mut eng,
+        &mut buf,
+        &mut cb,
+        pos(0, 0),
+        "<Home><Del><Del><End>d<CR>",
+    );
+    assert_eq!(buf.text(), "a\nc");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "V:<BS><BS><BS>d");
+    assert_eq!(eng.snapshot().command_line.as_deref(), Some("'<d"));
+}
+
+#[test]
+fn tab_key_is_ctrl_i() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "i<Tab><Esc>");
+    assert_eq!(buf.text(), "\ta\nb\nc");
+
+    // Normal mode: newer in the jumplist
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "G");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-o>");
+    assert_eq!(cur, pos(0, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<Tab>");
+    assert_eq!(cur, pos(2, 0));
+}
+
+#[test]
+fn special_keys_in_macros() {
+    let mut buf = MockBuffer::new("abc\ndef\nghi");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "qa<Down><Del>q");
+    assert_eq!(buf.text(), "abc\nef\nghi");
+
+    // The register text stands for the same keys when run from text
+    let text = eng.registers().get('a').unwrap().text.clone();
+    eng.registers_mut().set('b', Register::charwise(text));
+    let (mut cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "@b");
+    while let Some((next, cmds)) = eng.step_replay(&buf, &mut cb, cur) {
+        cmds.iter().for_each(|cmd| buf.apply(cmd));
+        cur = next;
+    }
+    assert_eq!(buf.text(), "abc\nef\nhi");
+}
@@ -0,0 +1,361 @@
+use vim_mini::{Command, Engine, KeyCode, Mode, Position, Range};
+
+mod support;
+use support::feed::{ex, feed, key, pos, send};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+fn message(cmds: &[Command]) -> Option<&str> {
+    cmds.iter().find_map(|c| match c {
+        Command::Message(m) | Command::Error(m) => Some(m.as_str()),
+        _ => None,
+    })
+}
+
+fn prompt(cmds: &[Command]) -> Option<(Range, &str)> {
+    cmds.iter().find_map(|c| match c {
+        Command::ConfirmSubstitute { range, replacement } => Some((*range, replacement.as_str())),
+        _ => None,
+    })
+}
+
+/// Substitute `line` on a fresh buffer holding `text`, returning the result.
+fn sub(text: &str, cur: Position, line: &str) -> String {
+    let mut buf = MockBuffer::new(text);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    ex(&mut eng, &mut buf, &mut cb, cur, line);
+    buf.text()
+}
+
+#[test]
+fn first_match_on_cursor_line() {
+    assert_eq!(sub("foo foo\nfoo", pos(0, 0), "s/foo/bar/"), "bar foo\nfoo");
+}
+
+#[test]
+fn global_flag_replaces_every_match() {
+    assert_eq!(
+        sub("foo foo\nfoo", pos(0, 0), "s/foo/bar/g"),
+        "bar bar\nfoo"
+    );
+    assert_eq!(sub("foo foo\nfoo", pos(0, 0), "%s/o/0/g"), "f00 f00\nf00");
+}
+
+#[test]
+fn empty_matches_stop_at_the_end_of_the_line() {
+    assert_eq!(sub("abc", pos(0, 0), "s/x*/-/g"), "-a-b-c");
+    assert_eq!(sub("aaa", pos(0, 0), "s/a*/-/g"), "-");
+    // Not right after a match either
+    assert_eq!(sub("abc", pos(0, 0), "s/b*/-/g"), "-a-c");
+    assert_eq!(sub("", pos(0, 0), "s/x*/-/g"), "-");
+}
+
+#[test]
+fn range_and_count() {
+    let text = "a\na\na\na";
+    assert_eq!(sub(text, pos(0, 0), "2,3s/a/b/"), "a\nb\nb\na");
+    assert_eq!(sub(text, pos(0, 0), "s/a/b/ 2"), "b\nb\na\na");
+}
+
+#[test]
+fn groups_and_whole_match_in_replacement() {
+    assert_eq!(
+        sub("hello world", pos(0, 0), r"s/\(\w\+\) \(\w\+\)/\2 \1/"),
+        "world hello"
+    );
+    assert_eq!(sub("x = 1", pos(0, 0), r"s/\d/[&]/"), "x = [1]");
+    assert_eq!(sub("x = 1", pos(0, 0), r"s/\d/\&/"), "x = &");
+    assert_eq!(sub("a.b", pos(0, 0), r"s/\./\//"), "a/b");
+}
+
+#[test]
+fn pattern_items() {
+    assert_eq!(sub("  indented", pos(0, 0), "s/^ */>/"), ">indented");
+    assert_eq!(sub("end end", pos(0, 0), "s/end$/END/"), "end END");
+    assert_eq!(
+        sub("cat concat", pos(0, 0), r"s/\<cat\>/dog/g"),
+        "dog concat"
+    );
+    assert_eq!(
+        sub("colour color", pos(0, 0), "s/colou\\=r/hue/g"),
+        "hue hue"
+    );
+    assert_eq!(sub("ab12cd", pos(0, 0), "s/[0-9]\\+/#/"), "ab#cd");
+    assert_eq!(sub("one two", pos(0, 0), r"s/two\|one/x/g"), "x x");
+    assert_eq!(sub("aaaa", pos(0, 0), r"s/a\{2}/b/g"), "bb");
+    assert_eq!(sub("<a><b>", pos(0, 0), r"s/<.\{-}>/T/"), "T<b>");
+}
+
+#[test]
+fn repeats_on_a_long_line() {
+    let long = "a".repeat(100_000);
+    assert_eq!(sub(&long, pos(0, 0), "s/.*/x/"), "x");
+    assert_eq!(sub(&format!("{long}b"), pos(0, 0), "s/a*b/x/"), "x");
+    assert_eq!(sub(&format!("{long}b"), pos(0, 0), r"s/\(a\)\{-}b/x/"), "x");
+}
+
+#[test]
+fn nested_repeats_do_not_backtrack_forever() {
+    let mut buf = MockBuffer::new(&format!("{}b", "a".repeat(60)));
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, pos(0, 0), r"s/\(a\+\)\+c/X/");
+    assert_eq!(message(&cmds), Some(r"E486: Pattern not found: \(a\+\)\+c"));
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, pos(0, 0), r"s/\(a*\)*c/X/");
+    assert!(message(&cmds).is_some());
+    assert_eq!(sub(&"a".repeat(1000), pos(0, 0), r"s/\(a*\)*$/X/"), "X");
+}
+
+#[test]
+fn case_flags_and_modifiers() {
+    assert_eq!(sub("Foo foo", pos(0, 0), "s/foo/x/gi"), "x x");
+    assert_eq!(sub("Foo foo", pos(0, 0), r"s/\cFOO/x/"), "x foo");
+    assert_eq!(
+        sub("hello world", pos(0, 0), r"s/\w\+/\u&/g"),
+        "Hello World"
+    );
+    assert_eq!(
+        sub("hello world", pos(0, 0), r"s/hello/\U&\E!/"),
+        "HELLO! world"
+    );
+}
+
+#[test]
+fn line_break_in_replacement() {
+    let mut buf = MockBuffer::new("a,b,c\nz");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, r"s/,/\r/g");
+    assert_eq!(buf.text(), "a\nb\nc\nz");
+    assert_eq!(cur, pos(2, 0));
+}
+
+#[test]
+fn multi_line_substitution_moves_to_last_changed_line_and_reports() {
+    let mut buf = MockBuffer::new("x\n  x\nx\ny");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/x/z/");
+    assert_eq!(buf.text(), "z\n  z\nz\ny");
+    assert_eq!(cur, pos(2, 0));
+    assert_eq!(message(&cmds), Some("3 substitutions on 3 lines"));
+
+    let (cur, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "1,2s/z/x/");
+    assert_eq!(cur, pos(1, 2));
+    assert_eq!(message(&cmds), None);
+}
+
+#[test]
+fn unicode_columns_are_graphemes() {
+    assert_eq!(
+        sub("cafe\u{301} 👍 x", pos(0, 0), "s/x/y/"),
+        "cafe\u{301} 👍 y"
+    );
+    assert_eq!(sub("naïve naïve", pos(0, 0), "s/ï/i/g"), "naive naive");
+}
+
+#[test]
+fn count_only_flag_reports_matches() {
+    let mut buf = MockBuffer::new("a a\nb\na");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/a//gn");
+    assert_eq!(message(&cmds), Some("3 matches on 2 lines"));
+    assert_eq!(buf.text(), "a a\nb\na");
+}
+
+#[test]
+fn no_match_is_an_error_unless_e_flag() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/x/y/");
+    assert_eq!(
+        cmds,
+        vec![Command::Error("E486: Pattern not found: x".into())]
+    );
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/x/y/e");
+    assert!(cmds.is_empty());
+
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, r"s/\(x/y/");
+    assert_eq!(message(&cmds), Some(r"E54: Unmatched \("));
+
+    // A pattern item that is not supported is not taken as a literal
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, r"s/\vabc/y/e");
+    assert_eq!(message(&cmds), Some(r"E867: Unknown operator '\v'"));
+    let (_, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, r"s/\%d97/y/");
+    assert_eq!(message(&cmds), Some(r"E71: Invalid character after \%"));
+    assert_eq!(buf.text(), "abc");
+}
+
+#[test]
+fn repeat_without_pattern() {
+    let mut buf = MockBuffer::new("a a\na a\na a\na a");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/a/b/g");
+    assert_eq!(buf.text(), "b b\na a\na a\na a");
+
+    // ":&&" keeps the flags, ":s" and ":&" drop them
+    ex(&mut eng, &mut buf, &mut cb, pos(1, 0), "&&");
+    assert_eq!(buf.text(), "b b\nb b\na a\na a");
+    ex(&mut eng, &mut buf, &mut cb, pos(2, 0), "s");
+    assert_eq!(buf.text(), "b b\nb b\nb a\na a");
+
+    // Normal-mode "&" is ":s" on the cursor line
+    feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "&");
+    assert_eq!(buf.text(), "b b\nb b\nb a\nb a");
+}
+
+#[test]
+fn g_ampersand_repeats_on_all_lines_with_flags() {
+    let mut buf = MockBuffer::new("a a\na a\na a");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/a/b/g");
+    feed(&mut eng, &mut buf, &mut cb, Position::ZERO, "g&");
+    assert_eq!(buf.text(), "b b\nb b\nb b");
+}
+
+#[test]
+fn ampersand_can_be_dot_repeated() {
+    let mut buf = MockBuffer::new("a\na\na");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/a/b/");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "&j");
+    feed(&mut eng, &mut buf, &mut cb, cur, ".");
+    assert_eq!(buf.text(), "b\nb\nb");
+}
+
+#[test]
+fn tilde_is_the_previous_replacement() {
+    let mut buf = MockBuffer::new("a b");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/a/xy/");
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s/b/~z/");
+    assert_eq!(buf.text(), "xy xyz");
+}
+
+#[test]
+fn empty_pattern_uses_last_search() {
+    let mut buf = MockBuffer::new("a.b a.b");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, Position::ZERO, "/a.b\r");
+
+    // The search was literal, so '.' only matches itself
+    ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "s//X/g");
+    assert_eq!(buf.text(), "X X");
+}
+
+/// Answer the confirm prompt with `answers`, collecting the prompts shown.
+fn confirm(
+    eng: &mut Engine,
+    buf: &mut MockBuffer,
+    cb: &mut MockClipboard,
+    mut cur: Position,
+    answers: &str,
+) -> (Position, Vec<Command>) {
+    let mut last = Vec::new();
+    for c in answers.chars() {
+        assert_eq!(eng.snapshot().mode, Mode::Confirm);
+        (cur, last) = send(eng, buf, cb, cur, key(KeyCode::Char(c)));
+    }
+    (cur, last)
+}
+
+#[test]
+fn confirm_asks_for_each_match() {
+    let mut buf = MockBuffer::new("a a\nb\na");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/a/xx/gc");
+    assert_eq!(eng.snapshot().mode, Mode::Confirm);
+    assert_eq!(cur, pos(0, 0));
+    assert_eq!(
+        prompt(&cmds),
+        Some((
+            Range {
+                start: pos(0, 0),
+                end: pos(0, 1)
+            },
+            "xx"
+        ))
+    );
+
+    // Yes: the next prompt is on the same line, after the replacement
+    let (cur, cmds) = confirm(&mut eng, &mut buf, &mut cb, cur, "y");
+    assert_eq!(buf.text(), "xx a\nb\na");
+    assert_eq!(cur, pos(0, 3));
+    assert_eq!(prompt(&cmds).unwrap().0.start, pos(0, 3));
+
+    // No: skip to the next line with a match
+    let (cur, _) = confirm(&mut eng, &mut buf, &mut cb, cur, "n");
+    assert_eq!(cur, pos(2, 0));
+
+    let (cur, cmds) = confirm(&mut eng, &mut buf, &mut cb, cur, "y");
+    assert_eq!(buf.text(), "xx a\nb\nxx");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+    assert_eq!(cur, pos(2, 0));
+    assert_eq!(prompt(&cmds), None);
+}
+
+#[test]
+fn confirm_all_last_and_quit() {
+    let text = "a a\na\na";
+
+    let mut buf = MockBuffer::new(text);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/a/b/gc");
+    let (cur, cmds) = confirm(&mut eng, &mut buf, &mut cb, cur, "na");
+    assert_eq!(buf.text(), "a b\nb\nb");
+    assert_eq!(cur, pos(2, 0));
+    assert_eq!(message(&cmds), Some("3 substitutions on 3 lines"));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    let mut buf = MockBuffer::new(text);
+    let mut eng = Engine::new();
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/a/b/gc");
+    confirm(&mut eng, &mut buf, &mut cb, cur, "yl");
+    assert_eq!(buf.text(), "b b\na\na");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    let mut buf = MockBuffer::new(text);
+    let mut eng = Engine::new();
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, "%s/a/b/gc");
+    confirm(&mut eng, &mut buf, &mut cb, cur, "q");
+    assert_eq!(buf.text(), text);
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn confirm_follows_inserted_line_breaks() {
+    let mut buf = MockBuffer::new("a,b\nc,d");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = ex(&mut eng, &mut buf, &mut cb, Position::ZERO, r"%s/,/\r/c");
+    let (cur, cmds) = confirm(&mut eng, &mut buf, &mut cb, cur, "y");
+    assert_eq!(buf.text(), "a\nb\nc,d");
+    assert_eq!(cur, pos(2, 1));
+    assert_eq!(prompt(&cmds).unwrap().0.start, pos(2, 1));
+
+    confirm(&mut eng, &mut buf, &mut cb, cur, "y");
+    assert_eq!(buf.text(), "a\nb\nc\nd");
+}
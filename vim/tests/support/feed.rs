@@ -0,0 +1,160 @@
+use vim_mini::{
+    Command, Engine, InputEvent, KeyCode, KeyEvent, Mode, Modifiers, Position, Range, Selection,
+};
+
+use super::mock_buffer::MockBuffer;
+use super::mock_clipboard::MockClipboard;
+
+#[allow(dead_code)]
+pub fn pos(line: u32, col: u32) -> Position {
+    Position { line, col }
+}
+
+/// A key without modifiers.
+#[allow(dead_code)]
+pub fn key(code: KeyCode) -> InputEvent {
+    InputEvent::Key(KeyEvent {
+        code,
+        mods: Modifiers::empty(),
+    })
+}
+
+/// Send one event to the engine and apply the commands to the buffer.
+#[allow(dead_code)]
+pub fn send(
+    eng: &mut Engine,
+    buf: &mut MockBuffer,
+    cb: &mut MockClipboard,
+    cur: Position,
+    input: InputEvent,
+) -> (Position, Vec<Command>) {
+    let (next, cmds) = eng.handle_event(buf, cb, cur, input);
+    cmds.iter().for_each(|cmd| buf.apply(cmd));
+    (next, cmds)
+}
+
+/// Feed `keys`, applying the commands to the buffer. Returns the cursor and
+/// the commands of the last key.
+///
+/// Special keys are written as in Vim, `<Esc>`, `<CR>`, `<BS>`, `<Del>`,
+/// `<Tab>`, `<Insert>`, arrows, `<Home>`, `<End>`, `<PageUp>`,
+/// `<PageDown>` and `<F1>` to `<F24>`, with `C-` or `S-` for Ctrl or
+/// Shift (`<C-o>`, `<S-Left>`); `\x1b`, `\r`, `\x08` and `\x7f` are Esc,
+/// Enter, Backspace and Delete. Other keys are text in insert and replace
+/// mode and on the `:` and `/` line, and commands otherwise.
+#[allow(dead_code)]
+pub fn feed(
+    eng: &mut Engine,
+    buf: &mut MockBuffer,
+    cb: &mut MockClipboard,
+    mut cur: Position,
+    keys: &str,
+) -> (Position, Vec<Command>) {
+    let mut last = Vec::new();
+    let mut rest = keys;
+    while let Some(c) = rest.chars().next() {
+        let input = match named(rest) {
+            Some((input, len)) => {
+                rest = &rest[len..];
+                input
+            }
+            None => {
+                rest = &rest[c.len_utf8()..];
+                typed(eng.snapshot().mode, c)
+            }
+        };
+        (cur, last) = send(eng, buf, cb, cur, input);
+    }
+    (cur, last)
+}
+
+/// Type `:{line}<CR>`, applying the commands to the buffer.
+#[allow(dead_code)]
+pub fn ex(
+    eng: &mut Engine,
+    buf: &mut MockBuffer,
+    cb: &mut MockClipboard,
+    cur: Position,
+    line: &str,
+) -> (Position, Vec<Command>) {
+    let (mut cur, _) = send(eng, buf, cb, cur, key(KeyCode::Char(':')));
+    for c in line.chars() {
+        (cur, _) = send(eng, buf, cb, cur, InputEvent::ReceivedChar(c));
+    }
+    send(eng, buf, cb, cur, key(KeyCode::Enter))
+}
+
+/// Run `keys` at `cur` on a fresh engine and return the buffer text
+/// afterwards.
+#[allow(dead_code)]
+pub fn edit(text: &str, cur: Position, keys: &str) -> String {
+    let mut buf = MockBuffer::new(text);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+    feed(&mut eng, &mut buf, &mut clipboard, cur, keys);
+    buf.text()
+}
+
+/// The last selection set by `cmds`.
+#[allow(dead_code)]
+pub fn selection(cmds: &[Command]) -> Option<Selection> {
+    cmds.iter().rev().find_map(|cmd| match cmd {
+        Command::SetSelection(sel) => *sel,
+        _ => None,
+    })
+}
+
+/// The first range deleted by `cmds`.
+#[allow(dead_code)]
+pub fn delete_range(cmds: &[Command]) -> Option<Range> {
+    cmds.iter().find_map(|cmd| match cmd {
+        Command::Delete { range } => Some(*range),
+        _ => None,
+    })
+}
+
+/// The key named at the start of `keys`, such as `<Esc>` or `<C-o>`, and
+/// the length of its name.
+fn named(keys: &str) -> Option<(InputEvent, usize)> {
+    let (name, _) = keys.strip_prefix('<')?.split_once('>')?;
+    let (mods, base) = match name.split_once('-') {
+        Some(("C", base)) => (Modifiers::CTRL, base),
+        Some(("S", base)) => (Modifiers::SHIFT, base),
+        _ => (Modifiers::empty(), name),
+    };
+    let code = match base {
+        "Esc" => KeyCode::Esc,
+        "CR" => KeyCode::Enter,
+        "BS" => KeyCode::Backspace,
+        "Del" => KeyCode::Delete,
+        "Tab" => KeyCode::Tab,
+        "Insert" => KeyCode::Insert,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ if mods == Modifiers::CTRL && base.chars().count() == 1 => {
+            KeyCode::Char(base.chars().next()?)
+        }
+        _ => KeyCode::F(base.strip_prefix('F')?.parse().ok()?),
+    };
+    Some((InputEvent::Key(KeyEvent { code, mods }), name.len() + 2))
+}
+
+/// The event for `c` typed in `mode`.
+fn typed(mode: Mode, c: char) -> InputEvent {
+    match (c, mode) {
+        ('\x1b', _) => key(KeyCode::Esc),
+        ('\r', _) => key(KeyCode::Enter),
+        ('\x08', _) => key(KeyCode::Backspace),
+        ('\x7f', _) => key(KeyCode::Delete),
+        (c, Mode::Insert | Mode::Replace | Mode::SearchPrompt | Mode::CommandLine) => {
+            InputEvent::ReceivedChar(c)
+        }
+        (c, _) => key(KeyCode::Char(c)),
+    }
+}
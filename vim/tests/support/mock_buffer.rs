@@ -1,7 +1,7 @@
 use ropey::Rope;
 use unicode_segmentation::UnicodeSegmentation;
 use vim_mini::traits::TextOps;
-use vim_mini::types::{Position, Range};
+use vim_mini::types::{Command, Position, Range};
 
 pub struct MockBuffer {
     rope: Rope,
@@ -14,6 +14,43 @@ impl MockBuffer {
         }
     }
 
+    /// Returns the whole buffer contents.
+    #[allow(dead_code)]
+    pub fn text(&self) -> String {
+        self.rope.to_string()
+    }
+
+    /// Apply an edit command emitted by the engine.
+    #[allow(dead_code)]
+    pub fn apply(&mut self, cmd: &Command) {
+        match cmd {
+            Command::Delete { range } => {
+                let start = self.char_idx(range.start);
+                let end = self.char_idx(range.end);
+                self.rope.remove(start..end);
+            }
+            Command::InsertText { at, text } => {
+                let idx = self.char_idx(*at);
+                self.rope.insert(idx, text);
+            }
+            _ => {}
+        }
+    }
+
+    fn char_idx(&self, pos: Position) -> usize {
+        if pos.line as usize >= self.rope.len_lines() {
+            return self.rope.len_chars();
+        }
+        let line_start = self.rope.line_to_char(pos.line as usize);
+        let line = self.line_str(pos.line);
+        let offset: usize = line
+            .graphemes(true)
+            .take(pos.col as usize)
+            .map(|g| g.chars().count())
+            .sum();
+        line_start + offset
+    }
+
     fn line_str(&self, line: u32) -> String {
         if line as usize >= self.rope.len_lines() {
             return String::new();
@@ -1,2 +1,3 @@
+pub mod feed;
 pub mod mock_buffer;
 pub mod mock_clipboard;
@@ -0,0 +1,363 @@
+use vim_mini::{
+    Engine,
+    types::{Mode, Range, VisualKind},
+};
+
+mod support;
+use support::feed::{delete_range, edit, feed, pos, selection};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn diw_deletes_word_under_cursor() {
+    assert_eq!(edit("foo bar baz", pos(0, 5), "diw"), "foo  baz");
+}
+
+#[test]
+fn daw_takes_trailing_blanks() {
+    assert_eq!(edit("foo bar baz", pos(0, 5), "daw"), "foo baz");
+}
+
+#[test]
+fn daw_on_last_word_takes_leading_blanks() {
+    assert_eq!(edit("foo bar", pos(0, 5), "daw"), "foo");
+}
+
+#[test]
+fn daw_keeps_indent() {
+    assert_eq!(edit("    foo", pos(0, 5), "daw"), "    ");
+}
+
+#[test]
+fn diw_on_blanks_deletes_the_blanks() {
+    assert_eq!(edit("foo   bar", pos(0, 4), "diw"), "foobar");
+}
+
+#[test]
+fn daw_on_blanks_takes_following_word() {
+    assert_eq!(edit("foo   bar baz", pos(0, 4), "daw"), "foo baz");
+}
+
+#[test]
+fn iw_stops_at_punctuation_but_iw_big_does_not() {
+    assert_eq!(edit("call foo.bar(x)", pos(0, 6), "diw"), "call .bar(x)");
+    assert_eq!(edit("call foo.bar(x)", pos(0, 6), "diW"), "call ");
+}
+
+#[test]
+fn count_iw_counts_blanks_as_words() {
+    // "3iw" is "foo", " " and "bar"
+    assert_eq!(edit("foo bar baz", pos(0, 0), "d3iw"), " baz");
+}
+
+#[test]
+fn count_aw_deletes_several_words() {
+    assert_eq!(edit("one two three four", pos(0, 0), "2daw"), "three four");
+}
+
+#[test]
+fn yiw_yanks_and_moves_to_word_start() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 8), "yiw");
+    assert!(cmds.is_empty());
+    assert_eq!(cur, pos(0, 6));
+    assert_eq!(eng.registers().get('0').unwrap().text, "world");
+}
+
+#[test]
+fn ciw_changes_word_and_enters_insert() {
+    let mut buf = MockBuffer::new("hello world");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 2), "ciw");
+    assert_eq!(cur, pos(0, 0));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    assert_eq!(
+        delete_range(&cmds),
+        Some(Range {
+            start: pos(0, 0),
+            end: pos(0, 5),
+        })
+    );
+}
+
+#[test]
+fn dot_repeats_daw() {
+    let mut buf = MockBuffer::new("one two three");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 0), "daw");
+    feed(&mut eng, &mut buf, &mut clipboard, cur, ".");
+    assert_eq!(buf.text(), "three");
+}
+
+#[test]
+fn viw_selects_word() {
+    let mut buf = MockBuffer::new("foo bar baz");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 5), "viw");
+    assert_eq!(cur, pos(0, 6));
+    let sel = selection(&cmds).unwrap();
+    assert_eq!(sel.start, pos(0, 4));
+    assert_eq!(sel.end, pos(0, 6));
+}
+
+#[test]
+fn repeated_iw_extends_visual_selection() {
+    let mut buf = MockBuffer::new("foo bar baz");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    // "iw" again adds the blank, then the next word
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 0), "viwiwiw");
+    assert_eq!(cur, pos(0, 6));
+    let sel = selection(&cmds).unwrap();
+    assert_eq!(sel.start, pos(0, 0));
+    assert_eq!(sel.end, pos(0, 6));
+}
+
+#[test]
+fn visual_count_aw_selects_several_words() {
+    let mut buf = MockBuffer::new("one two three four");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 0), "v2aw");
+    assert_eq!(cur, pos(0, 7));
+}
+
+#[test]
+fn linewise_visual_iw_becomes_characterwise() {
+    let mut buf = MockBuffer::new("foo bar");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 1), "Viw");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::CharWise));
+    assert_eq!(selection(&cmds).unwrap().kind, VisualKind::CharWise);
+}
+
+#[test]
+fn diw_on_empty_line_does_nothing() {
+    assert_eq!(edit("foo\n\nbar", pos(1, 0), "diw"), "foo\n\nbar");
+}
+
+#[test]
+fn di_paren_deletes_inside_parens() {
+    assert_eq!(edit("foo(bar, baz)", pos(0, 5), "di("), "foo()");
+    assert_eq!(edit("foo(bar, baz)", pos(0, 5), "dib"), "foo()");
+    assert_eq!(edit("foo(bar, baz)", pos(0, 5), "di)"), "foo()");
+}
+
+#[test]
+fn da_paren_includes_parens() {
+    assert_eq!(edit("foo(bar) + 1", pos(0, 5), "da("), "foo + 1");
+}
+
+#[test]
+fn paren_object_from_opening_and_closing_paren() {
+    assert_eq!(edit("f(a) g(b)", pos(0, 1), "di("), "f() g(b)");
+    assert_eq!(edit("f(a) g(b)", pos(0, 3), "di("), "f() g(b)");
+}
+
+#[test]
+fn nested_parens_and_count() {
+    let text = "f(a, g(b, c), d)";
+    assert_eq!(edit(text, pos(0, 7), "di("), "f(a, g(), d)");
+    assert_eq!(edit(text, pos(0, 7), "d2i("), "f()");
+    assert_eq!(edit(text, pos(0, 7), "2di("), "f()");
+}
+
+#[test]
+fn bracket_brace_and_angle_objects() {
+    assert_eq!(edit("x[1, 2]", pos(0, 3), "di["), "x[]");
+    assert_eq!(edit("x = { a }", pos(0, 6), "diB"), "x = {}");
+    assert_eq!(edit("Vec<u8>", pos(0, 5), "da<"), "Vec");
+}
+
+#[test]
+fn di_paren_outside_parens_fails() {
+    let mut buf = MockBuffer::new("foo bar");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 2), "di(");
+    assert_eq!(cur, pos(0, 2));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn di_paren_on_empty_parens_does_nothing() {
+    assert_eq!(edit("f()", pos(0, 1), "di("), "f()");
+}
+
+#[test]
+fn ci_paren_on_empty_parens_inserts_between() {
+    let mut buf = MockBuffer::new("f()");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 1), "ci(");
+    assert_eq!(cur, pos(0, 2));
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+}
+
+#[test]
+fn di_brace_on_multiline_block_deletes_lines() {
+    let text = "fn x() {\n    foo;\n    bar;\n}\n";
+    assert_eq!(edit(text, pos(1, 6), "di{"), "fn x() {\n}\n");
+    assert_eq!(edit(text, pos(1, 6), "da{"), "fn x() \n");
+}
+
+#[test]
+fn ci_brace_on_multiline_block_keeps_indent() {
+    let mut buf = MockBuffer::new("if x {\n    foo;\n}");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(1, 5), "ci{");
+    assert_eq!(cur, pos(1, 4));
+    assert_eq!(
+        delete_range(&cmds),
+        Some(Range {
+            start: pos(1, 4),
+            end: pos(1, 8),
+        })
+    );
+}
+
+#[test]
+fn yank_inner_block_is_linewise_for_whole_lines() {
+    let mut buf = MockBuffer::new("{\n  a\n  b\n}");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    feed(&mut eng, &mut buf, &mut clipboard, pos(1, 2), "yi{");
+    let reg = eng.registers().get('0').unwrap();
+    assert_eq!(reg.text, "  a\n  b\n");
+    assert_eq!(reg.kind, vim_mini::RegisterKind::LineWise);
+}
+
+#[test]
+fn di_quote_inside_string() {
+    assert_eq!(
+        edit(r#"x = "hello" + y"#, pos(0, 6), "di\""),
+        r#"x = "" + y"#
+    );
+}
+
+#[test]
+fn da_quote_takes_trailing_blanks() {
+    assert_eq!(edit(r#"x = "hello" + y"#, pos(0, 6), "da\""), "x = + y");
+}
+
+#[test]
+fn da_quote_at_line_end_takes_leading_blanks() {
+    assert_eq!(edit(r#"x = "hello""#, pos(0, 6), "da\""), "x =");
+}
+
+#[test]
+fn quote_object_searches_forward_on_line() {
+    assert_eq!(edit(r#"print("hi")"#, pos(0, 0), "ci\""), r#"print("")"#);
+    assert_eq!(edit("say 'a' and 'b'", pos(0, 0), "di'"), "say '' and 'b'");
+}
+
+#[test]
+fn quote_object_on_closing_quote_pairs_from_line_start() {
+    assert_eq!(edit("'a' x 'b'", pos(0, 2), "di'"), "'' x 'b'");
+    // The quote at column 6 opens the second string
+    assert_eq!(edit("'a' x 'b'", pos(0, 6), "di'"), "'a' x ''");
+}
+
+#[test]
+fn quote_object_skips_escaped_quotes() {
+    assert_eq!(edit(r#"s = "a\"b" ;"#, pos(0, 5), "di\""), r#"s = "" ;"#);
+}
+
+#[test]
+fn backtick_object_and_count_includes_quotes() {
+    assert_eq!(edit("run `ls -l` now", pos(0, 6), "di`"), "run `` now");
+    assert_eq!(edit("run `ls -l` now", pos(0, 6), "d2i`"), "run  now");
+}
+
+#[test]
+fn quote_object_without_pair_fails() {
+    assert_eq!(edit("it's here", pos(0, 0), "di'"), "it's here");
+}
+
+#[test]
+fn vi_quote_then_i_quote_includes_quotes() {
+    let mut buf = MockBuffer::new(r#"a "xyz" b"#);
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 4), "vi\"");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 3), pos(0, 5)));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 5), "i\"");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 2), pos(0, 6)));
+}
+
+#[test]
+fn dit_and_dat_on_tags() {
+    let text = "<div><b>bold</b> text</div>";
+    assert_eq!(edit(text, pos(0, 9), "dit"), "<div><b></b> text</div>");
+    assert_eq!(edit(text, pos(0, 9), "dat"), "<div> text</div>");
+    assert_eq!(edit(text, pos(0, 9), "d2it"), "<div></div>");
+}
+
+#[test]
+fn tag_object_from_start_tag_and_case_insensitive() {
+    assert_eq!(
+        edit("<P>one</p> <p>two</p>", pos(0, 1), "dit"),
+        "<P></p> <p>two</p>"
+    );
+}
+
+#[test]
+fn tag_object_skips_unclosed_and_self_closing_tags() {
+    let text = "<p>a<br>b<img src=x/>c</p>";
+    assert_eq!(edit(text, pos(0, 9), "dit"), "<p></p>");
+}
+
+#[test]
+fn dit_on_multiline_element_deletes_lines() {
+    let text = "<ul>\n  <li>x</li>\n</ul>\n";
+    assert_eq!(edit(text, pos(1, 2), "2dit"), "<ul>\n</ul>\n");
+}
+
+#[test]
+fn repeated_it_in_visual_mode_grows_selection() {
+    let mut buf = MockBuffer::new("<a><b>xy</b></a>");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 6), "vit");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 6), pos(0, 7)));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "it");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 3), pos(0, 11)));
+}
+
+#[test]
+fn repeated_i_paren_in_visual_mode_selects_enclosing_block() {
+    let mut buf = MockBuffer::new("f(a, g(bc))");
+    let mut eng = Engine::new();
+    let mut clipboard = MockClipboard::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut clipboard, pos(0, 7), "vi(");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 7), pos(0, 8)));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut clipboard, cur, "i(");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 2), pos(0, 9)));
+}
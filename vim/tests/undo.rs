@@ -0,0 +1,208 @@
+#![cfg(feature = "undo")]
+
+use vim_mini::{Command, Engine};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn u_undoes_and_ctrl_r_redoes() {
+    let mut buf = MockBuffer::new("one\ntwo\nthree");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 1), "x");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "dd");
+    assert_eq!(buf.text(), "to\nthree");
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "one\nto\nthree");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "one\ntwo\nthree");
+    assert_eq!(cur, pos(1, 1));
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-r>");
+    assert_eq!(buf.text(), "one\nto\nthree");
+    assert_eq!(cur, pos(1, 1));
+    feed(&mut eng, &mut buf, &mut cb, cur, "<C-r>");
+    assert_eq!(buf.text(), "to\nthree");
+}
+
+#[test]
+fn an_insert_session_is_one_step() {
+    let mut buf = MockBuffer::new("one two three");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "cwTWO\nx\x1b");
+    assert_eq!(buf.text(), "one TWO\nx three");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "one two three");
+    assert_eq!(cur, pos(0, 4));
+
+    feed(&mut eng, &mut buf, &mut cb, cur, "<C-r>");
+    assert_eq!(buf.text(), "one TWO\nx three");
+}
+
+#[test]
+fn counts_undo_and_redo_several_changes() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "xxx");
+    assert_eq!(buf.text(), "def");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2u");
+    assert_eq!(buf.text(), "bcdef");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "9u");
+    assert_eq!(buf.text(), "abcdef");
+    feed(&mut eng, &mut buf, &mut cb, cur, "2<C-r>");
+    assert_eq!(buf.text(), "cdef");
+}
+
+#[test]
+fn messages_at_either_end() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "u");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(
+        cmds,
+        vec![Command::Message("Already at oldest change".into())]
+    );
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-r>");
+    assert_eq!(
+        cmds,
+        vec![Command::Message("Already at newest change".into())]
+    );
+}
+
+#[test]
+fn a_new_change_drops_the_redo_steps() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "xu");
+    assert!(eng.undo_history().can_redo());
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "x");
+    assert!(!eng.undo_history().can_redo());
+    assert_eq!(buf.text(), "ab");
+}
+
+#[test]
+fn undo_is_not_repeated_by_dot() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "xu");
+    assert_eq!(buf.text(), "abcdef");
+    feed(&mut eng, &mut buf, &mut cb, cur, ".");
+    assert_eq!(buf.text(), "bcdef");
+
+    // The repeat is a change of its own
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "u");
+    assert_eq!(buf.text(), "abcdef");
+}
+
+#[test]
+fn substitute_on_several_lines_is_one_step() {
+    let mut buf = MockBuffer::new("a a\nb\na");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), ":%s/a/xy/g\r");
+    assert_eq!(buf.text(), "xy xy\nb\nxy");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "u");
+    assert_eq!(buf.text(), "a a\nb\na");
+    assert_eq!(cur, pos(1, 0));
+    feed(&mut eng, &mut buf, &mut cb, cur, "<C-r>");
+    assert_eq!(buf.text(), "xy xy\nb\nxy");
+}
+
+#[test]
+fn confirmed_substitutions_are_one_step() {
+    let mut buf = MockBuffer::new("a\na\na");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), ":%s/a/b/c\ryny");
+    assert_eq!(buf.text(), "b\na\nb");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "u");
+    assert_eq!(buf.text(), "a\na\na");
+}
+
+#[test]
+fn line_and_put_changes_restore_the_text() {
+    let mut buf = MockBuffer::new("one\ntwo\nthree");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ddp");
+    assert_eq!(buf.text(), "two\none\nthree");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "u");
+    assert_eq!(buf.text(), "two\nthree");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "u");
+    assert_eq!(buf.text(), "one\ntwo\nthree");
+}
+
+#[test]
+fn capital_u_restores_the_last_changed_line() {
+    let mut buf = MockBuffer::new("hello\nworld");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "xx");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "iXY\x1b");
+    assert_eq!(buf.text(), "hello\nrlXYd");
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 4), "U");
+    assert_eq!(buf.text(), "hello\nworld");
+    assert_eq!(cur, pos(1, 0));
+
+    // U is a change itself: U again brings the changes back, u undoes it
+    feed(&mut eng, &mut buf, &mut cb, cur, "U");
+    assert_eq!(buf.text(), "hello\nrlXYd");
+    feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "hello\nworld");
+}
+
+#[test]
+fn capital_u_only_covers_the_line_changed_last() {
+    let mut buf = MockBuffer::new("abc\ndef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "x");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "x");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "U");
+    assert_eq!(buf.text(), "bc\ndef");
+
+    // Deleting lines leaves nothing for U
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "dd");
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "U");
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn host_can_clear_the_history() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "x");
+    assert!(eng.undo_history().can_undo());
+    eng.undo_history_mut().clear();
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "u");
+    assert_eq!(
+        cmds,
+        vec![Command::Message("Already at oldest change".into())]
+    );
+    assert_eq!(buf.text(), "bc");
+}
@@ -0,0 +1,221 @@
+use vim_mini::{Command, Engine, TextOps, Viewport};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+/// Lines "  0" to "  {n - 1}", each indented by two spaces.
+fn numbered(n: u32) -> MockBuffer {
+    let lines: Vec<String> = (0..n).map(|i| format!("  {i}")).collect();
+    MockBuffer::new(&lines.join("\n"))
+}
+
+fn engine(top: u32, height: u32) -> Engine {
+    let mut eng = Engine::new();
+    eng.set_viewport(Viewport { top, height });
+    eng
+}
+
+#[test]
+fn h_m_l_go_to_lines_on_screen() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(10, 20);
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(15, 0), "H");
+    assert_eq!(cur, pos(10, 2));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(10, 2))]);
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "L");
+    assert_eq!(cur, pos(29, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "M");
+    assert_eq!(cur, pos(19, 2));
+    // A count counts lines from the top or the bottom
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3H");
+    assert_eq!(cur, pos(12, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "3L");
+    assert_eq!(cur, pos(27, 2));
+    // H and L are jumps
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "''");
+    assert_eq!(cur, pos(12, 2));
+}
+
+#[test]
+fn screen_lines_past_the_end_of_the_text() {
+    let mut buf = numbered(5);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 20);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "L");
+    assert_eq!(cur, pos(4, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "M");
+    assert_eq!(cur, pos(2, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "9H");
+    assert_eq!(cur, pos(4, 2));
+}
+
+#[test]
+fn operators_with_h_m_l_are_linewise() {
+    let mut buf = numbered(10);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(2, 5);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(4, 1), "dL");
+    assert_eq!(buf.text(), "  0\n  1\n  2\n  3\n  7\n  8\n  9");
+    assert_eq!(cur, pos(4, 0));
+    feed(&mut eng, &mut buf, &mut cb, pos(4, 0), "dH");
+    assert_eq!(buf.text(), "  0\n  1\n  8\n  9");
+}
+
+#[test]
+fn ctrl_d_and_ctrl_u_scroll_half_a_screen() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 20);
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(3, 0), "<C-d>");
+    assert_eq!(cur, pos(13, 2));
+    assert_eq!(
+        cmds,
+        vec![Command::ScrollTo(10), Command::SetCursor(pos(13, 2))]
+    );
+    assert_eq!(
+        eng.viewport(),
+        Some(Viewport {
+            top: 10,
+            height: 20
+        })
+    );
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-u>");
+    assert_eq!(cur, pos(3, 2));
+    assert_eq!(eng.viewport().unwrap().top, 0);
+
+    // A count sets the number of lines for the next ones too
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "4<C-d>");
+    assert_eq!(cur, pos(7, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-d>");
+    assert_eq!(cur, pos(11, 2));
+    assert_eq!(eng.viewport().unwrap().top, 8);
+}
+
+#[test]
+fn ctrl_d_stops_scrolling_at_the_end() {
+    let mut buf = numbered(30);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(10, 20);
+
+    // The last line is on screen: only the cursor moves
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(20, 0), "<C-d>");
+    assert_eq!(cur, pos(29, 2));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(29, 2))]);
+    // On the last line it fails
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-d>");
+    assert_eq!(cur, pos(29, 2));
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn ctrl_f_and_ctrl_b_scroll_a_screen() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 20);
+
+    // Two lines of the old screen stay on the new one
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(5, 0), "<C-f>");
+    assert_eq!(cur, pos(18, 2));
+    assert_eq!(
+        cmds,
+        vec![Command::ScrollTo(18), Command::SetCursor(pos(18, 2))]
+    );
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2<C-f>");
+    assert_eq!(cur, pos(54, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-b>");
+    assert_eq!(eng.viewport().unwrap().top, 36);
+    assert_eq!(cur, pos(54, 2));
+    // Back up far enough, the cursor goes to the last line on screen
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "9<C-b>");
+    assert_eq!(cur, pos(19, 2));
+    // Nothing above the first line
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-b>");
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn ctrl_e_and_ctrl_y_scroll_lines_and_push_the_cursor() {
+    let mut buf = MockBuffer::new("abcd\nabcd\nx\nabcd\nabcd\nabcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 3);
+
+    // The cursor stays while it is on screen
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "<C-e>");
+    assert_eq!(cur, pos(2, 0));
+    assert_eq!(
+        cmds,
+        vec![Command::ScrollTo(1), Command::SetCursor(pos(2, 0))]
+    );
+    // and otherwise keeps its column like 'j'
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 3), "2<C-e>");
+    assert_eq!(cur, pos(3, 3));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(5, 3), "3<C-y>");
+    assert_eq!(eng.viewport().unwrap().top, 0);
+    assert_eq!(cur, pos(2, 0));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "<C-y>");
+    assert!(cmds.is_empty());
+}
+
+#[test]
+fn z_commands_put_the_line_on_screen() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 21);
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(50, 1), "zt");
+    assert_eq!(
+        cmds,
+        vec![Command::ScrollTo(50), Command::SetCursor(pos(50, 1))]
+    );
+    assert_eq!(cur, pos(50, 1));
+    feed(&mut eng, &mut buf, &mut cb, cur, "zz");
+    assert_eq!(eng.viewport().unwrap().top, 40);
+    feed(&mut eng, &mut buf, &mut cb, cur, "zb");
+    assert_eq!(eng.viewport().unwrap().top, 30);
+    // With a count the line of that number, keeping the column
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "10zt");
+    assert_eq!(cur, pos(9, 1));
+    assert_eq!(eng.viewport().unwrap().top, 9);
+    feed(&mut eng, &mut buf, &mut cb, cur, "zb");
+    assert_eq!(eng.viewport().unwrap().top, 0);
+}
+
+#[test]
+fn scrolling_in_visual_mode_extends_the_selection() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = engine(0, 10);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "V<C-d>");
+    assert_eq!(cur, pos(5, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "L");
+    assert_eq!(cur, pos(14, 2));
+    feed(&mut eng, &mut buf, &mut cb, cur, "d");
+    assert_eq!(buf.line_count(), 85);
+}
+
+#[test]
+fn page_keys_and_screen_motions_need_a_viewport() {
+    let mut buf = numbered(100);
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    for keys in ["H", "M", "L", "<C-d>", "<C-f>", "<C-e>", "zz"] {
+        let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(3, 0), keys);
+        assert_eq!(cur, pos(3, 0), "{keys}");
+        assert!(cmds.is_empty(), "{keys}");
+    }
+    assert_eq!(eng.viewport(), None);
+
+    // Page Down is Ctrl-F
+    eng.set_viewport(Viewport { top: 0, height: 20 });
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<PageDown>");
+    assert_eq!(cur, pos(18, 2));
+}
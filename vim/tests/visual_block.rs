@@ -0,0 +1,211 @@
+use vim_mini::{Command, Engine, Mode, Register, RegisterKind, Selection, VisualKind};
+
+mod support;
+use support::feed::{feed, pos, selection};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn ctrl_v_selects_a_rectangle_of_screen_columns() {
+    let mut buf = MockBuffer::new("\tab\nxyz");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::Block));
+    assert_eq!(
+        selection(&cmds),
+        Some(Selection {
+            start: pos(0, 8),
+            end: pos(0, 8),
+            kind: VisualKind::Block,
+        })
+    );
+    // The tab takes the screen columns up to 8
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "j");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 1), pos(1, 8)));
+    // '$' reaches the end of every line
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "$");
+    assert_eq!(selection(&cmds).unwrap().end, pos(1, u32::MAX));
+}
+
+#[test]
+fn ctrl_v_switches_between_visual_kinds() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "v<C-v>");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::Block));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "V");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::LineWise));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>");
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+    assert_eq!(cmds, vec![Command::SetSelection(None)]);
+}
+
+#[test]
+fn delete_and_yank_a_block() {
+    let mut buf = MockBuffer::new("abcd\nefgh\nijkl");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jly");
+    assert_eq!(cur, pos(0, 1));
+    let reg = eng.registers().get('"').unwrap();
+    assert_eq!(reg.text, "bc\nfg");
+    assert_eq!(reg.kind, RegisterKind::Block);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 2), "<C-v>jd");
+    assert_eq!(buf.text(), "abcd\nefh\nijl");
+    assert_eq!(cur, pos(1, 2));
+    assert_eq!(eng.registers().get('"').unwrap().text, "g\nk");
+}
+
+#[test]
+fn short_lines_are_skipped() {
+    let mut buf = MockBuffer::new("abcdef\nab\nabcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "<C-v>jjld");
+    assert_eq!(buf.text(), "abcf\nab\nabcf");
+    assert_eq!(eng.registers().get('"').unwrap().text, "de\n\nde");
+}
+
+#[test]
+fn dollar_extends_every_line_to_its_end() {
+    let mut buf = MockBuffer::new("ab\nabcd\nabc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>$jjd");
+    assert_eq!(buf.text(), "a\na\na");
+
+    let mut buf = MockBuffer::new("ab\nabcd\nabc");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jj$A;\x1b");
+    assert_eq!(buf.text(), "ab;\nabcd;\nabc;");
+}
+
+#[test]
+fn wide_graphemes_and_tabs_cut_by_the_block_become_spaces() {
+    let mut buf = MockBuffer::new("abcd\na中b");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // 中 takes screen columns 1 and 2; the block covers columns 2 and 3
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "<C-v>jd");
+    assert_eq!(buf.text(), "ab\na ");
+    assert_eq!(eng.registers().get('"').unwrap().text, "cd\n b");
+
+    let mut buf = MockBuffer::new("abcdefghij\n\tx");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "<C-v>jd");
+    assert_eq!(buf.text(), "abj\n  ");
+}
+
+#[test]
+fn block_insert_copies_the_text_to_every_line() {
+    let mut buf = MockBuffer::new("abc\nd\nabc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jjIxy\x1b");
+    // The short line does not reach the block
+    assert_eq!(buf.text(), "axybc\nd\naxybc");
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    // Repeating inserts into a block of the same size at the cursor
+    let mut buf = MockBuffer::new("abc\nabc\nabc");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>jI-\x1b");
+    assert_eq!(buf.text(), "-abc\n-abc\nabc");
+    feed(&mut eng, &mut buf, &mut cb, pos(1, 2), ".");
+    assert_eq!(buf.text(), "-abc\n-a-bc\nab-c");
+}
+
+#[test]
+fn block_append_pads_short_lines() {
+    let mut buf = MockBuffer::new("ab\na\nab");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jjAX\x1b");
+    assert_eq!(buf.text(), "abX\na X\nabX");
+}
+
+#[test]
+fn block_change_replaces_every_line() {
+    let mut buf = MockBuffer::new("abcd\nabcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jlc");
+    assert_eq!(eng.snapshot().mode, Mode::Insert);
+    assert_eq!(cur, pos(0, 1));
+    feed(&mut eng, &mut buf, &mut cb, cur, "XY\x1b");
+    assert_eq!(buf.text(), "aXYd\naXYd");
+    assert_eq!(eng.registers().get('"').unwrap().text, "bc\nbc");
+}
+
+#[test]
+fn block_replace_fills_every_column() {
+    let mut buf = MockBuffer::new("abcd\nab\nabcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jjlrx");
+    assert_eq!(buf.text(), "axxd\nax\naxxd");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn block_registers_paste_as_blocks() {
+    let mut buf = MockBuffer::new("123\n456");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    eng.registers_mut().set('"', Register::blockwise("x\nyy"));
+
+    // Rows with text after them are padded to the block width
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "p");
+    assert_eq!(buf.text(), "1x 23\n4yy56");
+    assert_eq!(cur, pos(0, 1));
+
+    // Lines past the end of the buffer are added, indented to the column
+    let mut buf = MockBuffer::new("abcd\nxy\nefgh");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>jly");
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 3), "p");
+    assert_eq!(buf.text(), "abcd\nxy\nefghab\n    xy");
+}
+
+#[test]
+fn put_over_a_block() {
+    let mut buf = MockBuffer::new("abcd\nabcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    eng.registers_mut().set('a', Register::charwise("X"));
+
+    // One line of text goes on each line of the block
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jl\"ap");
+    assert_eq!(buf.text(), "aXd\naXd");
+    assert_eq!(eng.registers().get('"').unwrap().text, "bc\nbc");
+
+    // A block replaces a block
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>jp");
+    assert_eq!(buf.text(), "bcXd\nbcXd");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[cfg(feature = "undo")]
+#[test]
+fn undo_removes_a_block_insert_at_once() {
+    let mut buf = MockBuffer::new("abc\nabc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "<C-v>jI--\x1b");
+    assert_eq!(buf.text(), "--abc\n--abc");
+    feed(&mut eng, &mut buf, &mut cb, cur, "u");
+    assert_eq!(buf.text(), "abc\nabc");
+}
@@ -0,0 +1,228 @@
+use vim_mini::{Engine, Mode, Register, RegisterKind, VisualKind};
+
+mod support;
+use support::feed::{feed, pos, selection};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn o_moves_to_the_other_end() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vll");
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "o");
+    assert_eq!(cur, pos(0, 1));
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 1), pos(0, 3)));
+    // The anchor is now the old cursor
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "hd");
+    assert_eq!(cur, pos(0, 0));
+    assert_eq!(buf.text(), "ef");
+}
+
+#[test]
+fn capital_o_moves_to_the_other_corner_of_a_block() {
+    let mut buf = MockBuffer::new("abcd\nabcd");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "<C-v>jl");
+    assert_eq!(cur, pos(1, 2));
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "O");
+    assert_eq!(cur, pos(1, 1));
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 1), pos(1, 2)));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "o");
+    assert_eq!(cur, pos(0, 2));
+}
+
+#[test]
+fn gv_reselects_the_last_area() {
+    let mut buf = MockBuffer::new("abcdef\nghijkl");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "vh\x1b");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "jgv");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::CharWise));
+    // The cursor goes back to the end it was on
+    assert_eq!(cur, pos(0, 2));
+    feed(&mut eng, &mut buf, &mut cb, cur, "d");
+    assert_eq!(buf.text(), "abef\nghijkl");
+
+    // Line and block areas come back with their kind
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(1, 0), "V\x1b");
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "kgv");
+    assert_eq!(eng.snapshot().mode, Mode::Visual(VisualKind::LineWise));
+    assert_eq!(selection(&cmds).unwrap().kind, VisualKind::LineWise);
+}
+
+#[test]
+fn gv_in_visual_mode_exchanges_the_areas() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "vl\x1b");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "$vh");
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "gv");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 0), pos(0, 1)));
+    let (_, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "gv");
+    let sel = selection(&cmds).unwrap();
+    assert_eq!((sel.start, sel.end), (pos(0, 4), pos(0, 5)));
+}
+
+#[test]
+fn gv_without_a_previous_area_does_nothing() {
+    let mut buf = MockBuffer::new("abc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "gv");
+    assert_eq!(cur, pos(0, 1));
+    assert!(cmds.is_empty());
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+}
+
+#[test]
+fn c_and_s_change_the_area() {
+    let mut buf = MockBuffer::new("abcdef");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vlsX\x1b");
+    assert_eq!(buf.text(), "aXdef");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vlcY\x1b");
+    assert_eq!(buf.text(), "aYef");
+    assert_eq!(eng.registers().get('"').unwrap().text, "Xd");
+}
+
+#[test]
+fn r_replaces_every_character_but_line_breaks() {
+    let mut buf = MockBuffer::new("abc\ndef\nghi");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vjrx");
+    assert_eq!(buf.text(), "axx\nxxf\nghi");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "Vr-");
+    assert_eq!(buf.text(), "axx\nxxf\n---");
+}
+
+#[test]
+fn capital_j_joins_the_lines() {
+    let mut buf = MockBuffer::new("one\n    two\nthree\nfour");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "vjjJ");
+    assert_eq!(buf.text(), "one two three\nfour");
+    assert_eq!(cur, pos(0, 7));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    // A single line is joined with the next one
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "VJ");
+    assert_eq!(buf.text(), "one two three four");
+}
+
+#[test]
+fn case_commands() {
+    let mut buf = MockBuffer::new("Hello World");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "vll~");
+    assert_eq!(buf.text(), "hELlo World");
+    assert_eq!(cur, pos(0, 0));
+    feed(&mut eng, &mut buf, &mut cb, cur, "VU");
+    assert_eq!(buf.text(), "HELLO WORLD");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "v$u");
+    assert_eq!(buf.text(), "HELLO world");
+
+    // Case mappings may change the length
+    let mut buf = MockBuffer::new("straße");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "v$U");
+    assert_eq!(buf.text(), "STRASSE");
+}
+
+#[test]
+fn shift_lines_with_a_count() {
+    let mut buf = MockBuffer::new("a\n\nb");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Vjj>");
+    // Empty lines are left alone
+    assert_eq!(buf.text(), "\ta\n\n\tb");
+    assert_eq!(cur, pos(0, 1));
+    assert_eq!(eng.snapshot().mode, Mode::Normal);
+
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 1), "v2>");
+    assert_eq!(buf.text(), "\ta\n\n\t\t\tb");
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "vk5<");
+    assert_eq!(buf.text(), "\ta\n\nb");
+}
+
+#[test]
+fn put_replaces_a_charwise_area() {
+    let mut buf = MockBuffer::new("one two");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    eng.registers_mut().set('"', Register::charwise("six"));
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 4), "v$p");
+    assert_eq!(buf.text(), "one six");
+    assert_eq!(cur, pos(0, 6));
+    // The replaced text goes to the unnamed register
+    let reg = eng.registers().get('"').unwrap();
+    assert_eq!(
+        (reg.text.as_str(), reg.kind),
+        ("two", RegisterKind::CharWise)
+    );
+
+    // Lines put over characters split the line around them
+    eng.registers_mut().set('a', Register::linewise("new\n"));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "v\"ap");
+    assert_eq!(buf.text(), "one\nnew\nsix");
+    assert_eq!(cur, pos(1, 0));
+}
+
+#[test]
+fn put_replaces_a_linewise_area() {
+    let mut buf = MockBuffer::new("a\nb\nc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+    eng.registers_mut().set('a', Register::linewise("x\n"));
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "Vj\"a2p");
+    assert_eq!(buf.text(), "x\nx\nc");
+    let reg = eng.registers().get('"').unwrap();
+    assert_eq!(
+        (reg.text.as_str(), reg.kind),
+        ("a\nb\n", RegisterKind::LineWise)
+    );
+
+    // Characters put over lines become a line of their own
+    eng.registers_mut().set('a', Register::charwise("y"));
+    feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "V\"ap");
+    assert_eq!(buf.text(), "x\nx\ny");
+}
+
+#[test]
+fn capital_p_keeps_the_register() {
+    let mut buf = MockBuffer::new("aa bb cc");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "yiw");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 3), "viwP");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "viwP");
+    assert_eq!(buf.text(), "aa aa aa");
+    assert_eq!(eng.registers().get('"').unwrap().text, "aa");
+}
@@ -0,0 +1,140 @@
+use vim_mini::{Command, Engine, TextOps};
+
+mod support;
+use support::feed::{feed, pos};
+use support::mock_buffer::MockBuffer;
+use support::mock_clipboard::MockClipboard;
+
+#[test]
+fn default_methods_follow_vim_words() {
+    let buf = MockBuffer::new("foo.bar baz\n\nqux");
+
+    assert_eq!(buf.next_word_end(pos(0, 0), 1), pos(0, 2));
+    assert_eq!(buf.next_word_end(pos(0, 0), 3), pos(0, 6));
+    assert_eq!(buf.next_bigword_end(pos(0, 0), 1), pos(0, 6));
+    assert_eq!(buf.prev_word_end(pos(2, 1), 1), pos(1, 0));
+    assert_eq!(buf.prev_bigword_end(pos(0, 10), 1), pos(0, 6));
+    assert_eq!(buf.next_bigword_start(pos(0, 0), 1), pos(0, 8));
+    assert_eq!(buf.prev_bigword_start(pos(0, 8), 1), pos(0, 0));
+    // After the last WORD: the end of its line
+    assert_eq!(buf.next_bigword_start(pos(2, 0), 1), pos(2, 3));
+}
+
+#[test]
+fn e_moves_to_the_end_of_words() {
+    let mut buf = MockBuffer::new("foo bar.baz\n\n  qux");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "e");
+    assert_eq!(cur, pos(0, 2));
+    // Punctuation is a word of its own
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2e");
+    assert_eq!(cur, pos(0, 7));
+    // Empty lines are skipped
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "2e");
+    assert_eq!(cur, pos(2, 4));
+    // Nothing after the last word
+    let (cur, cmds) = feed(&mut eng, &mut buf, &mut cb, cur, "e");
+    assert_eq!(cur, pos(2, 4));
+    assert_eq!(cmds, vec![Command::SetCursor(pos(2, 4))]);
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "E");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "E");
+    assert_eq!(cur, pos(0, 10));
+}
+
+#[test]
+fn ge_moves_to_the_end_of_the_previous_word() {
+    let mut buf = MockBuffer::new("foo c.d\n\nbar");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    // An empty line counts as a word
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 1), "ge");
+    assert_eq!(cur, pos(1, 0));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "ge");
+    assert_eq!(cur, pos(0, 6));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "ge");
+    assert_eq!(cur, pos(0, 5));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "gE");
+    assert_eq!(cur, pos(0, 2));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(2, 0), "3ge");
+    assert_eq!(cur, pos(0, 5));
+}
+
+#[test]
+fn big_word_motions_skip_punctuation() {
+    let mut buf = MockBuffer::new("a.b c-d  e");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "W");
+    assert_eq!(cur, pos(0, 4));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "W");
+    assert_eq!(cur, pos(0, 9));
+    // The last WORD: the cursor stays on its last character
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "W");
+    assert_eq!(cur, pos(0, 9));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, cur, "B");
+    assert_eq!(cur, pos(0, 4));
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 9), "2B");
+    assert_eq!(cur, pos(0, 0));
+}
+
+#[test]
+fn operators_with_e_are_inclusive() {
+    let mut buf = MockBuffer::new("foo bar baz");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "de");
+    assert_eq!(buf.text(), " bar baz");
+    assert_eq!(cur, pos(0, 0));
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "d2e");
+    assert_eq!(buf.text(), "");
+
+    let mut buf = MockBuffer::new("a.b c");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "dE");
+    assert_eq!(buf.text(), " c");
+
+    // ge takes the character under the cursor too
+    let mut buf = MockBuffer::new("foo bar");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 5), "dge");
+    assert_eq!(buf.text(), "for");
+    assert_eq!(cur, pos(0, 2));
+}
+
+#[test]
+fn operators_with_big_words() {
+    let mut buf = MockBuffer::new("a.b c.d e");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "dW");
+    assert_eq!(buf.text(), "c.d e");
+    // On a WORD "cW" changes to its end, like "cE"
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "cWx\x1b");
+    assert_eq!(buf.text(), "x e");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 2), "dB");
+    assert_eq!(buf.text(), "e");
+}
+
+#[test]
+fn word_end_motions_in_visual_mode() {
+    let mut buf = MockBuffer::new("foo bar.baz");
+    let mut cb = MockClipboard::new();
+    let mut eng = Engine::new();
+
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 0), "ved");
+    assert_eq!(buf.text(), " bar.baz");
+    feed(&mut eng, &mut buf, &mut cb, pos(0, 1), "vEd");
+    assert_eq!(buf.text(), " ");
+
+    let mut buf = MockBuffer::new("foo bar");
+    let (cur, _) = feed(&mut eng, &mut buf, &mut cb, pos(0, 6), "vge");
+    assert_eq!(cur, pos(0, 2));
+    feed(&mut eng, &mut buf, &mut cb, cur, "d");
+    assert_eq!(buf.text(), "fo");
+}
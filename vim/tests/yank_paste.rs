@@ -1,18 +1,16 @@
 use vim_mini::{
-    Engine, InputEvent, KeyCode, KeyEvent,
+    Engine, KeyCode,
     traits::Clipboard,
     types::{Command, Position},
 };
 
 mod support;
+use support::feed::key;
 use support::mock_buffer::MockBuffer;
 use support::mock_clipboard::MockClipboard;
 
-fn key(c: char) -> InputEvent {
-    InputEvent::Key(KeyEvent {
-        code: KeyCode::Char(c),
-        mods: vim_mini::key::Modifiers::empty(),
-    })
+fn unnamed(eng: &Engine) -> Option<String> {
+    eng.registers().get('"').map(|r| r.text.clone())
 }
 
 #[test]
@@ -23,13 +21,13 @@ fn test_yy_yanks_line() {
     let cur = Position { line: 1, col: 0 };
 
     // yy on second line
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
     assert_eq!(cmds.len(), 0); // No commands on first 'y'
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
     assert_eq!(cmds.len(), 0); // yy doesn't emit commands, just copies
 
     // Check clipboard content
-    assert_eq!(clipboard.get(), Some("line two\n".to_string()));
+    assert_eq!(unnamed(&eng), Some("line two\n".to_string()));
 }
 
 #[test]
@@ -40,11 +38,11 @@ fn test_2yy_yanks_two_lines() {
     let cur = Position { line: 0, col: 0 };
 
     // 2yy yanks 2 lines
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('2'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('2')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
 
-    assert_eq!(clipboard.get(), Some("line one\nline two\n".to_string()));
+    assert_eq!(unnamed(&eng), Some("line one\nline two\n".to_string()));
 }
 
 #[test]
@@ -55,10 +53,10 @@ fn test_yw_yanks_word() {
     let cur = Position { line: 0, col: 0 };
 
     // yw yanks word
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
 
-    assert_eq!(clipboard.get(), Some("hello ".to_string()));
+    assert_eq!(unnamed(&eng), Some("hello ".to_string()));
 }
 
 #[test]
@@ -69,10 +67,10 @@ fn test_y_dollar_yanks_to_line_end() {
     let cur = Position { line: 0, col: 6 }; // at 'w'
 
     // y$ yanks to end of line
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('$'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('$')));
 
-    assert_eq!(clipboard.get(), Some("world".to_string()));
+    assert_eq!(unnamed(&eng), Some("world".to_string()));
 }
 
 #[test]
@@ -83,20 +81,20 @@ fn test_visual_yank_charwise() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter visual mode and select "hello"
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('v'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('l')); // at 'o'
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('v')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('l'))); // at 'o'
 
     // Yank selection
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
     assert!(
         cmds.iter()
             .any(|c| matches!(c, Command::SetSelection(None)))
     );
 
-    assert_eq!(clipboard.get(), Some("hello".to_string()));
+    assert_eq!(unnamed(&eng), Some("hello".to_string()));
 }
 
 #[test]
@@ -107,17 +105,17 @@ fn test_visual_yank_linewise() {
     let cur = Position { line: 0, col: 0 };
 
     // Enter linewise visual mode and select two lines
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('V'));
-    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key('j'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('V')));
+    let (cur, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('j')));
 
     // Yank selection
-    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
     assert!(
         cmds.iter()
             .any(|c| matches!(c, Command::SetSelection(None)))
     );
 
-    assert_eq!(clipboard.get(), Some("line one\nline two\n".to_string()));
+    assert_eq!(unnamed(&eng), Some("line one\nline two\n".to_string()));
 }
 
 #[test]
@@ -130,7 +128,10 @@ fn test_p_pastes_after_cursor() {
     clipboard.set("TEST".to_string());
 
     let cur = Position { line: 0, col: 5 }; // at space
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('p'));
+    // "+p pastes from the host clipboard
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('"')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('+')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('p')));
 
     // Should paste after cursor
     assert_eq!(new_cur, Position { line: 0, col: 6 });
@@ -147,11 +148,11 @@ fn test_p_pastes_linewise_on_next_line() {
     let cur = Position { line: 0, col: 0 };
 
     // First yank a line with yy
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
 
     // Now paste with p
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('p'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('p')));
 
     // Should paste on next line
     assert_eq!(new_cur, Position { line: 1, col: 0 });
@@ -171,9 +172,11 @@ fn test_count_p_pastes_multiple_times() {
 
     let cur = Position { line: 0, col: 5 };
 
-    // 3p should paste 3 times
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('3'));
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('p'));
+    // "+3p should paste 3 times
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('"')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('+')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('3')));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('p')));
 
     assert_eq!(new_cur, Position { line: 0, col: 6 });
     assert_eq!(cmds.len(), 3);
@@ -195,7 +198,7 @@ fn test_p_with_empty_clipboard_does_nothing() {
     let cur = Position { line: 0, col: 5 };
 
     // Try to paste with empty clipboard
-    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key('p'));
+    let (new_cur, cmds) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('p')));
 
     // Should do nothing
     assert_eq!(new_cur, cur);
@@ -210,11 +213,11 @@ fn test_yank_with_find_motion() {
     let cur = Position { line: 0, col: 0 };
 
     // yfw yanks up to and including 'w'
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('f'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('f')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
 
-    assert_eq!(clipboard.get(), Some("hello w".to_string()));
+    assert_eq!(unnamed(&eng), Some("hello w".to_string()));
 }
 
 #[test]
@@ -225,9 +228,9 @@ fn test_yank_with_till_motion() {
     let cur = Position { line: 0, col: 0 };
 
     // ytw yanks up to but not including 'w'
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('y'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('t'));
-    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key('w'));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('y')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('t')));
+    let (_, _) = eng.handle_event(&buf, &mut clipboard, cur, key(KeyCode::Char('w')));
 
-    assert_eq!(clipboard.get(), Some("hello ".to_string()));
+    assert_eq!(unnamed(&eng), Some("hello ".to_string()));
 }